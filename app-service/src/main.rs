@@ -10,7 +10,7 @@ use axum::{
     Extension, Json, Router,
 };
 use axum_extra::extract::CookieJar;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tower_http::services::ServeDir;
 
 fn get_auth_address(prefix: &str, path: &str, ipc: bool) -> String {
@@ -153,18 +153,32 @@ async fn protected(jar: CookieJar, Extension(prefix): Extension<String>) -> impl
         return StatusCode::INTERNAL_SERVER_ERROR.into_response();
     };
 
-    match response.status() {
-        reqwest::StatusCode::OK => Json(ProtectedRouteResponse {
-            img_url: "https://i.ibb.co/YP90j68/Light-Live-Bootcamp-Certificate.png".to_owned(),
-        })
-        .into_response(),
-        _ => response.status().into_response(),
+    if response.status() != reqwest::StatusCode::OK {
+        return response.status().into_response();
     }
+
+    // The auth service tells us the user's roles, so we can authorize on our own
+    let Ok(verified_user) = response.json::<VerifiedUser>().await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    Json(ProtectedRouteResponse {
+        img_url: "https://i.ibb.co/YP90j68/Light-Live-Bootcamp-Certificate.png".to_owned(),
+        roles: verified_user.roles,
+    })
+    .into_response()
+}
+
+#[derive(Deserialize)]
+struct VerifiedUser {
+    #[serde(default)]
+    roles: Vec<String>,
 }
 
 #[derive(Serialize)]
 pub struct ProtectedRouteResponse {
     pub img_url: String,
+    pub roles: Vec<String>,
 }
 
 // Simple health check endpoint for container health monitoring
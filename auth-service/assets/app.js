@@ -1,13 +1,20 @@
 const loginSection = document.getElementById("login-section");
 const twoFASection = document.getElementById("2fa-section");
 const signupSection = document.getElementById("signup-section");
+const passwordResetSection = document.getElementById("password-reset-section");
+const passwordResetConfirmSection = document.getElementById("password-reset-confirm-section");
 
 const signupLink = document.getElementById("signup-link");
 const twoFALoginLink = document.getElementById("2fa-login-link");
 const signupLoginLink = document.getElementById("signup-login-link");
+const passwordResetLink = document.getElementById("password-reset-link");
+const passwordResetLoginLink = document.getElementById("password-reset-login-link");
 
-// Get the current prefix from the path or use an empty string
-const currentPrefix = window.location.pathname.includes("/auth") ? "/auth" : "";
+// The prefix the service is mounted under, e.g. "/auth" behind the proxy
+const currentPrefix = document.body.dataset.prefix;
+
+// On the OAuth authorization page, logging in continues with the authorization request
+const isAuthorizing = window.location.pathname.endsWith("/authorize");
 
 signupLink.addEventListener("click", (e) => {
   e.preventDefault();
@@ -33,6 +40,20 @@ signupLoginLink.addEventListener("click", (e) => {
   signupSection.style.display = "none";
 });
 
+passwordResetLink.addEventListener("click", (e) => {
+  e.preventDefault();
+
+  loginSection.style.display = "none";
+  passwordResetSection.style.display = "block";
+});
+
+passwordResetLoginLink.addEventListener("click", (e) => {
+  e.preventDefault();
+
+  loginSection.style.display = "block";
+  passwordResetSection.style.display = "none";
+});
+
 // -----------------------------------------------------
 
 const loginForm = document.getElementById("login-form");
@@ -56,6 +77,10 @@ loginButton.addEventListener("click", (e) => {
       TwoFAForm.email.value = email;
       response.json().then((data) => {
         TwoFAForm.login_attempt_id.value = data.loginAttemptId;
+        document.getElementById("2fa-hint").innerText =
+          data.twoFAMethod === "totp"
+            ? "Enter the code from your authenticator app."
+            : "Enter the code we sent to your email.";
       });
 
       loginForm.email.value = "";
@@ -69,6 +94,10 @@ loginButton.addEventListener("click", (e) => {
       loginForm.email.value = "";
       loginForm.password.value = "";
       loginErrAlter.style.display = "none";
+      if (isAuthorizing) {
+        window.location.reload();
+        return;
+      }
       alert("You have successfully logged in.");
     } else {
       response.json().then((data) => {
@@ -107,7 +136,15 @@ signupButton.addEventListener("click", (e) => {
       signupForm.password.value = "";
       signupForm.twoFA.checked = false;
       signupErrAlter.style.display = "none";
-      alert("You have successfully created a user.");
+      response.json().then((data) => {
+        if (data.recoveryCodes) {
+          alert(
+            "Store these recovery codes somewhere safe. Each one can be used once in place of a 2FA code:\n\n" +
+              data.recoveryCodes.join("\n")
+          );
+        }
+      });
+      alert("You have successfully created a user. Check your email to verify your address.");
       loginSection.style.display = "block";
       twoFASection.style.display = "none";
       signupSection.style.display = "none";
@@ -148,6 +185,10 @@ TwoFAButton.addEventListener("click", (e) => {
       TwoFAForm.email_code.value = "";
       TwoFAForm.login_attempt_id.value = "";
       TwoFAErrAlter.style.display = "none";
+      if (isAuthorizing) {
+        window.location.reload();
+        return;
+      }
       alert("You have successfully logged in.");
       loginSection.style.display = "block";
       twoFASection.style.display = "none";
@@ -165,3 +206,124 @@ TwoFAButton.addEventListener("click", (e) => {
     }
   });
 });
+
+const passwordResetForm = document.getElementById("password-reset-form");
+const passwordResetButton = document.getElementById("password-reset-form-submit");
+const passwordResetErrAlter = document.getElementById("password-reset-err-alert");
+
+passwordResetButton.addEventListener("click", (e) => {
+  e.preventDefault();
+
+  const email = passwordResetForm.email.value;
+
+  fetch(currentPrefix + "/password-reset/request", {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({ email }),
+  }).then((response) => {
+    if (response.ok) {
+      passwordResetForm.email.value = "";
+      passwordResetErrAlter.style.display = "none";
+      alert("If the account exists, a password reset link has been sent.");
+      loginSection.style.display = "block";
+      passwordResetSection.style.display = "none";
+    } else {
+      response.json().then((data) => {
+        let error_msg = data.error;
+        if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
+          passwordResetErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+          passwordResetErrAlter.style.display = "block";
+        } else {
+          passwordResetErrAlter.style.display = "none";
+        }
+      });
+    }
+  });
+});
+
+const passwordResetConfirmForm = document.getElementById("password-reset-confirm-form");
+const passwordResetConfirmButton = document.getElementById("password-reset-confirm-form-submit");
+const passwordResetConfirmErrAlter = document.getElementById("password-reset-confirm-err-alert");
+
+// Links from password reset emails carry the token in the query string
+const resetToken = new URLSearchParams(window.location.search).get("reset_token");
+if (resetToken) {
+  passwordResetConfirmForm.token.value = resetToken;
+  loginSection.style.display = "none";
+  passwordResetConfirmSection.style.display = "block";
+}
+
+passwordResetConfirmButton.addEventListener("click", (e) => {
+  e.preventDefault();
+
+  const token = passwordResetConfirmForm.token.value;
+  const newPassword = passwordResetConfirmForm.password.value;
+
+  fetch(currentPrefix + "/password-reset/confirm", {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({ token, newPassword }),
+  }).then((response) => {
+    if (response.ok) {
+      passwordResetConfirmForm.token.value = "";
+      passwordResetConfirmForm.password.value = "";
+      passwordResetConfirmErrAlter.style.display = "none";
+      alert("Your password has been reset. Please log in.");
+      window.history.replaceState({}, "", window.location.pathname);
+      loginSection.style.display = "block";
+      passwordResetConfirmSection.style.display = "none";
+    } else {
+      response.json().then((data) => {
+        let error_msg = data.error;
+        if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
+          passwordResetConfirmErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+          passwordResetConfirmErrAlter.style.display = "block";
+        } else {
+          passwordResetConfirmErrAlter.style.display = "none";
+        }
+      });
+    }
+  });
+});
+
+// Links from verification emails carry the token in the query string
+const verifyToken = new URLSearchParams(window.location.search).get("verify_token");
+if (verifyToken) {
+  fetch(currentPrefix + "/verify-email", {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({ token: verifyToken }),
+  }).then((response) => {
+    window.history.replaceState({}, "", window.location.pathname);
+    if (response.ok) {
+      alert("Your email address has been verified. Please log in.");
+    } else {
+      alert("The verification link is not valid or has expired.");
+    }
+  });
+}
+
+// Links from email change confirmations carry the token in the query string
+const emailChangeToken = new URLSearchParams(window.location.search).get("email_change_token");
+if (emailChangeToken) {
+  fetch(currentPrefix + "/account/email/confirm", {
+    method: "POST",
+    headers: {
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({ token: emailChangeToken }),
+  }).then((response) => {
+    window.history.replaceState({}, "", window.location.pathname);
+    if (response.ok) {
+      alert("Your email address has been changed. Please log in with the new address.");
+    } else {
+      alert("The confirmation link is not valid or has expired.");
+    }
+  });
+}
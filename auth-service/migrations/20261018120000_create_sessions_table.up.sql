@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS sessions(
+   id TEXT PRIMARY KEY,
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   created_at BIGINT NOT NULL,
+   last_seen_at BIGINT NOT NULL,
+   user_agent TEXT,
+   ip TEXT,
+   revoked BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS sessions_email_idx ON sessions(email);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS oauth_clients(
+   client_id TEXT PRIMARY KEY,
+   name TEXT NOT NULL,
+   redirect_uris TEXT[] NOT NULL,
+   client_secret_hash TEXT
+);
+
+CREATE TABLE IF NOT EXISTS oauth_consents(
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   client_id TEXT NOT NULL REFERENCES oauth_clients(client_id) ON DELETE CASCADE,
+   scope TEXT[] NOT NULL,
+   PRIMARY KEY (email, client_id)
+);
@@ -0,0 +1,2 @@
+-- How the user authenticated when the session started (RFC 8176), unknown for older sessions
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS amr TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS roles(
+   name TEXT PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions(
+   role TEXT NOT NULL REFERENCES roles(name) ON DELETE CASCADE,
+   permission TEXT NOT NULL,
+   PRIMARY KEY (role, permission)
+);
+
+CREATE TABLE IF NOT EXISTS user_roles(
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE,
+   role TEXT NOT NULL REFERENCES roles(name) ON DELETE CASCADE,
+   PRIMARY KEY (email, role)
+);
+
+-- The admin API is guarded by this role
+INSERT INTO roles (name) VALUES ('admin') ON CONFLICT DO NOTHING;
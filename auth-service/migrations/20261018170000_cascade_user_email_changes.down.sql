@@ -0,0 +1,14 @@
+ALTER TABLE recovery_codes
+   DROP CONSTRAINT IF EXISTS recovery_codes_email_fkey,
+   ADD CONSTRAINT recovery_codes_email_fkey
+      FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE;
+
+ALTER TABLE sessions
+   DROP CONSTRAINT IF EXISTS sessions_email_fkey,
+   ADD CONSTRAINT sessions_email_fkey
+      FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE;
+
+ALTER TABLE oauth_consents
+   DROP CONSTRAINT IF EXISTS oauth_consents_email_fkey,
+   ADD CONSTRAINT oauth_consents_email_fkey
+      FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE;
@@ -0,0 +1,18 @@
+ALTER TABLE recovery_codes DROP CONSTRAINT IF EXISTS recovery_codes_email_fkey;
+ALTER TABLE sessions DROP CONSTRAINT IF EXISTS sessions_email_fkey;
+ALTER TABLE oauth_consents DROP CONSTRAINT IF EXISTS oauth_consents_email_fkey;
+ALTER TABLE user_roles DROP CONSTRAINT IF EXISTS user_roles_email_fkey;
+
+ALTER TABLE users DROP CONSTRAINT users_pkey;
+ALTER TABLE users ADD PRIMARY KEY (email);
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+ALTER TABLE users DROP COLUMN IF EXISTS id;
+
+ALTER TABLE recovery_codes ADD CONSTRAINT recovery_codes_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+ALTER TABLE sessions ADD CONSTRAINT sessions_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+ALTER TABLE oauth_consents ADD CONSTRAINT oauth_consents_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+ALTER TABLE user_roles ADD CONSTRAINT user_roles_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
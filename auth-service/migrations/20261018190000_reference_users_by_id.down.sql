@@ -0,0 +1,31 @@
+ALTER TABLE recovery_codes ADD COLUMN email TEXT;
+UPDATE recovery_codes SET email = users.email FROM users WHERE users.id = recovery_codes.user_id;
+ALTER TABLE recovery_codes ALTER COLUMN email SET NOT NULL;
+ALTER TABLE recovery_codes ADD CONSTRAINT recovery_codes_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+ALTER TABLE recovery_codes DROP COLUMN user_id;
+CREATE INDEX IF NOT EXISTS recovery_codes_email_idx ON recovery_codes(email);
+
+ALTER TABLE sessions ADD COLUMN email TEXT;
+UPDATE sessions SET email = users.email FROM users WHERE users.id = sessions.user_id;
+ALTER TABLE sessions ALTER COLUMN email SET NOT NULL;
+ALTER TABLE sessions ADD CONSTRAINT sessions_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+ALTER TABLE sessions DROP COLUMN user_id;
+CREATE INDEX IF NOT EXISTS sessions_email_idx ON sessions(email);
+
+ALTER TABLE oauth_consents ADD COLUMN email TEXT;
+UPDATE oauth_consents SET email = users.email FROM users WHERE users.id = oauth_consents.user_id;
+ALTER TABLE oauth_consents ALTER COLUMN email SET NOT NULL;
+ALTER TABLE oauth_consents ADD CONSTRAINT oauth_consents_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+ALTER TABLE oauth_consents DROP COLUMN user_id;
+ALTER TABLE oauth_consents ADD PRIMARY KEY (email, client_id);
+
+ALTER TABLE user_roles ADD COLUMN email TEXT;
+UPDATE user_roles SET email = users.email FROM users WHERE users.id = user_roles.user_id;
+ALTER TABLE user_roles ALTER COLUMN email SET NOT NULL;
+ALTER TABLE user_roles ADD CONSTRAINT user_roles_email_fkey
+   FOREIGN KEY (email) REFERENCES users(email) ON DELETE CASCADE ON UPDATE CASCADE;
+ALTER TABLE user_roles DROP COLUMN user_id;
+ALTER TABLE user_roles ADD PRIMARY KEY (email, role);
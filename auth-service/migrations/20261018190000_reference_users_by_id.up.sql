@@ -0,0 +1,32 @@
+-- Tables that belong to a user reference its id, so they follow the user when the email changes
+ALTER TABLE recovery_codes ADD COLUMN user_id UUID;
+UPDATE recovery_codes SET user_id = users.id FROM users WHERE users.email = recovery_codes.email;
+ALTER TABLE recovery_codes ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE recovery_codes ADD CONSTRAINT recovery_codes_user_id_fkey
+   FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE recovery_codes DROP COLUMN email;
+CREATE INDEX IF NOT EXISTS recovery_codes_user_id_idx ON recovery_codes(user_id);
+
+ALTER TABLE sessions ADD COLUMN user_id UUID;
+UPDATE sessions SET user_id = users.id FROM users WHERE users.email = sessions.email;
+ALTER TABLE sessions ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE sessions ADD CONSTRAINT sessions_user_id_fkey
+   FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE sessions DROP COLUMN email;
+CREATE INDEX IF NOT EXISTS sessions_user_id_idx ON sessions(user_id);
+
+ALTER TABLE oauth_consents ADD COLUMN user_id UUID;
+UPDATE oauth_consents SET user_id = users.id FROM users WHERE users.email = oauth_consents.email;
+ALTER TABLE oauth_consents ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE oauth_consents ADD CONSTRAINT oauth_consents_user_id_fkey
+   FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE oauth_consents DROP COLUMN email;
+ALTER TABLE oauth_consents ADD PRIMARY KEY (user_id, client_id);
+
+ALTER TABLE user_roles ADD COLUMN user_id UUID;
+UPDATE user_roles SET user_id = users.id FROM users WHERE users.email = user_roles.email;
+ALTER TABLE user_roles ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE user_roles ADD CONSTRAINT user_roles_user_id_fkey
+   FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE user_roles DROP COLUMN email;
+ALTER TABLE user_roles ADD PRIMARY KEY (user_id, role);
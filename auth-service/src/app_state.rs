@@ -1,34 +1,93 @@
+use secrecy::Secret;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore};
+use crate::domain::{
+    AuthorizationCodeStore, BannedTokenStore, EmailClient, EmailVerificationTokenStore,
+    LoginAttemptStore, LoginThrottleConfig, OAuthClientStore, PasswordResetTokenStore,
+    RateLimitConfig, RateLimitStore, RefreshTokenStore, SessionStore, TwoFACodeStore,
+    UnverifiedLoginPolicy, UserStore,
+};
+use crate::utils::JwtKeyRing;
 
 // Using a type alias to improve readability!
 pub type UserStoreType = Arc<RwLock<dyn UserStore + Send + Sync>>;
 pub type BannedTokenStoreType = Arc<RwLock<dyn BannedTokenStore + Send + Sync>>;
+pub type RefreshTokenStoreType = Arc<RwLock<dyn RefreshTokenStore + Send + Sync>>;
+pub type SessionStoreType = Arc<RwLock<dyn SessionStore + Send + Sync>>;
+pub type PasswordResetTokenStoreType = Arc<RwLock<dyn PasswordResetTokenStore + Send + Sync>>;
+pub type EmailVerificationTokenStoreType =
+    Arc<RwLock<dyn EmailVerificationTokenStore + Send + Sync>>;
+pub type OAuthClientStoreType = Arc<RwLock<dyn OAuthClientStore + Send + Sync>>;
+pub type AuthorizationCodeStoreType = Arc<RwLock<dyn AuthorizationCodeStore + Send + Sync>>;
+pub type LoginAttemptStoreType = Arc<RwLock<dyn LoginAttemptStore + Send + Sync>>;
+pub type RateLimitStoreType = Arc<RwLock<dyn RateLimitStore + Send + Sync>>;
 pub type TwoFACodeStoreType = Arc<RwLock<dyn TwoFACodeStore + Send + Sync>>;
 pub type EmailClientType = Arc<RwLock<dyn EmailClient + Send + Sync>>;
+pub type JwtKeyRingType = Arc<RwLock<JwtKeyRing>>;
 
 #[derive(Clone)]
 pub struct AppState {
     pub user_store: UserStoreType,
     pub banned_token_store: BannedTokenStoreType,
+    pub refresh_token_store: RefreshTokenStoreType,
+    pub session_store: SessionStoreType,
     pub two_fa_code_store: TwoFACodeStoreType,
+    pub password_reset_token_store: PasswordResetTokenStoreType,
+    pub email_verification_token_store: EmailVerificationTokenStoreType,
+    pub oauth_client_store: OAuthClientStoreType,
+    pub authorization_code_store: AuthorizationCodeStoreType,
+    pub login_attempt_store: LoginAttemptStoreType,
+    pub rate_limit_store: RateLimitStoreType,
     pub email_client: EmailClientType,
+    pub jwt_key_ring: JwtKeyRingType,
+    pub unverified_login_policy: UnverifiedLoginPolicy,
+    pub login_throttle: LoginThrottleConfig,
+    // Shared, as the state is cloned for every request
+    pub rate_limits: Arc<RateLimitConfig>,
+    // Bearer token for admin operations, which are disabled without one
+    pub admin_token: Option<Secret<String>>,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         user_store: UserStoreType,
         banned_token_store: BannedTokenStoreType,
+        refresh_token_store: RefreshTokenStoreType,
+        session_store: SessionStoreType,
         two_fa_code_store: TwoFACodeStoreType,
+        password_reset_token_store: PasswordResetTokenStoreType,
+        email_verification_token_store: EmailVerificationTokenStoreType,
+        oauth_client_store: OAuthClientStoreType,
+        authorization_code_store: AuthorizationCodeStoreType,
+        login_attempt_store: LoginAttemptStoreType,
+        rate_limit_store: RateLimitStoreType,
         email_client: EmailClientType,
+        jwt_key_ring: JwtKeyRingType,
+        unverified_login_policy: UnverifiedLoginPolicy,
+        login_throttle: LoginThrottleConfig,
+        rate_limits: Arc<RateLimitConfig>,
+        admin_token: Option<Secret<String>>,
     ) -> Self {
         Self {
             user_store,
             banned_token_store,
+            refresh_token_store,
+            session_store,
             two_fa_code_store,
+            password_reset_token_store,
+            email_verification_token_store,
+            oauth_client_store,
+            authorization_code_store,
+            login_attempt_store,
+            rate_limit_store,
             email_client,
+            jwt_key_ring,
+            unverified_login_policy,
+            login_throttle,
+            rate_limits,
+            admin_token,
         }
     }
 }
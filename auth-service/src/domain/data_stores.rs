@@ -1,20 +1,109 @@
 use color_eyre::eyre::Report;
 use thiserror::Error;
+use uuid::Uuid;
 
-use super::{Email, LoginAttemptId, Password, TwoFACode, User};
+use super::{
+    AuthorizationCode, AuthorizationGrant, Email, EmailVerificationToken, LoginAttemptId,
+    LoginThrottleKey, OAuthClient, OAuthConsent, Password, PasswordResetToken, Permission,
+    RateLimit, RateLimitDecision, RecoveryCode, RefreshToken, RefreshTokenRecord, Role, Session,
+    TotpSecret, TwoFACode, User, UserRoles,
+};
 
 #[async_trait::async_trait]
 pub trait UserStore {
     async fn add_user(&mut self, user: User) -> Result<(), UserStoreError>;
     async fn get_user(&self, email: &Email) -> Result<User, UserStoreError>;
+    async fn get_user_by_id(&self, id: &Uuid) -> Result<User, UserStoreError>;
     async fn validate_user(&self, email: &Email, password: &Password)
         -> Result<(), UserStoreError>;
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError>;
+    async fn mark_verified(&mut self, email: &Email) -> Result<(), UserStoreError>;
+    // Moves the user with everything they own to a new address, which starts out unverified
+    async fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> Result<(), UserStoreError>;
+    // A new TOTP secret stays pending until the user confirms it with a valid code
+    async fn set_pending_totp_secret(
+        &mut self,
+        email: &Email,
+        secret: TotpSecret,
+    ) -> Result<(), UserStoreError>;
+    async fn get_pending_totp_secret(&self, email: &Email) -> Result<TotpSecret, UserStoreError>;
+    // Activates the pending secret and switches the user to TOTP 2FA
+    async fn confirm_totp_secret(&mut self, email: &Email) -> Result<(), UserStoreError>;
+    async fn get_totp_secret(&self, email: &Email) -> Result<TotpSecret, UserStoreError>;
+    // Replaces the user's recovery codes, invalidating the previous batch
+    async fn set_recovery_codes(
+        &mut self,
+        email: &Email,
+        codes: Vec<RecoveryCode>,
+    ) -> Result<(), UserStoreError>;
+    // Removes the matching code so it cannot be used again
+    async fn use_recovery_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<(), UserStoreError>;
+    // Creates the role or replaces its permissions
+    async fn set_role_permissions(
+        &mut self,
+        role: &Role,
+        permissions: Vec<Permission>,
+    ) -> Result<(), UserStoreError>;
+    // Replaces the user's roles. Every role must exist already.
+    async fn set_user_roles(
+        &mut self,
+        email: &Email,
+        roles: Vec<Role>,
+    ) -> Result<(), UserStoreError>;
+    async fn get_user_roles(&self, email: &Email) -> Result<UserRoles, UserStoreError>;
+    // Returns up to `limit` users ordered by email, starting after the `cursor` email.
+    // `search` keeps only users whose email contains it, ignoring case.
+    async fn list_users(
+        &self,
+        cursor: Option<&Email>,
+        limit: usize,
+        search: Option<&str>,
+    ) -> Result<Vec<User>, UserStoreError>;
+    async fn count_users(&self, search: Option<&str>) -> Result<u64, UserStoreError>;
+    // Disabled users cannot log in until they are enabled again
+    async fn set_disabled(&mut self, email: &Email, disabled: bool) -> Result<(), UserStoreError>;
+    async fn set_requires_2fa(
+        &mut self,
+        email: &Email,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError>;
+    // Removes the user along with their TOTP secrets, recovery codes and roles
+    async fn delete_user(&mut self, email: &Email) -> Result<(), UserStoreError>;
 }
 
 #[async_trait::async_trait]
 pub trait BannedTokenStore {
-    async fn add_banned_token(&mut self, token: String) -> Result<(), BannedTokenStoreError>;
-    async fn check_banned_token(&self, token: &str) -> Result<bool, BannedTokenStoreError>;
+    // Revokes a single token by its `jti`. The entry is only kept until the token's own
+    // `expires_at` unix timestamp, after which the token is rejected anyway.
+    async fn add_banned_token(
+        &mut self,
+        jti: &str,
+        expires_at: i64,
+    ) -> Result<(), BannedTokenStoreError>;
+    async fn check_banned_token(&self, jti: &str) -> Result<bool, BannedTokenStoreError>;
+    // Invalidates every token of the user that was issued before the given unix timestamp
+    async fn ban_user_tokens(
+        &mut self,
+        user_id: &Uuid,
+        issued_before: i64,
+    ) -> Result<(), BannedTokenStoreError>;
+    async fn check_user_tokens_banned(
+        &self,
+        user_id: &Uuid,
+        issued_at: i64,
+    ) -> Result<bool, BannedTokenStoreError>;
 }
 #[derive(Debug, Error)]
 pub enum BannedTokenStoreError {
@@ -24,6 +113,154 @@ pub enum BannedTokenStoreError {
     UnexpectedError(#[source] Report),
 }
 
+#[async_trait::async_trait]
+pub trait RefreshTokenStore {
+    async fn add_refresh_token(
+        &mut self,
+        token: RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError>;
+    async fn get_refresh_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError>;
+    async fn mark_refresh_token_used(
+        &mut self,
+        token: &RefreshToken,
+    ) -> Result<(), RefreshTokenStoreError>;
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError>;
+    async fn is_family_revoked(&self, family_id: &str) -> Result<bool, RefreshTokenStoreError>;
+    // Removes every refresh token issued to the user, used ones included
+    async fn delete_user_tokens(&mut self, user_id: &Uuid) -> Result<(), RefreshTokenStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum RefreshTokenStoreError {
+    #[error("Refresh token not found")]
+    TokenNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for RefreshTokenStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::TokenNotFound, Self::TokenNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+// Devices the users are logged in from
+#[async_trait::async_trait]
+pub trait SessionStore {
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError>;
+    async fn get_session(&self, id: &str) -> Result<Session, SessionStoreError>;
+    // Returns the user's sessions that are neither revoked nor expired, most recently seen first
+    async fn list_sessions(&self, user_id: &Uuid) -> Result<Vec<Session>, SessionStoreError>;
+    async fn touch_session(&mut self, id: &str, last_seen_at: i64)
+        -> Result<(), SessionStoreError>;
+    async fn revoke_session(&mut self, id: &str) -> Result<(), SessionStoreError>;
+    async fn revoke_user_sessions(&mut self, user_id: &Uuid) -> Result<(), SessionStoreError>;
+    // Removes every session of the user, revoked and expired ones included
+    async fn delete_user_sessions(&mut self, user_id: &Uuid) -> Result<(), SessionStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum SessionStoreError {
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for SessionStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::SessionNotFound, Self::SessionNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait::async_trait]
+pub trait PasswordResetTokenStore {
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+        email: Email,
+    ) -> Result<(), PasswordResetTokenStoreError>;
+    // Returns the email the token was issued for and removes the token, so it can only be used once
+    async fn consume_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> Result<Email, PasswordResetTokenStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum PasswordResetTokenStoreError {
+    #[error("Password reset token not found")]
+    TokenNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for PasswordResetTokenStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::TokenNotFound, Self::TokenNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait::async_trait]
+pub trait EmailVerificationTokenStore {
+    async fn add_token(
+        &mut self,
+        token: EmailVerificationToken,
+        email: Email,
+    ) -> Result<(), EmailVerificationTokenStoreError>;
+    // Returns the email the token was issued for and removes the token, so it can only be used once
+    async fn consume_token(
+        &mut self,
+        token: &EmailVerificationToken,
+    ) -> Result<Email, EmailVerificationTokenStoreError>;
+    // Remembers a change of address until the link sent to the new address is used
+    async fn add_email_change_token(
+        &mut self,
+        token: EmailVerificationToken,
+        user_id: Uuid,
+        new_email: Email,
+    ) -> Result<(), EmailVerificationTokenStoreError>;
+    // Returns the user and the address they asked to move to and removes the token
+    async fn consume_email_change_token(
+        &mut self,
+        token: &EmailVerificationToken,
+    ) -> Result<(Uuid, Email), EmailVerificationTokenStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum EmailVerificationTokenStoreError {
+    #[error("Email verification token not found")]
+    TokenNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for EmailVerificationTokenStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::TokenNotFound, Self::TokenNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum UserStoreError {
     #[error("User already exists")]
@@ -32,6 +269,10 @@ pub enum UserStoreError {
     UserNotFound,
     #[error("Invalid credentials")]
     InvalidCredentials,
+    #[error("TOTP secret not found")]
+    TotpSecretNotFound,
+    #[error("Role not found")]
+    RoleNotFound,
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
@@ -43,6 +284,8 @@ impl PartialEq for UserStoreError {
             (Self::UserAlreadyExists, Self::UserAlreadyExists)
                 | (Self::UserNotFound, Self::UserNotFound)
                 | (Self::InvalidCredentials, Self::InvalidCredentials)
+                | (Self::TotpSecretNotFound, Self::TotpSecretNotFound)
+                | (Self::RoleNotFound, Self::RoleNotFound)
                 | (Self::UnexpectedError(_), Self::UnexpectedError(_))
         )
     }
@@ -62,6 +305,8 @@ pub trait TwoFACodeStore {
         &self,
         email: &Email,
     ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
+    // Counts a wrong code for the pending login attempt and returns the failures so far
+    async fn record_failed_attempt(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError>;
 }
 
 #[derive(Debug, Error)]
@@ -81,3 +326,125 @@ impl PartialEq for TwoFACodeStoreError {
         )
     }
 }
+
+// Registered OAuth clients and the scopes users consented to give them
+#[async_trait::async_trait]
+pub trait OAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError>;
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError>;
+    // Adds the scopes to the ones the user already granted the client
+    async fn grant_consent(
+        &mut self,
+        user_id: &Uuid,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthClientStoreError>;
+    // Whether the user granted the client all of the scopes
+    async fn has_consent(
+        &self,
+        user_id: &Uuid,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<bool, OAuthClientStoreError>;
+    // The consents of the user, ordered by client id
+    async fn list_consents(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<OAuthConsent>, OAuthClientStoreError>;
+    async fn delete_consents(&mut self, user_id: &Uuid) -> Result<(), OAuthClientStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum OAuthClientStoreError {
+    #[error("OAuth client not found")]
+    ClientNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for OAuthClientStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::ClientNotFound, Self::ClientNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[async_trait::async_trait]
+pub trait AuthorizationCodeStore {
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationGrant,
+    ) -> Result<(), AuthorizationCodeStoreError>;
+    // Returns the grant behind the code and removes the code, so it can only be exchanged once
+    async fn consume_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationGrant, AuthorizationCodeStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum AuthorizationCodeStoreError {
+    #[error("Authorization code not found")]
+    CodeNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for AuthorizationCodeStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::CodeNotFound, Self::CodeNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+// Keeps track of failed password logins per email and per client IP
+#[async_trait::async_trait]
+pub trait LoginAttemptStore {
+    // Counts a failure and returns the failures so far, which are forgotten after `window_seconds`
+    async fn record_failure(
+        &mut self,
+        key: &LoginThrottleKey,
+        window_seconds: u64,
+    ) -> Result<u32, LoginAttemptStoreError>;
+    async fn lock_out(
+        &mut self,
+        key: &LoginThrottleKey,
+        seconds: u64,
+    ) -> Result<(), LoginAttemptStoreError>;
+    // Returns the seconds left until the key may try again, if it is locked out
+    async fn get_lockout(
+        &self,
+        key: &LoginThrottleKey,
+    ) -> Result<Option<u64>, LoginAttemptStoreError>;
+    async fn clear(&mut self, key: &LoginThrottleKey) -> Result<(), LoginAttemptStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum LoginAttemptStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+// Token buckets used by the rate limiting middleware
+#[async_trait::async_trait]
+pub trait RateLimitStore {
+    // Takes a token from the bucket behind `key`, starting with a full bucket if there is none
+    async fn take_token(
+        &mut self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<RateLimitDecision, RateLimitStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum RateLimitStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
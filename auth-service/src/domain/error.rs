@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -7,6 +7,8 @@ use color_eyre::eyre::Report;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use super::OAuthError;
+
 #[derive(Debug, Error)]
 pub enum AuthAPIError {
     #[error("User already exists")]
@@ -21,6 +23,35 @@ pub enum AuthAPIError {
     InvalidToken,
     #[error("Verification failed")]
     VerificationFailed,
+    #[error("Email not verified")]
+    EmailNotVerified,
+    #[error("Account disabled")]
+    UserDisabled,
+    #[error("Too many 2FA attempts")]
+    TooManyTwoFAAttempts,
+    #[error("2FA not enabled")]
+    TwoFANotEnabled,
+    #[error("Too many login attempts")]
+    TooManyLoginAttempts { retry_after_seconds: u64 },
+    #[error("Rate limited")]
+    RateLimited { retry_after_seconds: u64 },
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Unknown audience")]
+    UnknownAudience,
+    #[error("Missing role")]
+    MissingRole,
+    #[error("Invalid role")]
+    InvalidRole,
+    #[error("Key rotation unavailable")]
+    KeyRotationUnavailable,
+    #[error("Invalid OAuth client")]
+    InvalidOAuthClient,
+    // Reported with the error codes of RFC 6749, which OAuth clients expect
+    #[error("OAuth error: {}", .0.as_str())]
+    OAuth(OAuthError),
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
@@ -32,6 +63,17 @@ pub struct ErrorResponse {
 
 impl IntoResponse for AuthAPIError {
     fn into_response(self) -> Response {
+        // Tell throttled clients when they may try again
+        let retry_after = match &self {
+            AuthAPIError::TooManyLoginAttempts {
+                retry_after_seconds,
+            }
+            | AuthAPIError::RateLimited {
+                retry_after_seconds,
+            } => Some(retry_after_seconds.to_string()),
+            _ => None,
+        };
+
         let (status, error_message) = match self {
             AuthAPIError::UserAlreadyExists => (StatusCode::CONFLICT, "User already exists"),
             AuthAPIError::InvalidCredentials => (StatusCode::BAD_REQUEST, "Invalid credentials"),
@@ -40,6 +82,31 @@ impl IntoResponse for AuthAPIError {
             AuthAPIError::VerificationFailed => {
                 (StatusCode::PAYMENT_REQUIRED, "Verification failed")
             }
+            AuthAPIError::EmailNotVerified => (StatusCode::FORBIDDEN, "Email not verified"),
+            AuthAPIError::UserDisabled => (StatusCode::FORBIDDEN, "Account disabled"),
+            AuthAPIError::TooManyTwoFAAttempts => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many 2FA attempts, please log in again",
+            ),
+            AuthAPIError::TwoFANotEnabled => (StatusCode::CONFLICT, "2FA is not enabled"),
+            AuthAPIError::TooManyLoginAttempts { .. } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many login attempts, please try again later",
+            ),
+            AuthAPIError::RateLimited { .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, "Too many requests")
+            }
+            AuthAPIError::UserNotFound => (StatusCode::NOT_FOUND, "User not found"),
+            AuthAPIError::SessionNotFound => (StatusCode::NOT_FOUND, "Session not found"),
+            AuthAPIError::UnknownAudience => (StatusCode::BAD_REQUEST, "Unknown audience"),
+            AuthAPIError::MissingRole => (StatusCode::FORBIDDEN, "Missing role"),
+            AuthAPIError::InvalidRole => (StatusCode::BAD_REQUEST, "Invalid role"),
+            AuthAPIError::KeyRotationUnavailable => (
+                StatusCode::CONFLICT,
+                "Key rotation needs an RS256 or EdDSA key directory",
+            ),
+            AuthAPIError::InvalidOAuthClient => (StatusCode::BAD_REQUEST, "Invalid OAuth client"),
+            AuthAPIError::OAuth(error) => (error.status_code(), error.as_str()),
             AuthAPIError::IncorrectCredentials => {
                 (StatusCode::UNAUTHORIZED, "Incorrect credentials")
             }
@@ -52,6 +119,9 @@ impl IntoResponse for AuthAPIError {
             error: error_message.to_string(),
         });
 
-        (status, body).into_response()
+        match retry_after {
+            Some(seconds) => (status, [(header::RETRY_AFTER, seconds)], body).into_response(),
+            None => (status, body).into_response(),
+        }
     }
 }
@@ -68,7 +68,7 @@ mod tests {
         let id = LoginAttemptId::default();
 
         // Assert
-        assert!(Uuid::parse_str(&id.0.expose_secret()).is_ok());
+        assert!(Uuid::parse_str(id.0.expose_secret()).is_ok());
     }
 
     #[test]
@@ -0,0 +1,139 @@
+use std::net::IpAddr;
+
+use secrecy::ExposeSecret;
+
+use super::Email;
+
+// What failed password logins are counted against
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoginThrottleKey {
+    Email(Email),
+    Ip(IpAddr),
+}
+
+impl LoginThrottleKey {
+    pub fn as_key(&self) -> String {
+        match self {
+            LoginThrottleKey::Email(email) => format!("email:{}", email.as_ref().expose_secret()),
+            LoginThrottleKey::Ip(ip) => format!("ip:{}", ip),
+        }
+    }
+}
+
+// Thresholds for one kind of key. Failures are forgotten once no new one
+// arrives for `lockout_seconds`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoginThrottlePolicy {
+    // Failures allowed before any delay is imposed
+    pub backoff_after: u32,
+    // Failures after which the key is locked out for `lockout_seconds`
+    pub lockout_after: u32,
+    pub base_backoff_seconds: u64,
+    pub lockout_seconds: u64,
+}
+
+impl LoginThrottlePolicy {
+    // Seconds the key has to wait after its `failures`-th failure, doubling with every failure
+    pub fn delay_for(&self, failures: u32) -> Option<u64> {
+        if failures >= self.lockout_after {
+            return Some(self.lockout_seconds);
+        }
+        if failures < self.backoff_after {
+            return None;
+        }
+
+        let exponent = (failures - self.backoff_after).min(u64::BITS - 1);
+        let delay = self.base_backoff_seconds.saturating_mul(1 << exponent);
+        Some(delay.min(self.lockout_seconds))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoginThrottleConfig {
+    pub email: LoginThrottlePolicy,
+    // Many users can share an address, so this one is usually more lenient
+    pub ip: LoginThrottlePolicy,
+}
+
+impl LoginThrottleConfig {
+    pub fn policy_for(&self, key: &LoginThrottleKey) -> &LoginThrottlePolicy {
+        match key {
+            LoginThrottleKey::Email(_) => &self.email,
+            LoginThrottleKey::Ip(_) => &self.ip,
+        }
+    }
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        Self {
+            email: LoginThrottlePolicy {
+                backoff_after: 3,
+                lockout_after: 10,
+                base_backoff_seconds: 1,
+                lockout_seconds: 900,
+            },
+            ip: LoginThrottlePolicy {
+                backoff_after: 20,
+                lockout_after: 100,
+                base_backoff_seconds: 1,
+                lockout_seconds: 900,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: LoginThrottlePolicy = LoginThrottlePolicy {
+        backoff_after: 3,
+        lockout_after: 10,
+        base_backoff_seconds: 1,
+        lockout_seconds: 900,
+    };
+
+    #[test]
+    fn test_delay_for_doubles_after_backoff_threshold() {
+        // Arrange
+        let cases = [
+            (1, None),
+            (2, None),
+            (3, Some(1)),
+            (4, Some(2)),
+            (5, Some(4)),
+            (9, Some(64)),
+        ];
+
+        for (failures, expected) in cases {
+            // Act
+            let result = POLICY.delay_for(failures);
+
+            // Assert
+            assert_eq!(result, expected, "Failed for failures: {}", failures);
+        }
+    }
+
+    #[test]
+    fn test_delay_for_locks_out_at_threshold() {
+        // Arrange & Act & Assert
+        assert_eq!(POLICY.delay_for(10), Some(900));
+        assert_eq!(POLICY.delay_for(u32::MAX), Some(900));
+    }
+
+    #[test]
+    fn test_delay_for_never_exceeds_lockout() {
+        // Arrange
+        let policy = LoginThrottlePolicy {
+            lockout_after: 100,
+            ..POLICY
+        };
+
+        // Act
+        let result = policy.delay_for(80);
+
+        // Assert
+        assert_eq!(result, Some(900));
+    }
+}
@@ -1,9 +1,19 @@
 mod data_stores;
 mod email;
 mod email_client;
+mod email_verification_token;
 mod error;
 mod login_attempt_id;
+mod login_throttle;
+mod oauth;
 mod password;
+mod password_reset_token;
+mod rate_limit;
+mod recovery_code;
+mod refresh_token;
+mod role;
+mod session;
+mod totp_secret;
 mod two_fa_code;
 mod user;
 
@@ -11,8 +21,18 @@ mod user;
 pub use data_stores::*;
 pub use email::*;
 pub use email_client::*;
+pub use email_verification_token::*;
 pub use error::*;
 pub use login_attempt_id::*;
+pub use login_throttle::*;
+pub use oauth::*;
 pub use password::*;
+pub use password_reset_token::*;
+pub use rate_limit::*;
+pub use recovery_code::*;
+pub use refresh_token::*;
+pub use role::*;
+pub use session::*;
+pub use totp_secret::*;
 pub use two_fa_code::*;
 pub use user::*;
@@ -0,0 +1,351 @@
+use axum::http::StatusCode;
+use color_eyre::eyre::{eyre, Result};
+use data_encoding::{BASE64URL_NOPAD, HEXLOWER};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::Url;
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
+use std::hash::Hash;
+use uuid::Uuid;
+
+const CLIENT_SECRET_LENGTH: usize = 48;
+const AUTHORIZATION_CODE_LENGTH: usize = 43;
+
+// An app that can obtain tokens through the authorization code flow.
+// Confidential clients authenticate with a secret, public clients (e.g. SPAs) only with PKCE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub secret_hash: Option<String>,
+}
+
+impl OAuthClient {
+    // Registers a new client, returning the plain secret of a confidential client.
+    // This is the only time the plain secret is available.
+    pub fn new(
+        name: String,
+        redirect_uris: Vec<String>,
+        confidential: bool,
+    ) -> Result<(Self, Option<Secret<String>>)> {
+        if name.trim().is_empty() {
+            return Err(eyre!("Client name must not be empty"));
+        }
+        if redirect_uris.is_empty() {
+            return Err(eyre!("At least one redirect URI is required"));
+        }
+        for redirect_uri in &redirect_uris {
+            validate_redirect_uri(redirect_uri)?;
+        }
+
+        let secret = confidential.then(|| {
+            let secret: String = rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(CLIENT_SECRET_LENGTH)
+                .map(char::from)
+                .collect();
+            Secret::new(secret)
+        });
+        let client = Self {
+            client_id: Uuid::new_v4().to_string(),
+            name,
+            redirect_uris,
+            secret_hash: secret.as_ref().map(hash_client_secret),
+        };
+
+        Ok((client, secret))
+    }
+
+    pub fn is_confidential(&self) -> bool {
+        self.secret_hash.is_some()
+    }
+
+    // Redirect URIs are compared exactly, so no other page of the client can receive codes
+    pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris.iter().any(|uri| uri == redirect_uri)
+    }
+
+    pub fn verify_secret(&self, secret: &Secret<String>) -> bool {
+        // The secrets are random, so a fast hash is enough to keep them out of the database
+        match &self.secret_hash {
+            Some(secret_hash) => *secret_hash == hash_client_secret(secret),
+            None => false,
+        }
+    }
+}
+
+fn hash_client_secret(secret: &Secret<String>) -> String {
+    HEXLOWER.encode(&Sha256::digest(secret.expose_secret().as_bytes()))
+}
+
+// Redirect URIs must be absolute and without a fragment. Plain http is only allowed for
+// loopback addresses, which native apps and local development use.
+fn validate_redirect_uri(redirect_uri: &str) -> Result<()> {
+    let url = Url::parse(redirect_uri).map_err(|_| eyre!("Invalid redirect URI"))?;
+    if url.fragment().is_some() {
+        return Err(eyre!("Redirect URI must not contain a fragment"));
+    }
+
+    match url.scheme() {
+        "https" => Ok(()),
+        "http" if matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]")) => Ok(()),
+        _ => Err(eyre!("Redirect URI must use https")),
+    }
+}
+
+// The scope asking for an OpenID Connect id token
+pub const OPENID_SCOPE: &str = "openid";
+
+// Splits a space-delimited scope into a sorted list without duplicates (RFC 6749 section 3.3)
+pub fn parse_scope(scope: &str) -> Result<Vec<String>> {
+    let mut scopes: Vec<String> = scope
+        .split(' ')
+        .filter(|s| !s.is_empty())
+        .map(str::to_owned)
+        .collect();
+    // Scope tokens are printable ASCII except space, double quote and backslash
+    let is_valid_char = |c: char| c == '!' || ('#'..='[').contains(&c) || (']'..='~').contains(&c);
+    if scopes.iter().any(|s| !s.chars().all(is_valid_char)) {
+        return Err(eyre!("Invalid scope"));
+    }
+    scopes.sort();
+    scopes.dedup();
+    Ok(scopes)
+}
+
+// The scopes a user granted a client, remembered so they are not asked again
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthConsent {
+    pub client_id: String,
+    pub scopes: Vec<String>,
+}
+
+// What the user allowed a client to do, handed over for a single authorization code
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorizationGrant {
+    pub client_id: String,
+    pub user_id: Uuid,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub code_challenge: String,
+    // The login session the user authorized the client from
+    pub session_id: String,
+    // Reported in the id token of OpenID Connect clients
+    pub nonce: Option<String>,
+    pub auth_time: i64,
+    pub amr: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthorizationCode(Secret<String>);
+
+impl PartialEq for AuthorizationCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+impl Hash for AuthorizationCode {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.expose_secret().hash(state);
+    }
+}
+impl Eq for AuthorizationCode {}
+
+impl AuthorizationCode {
+    pub fn parse(code: Secret<String>) -> Result<Self> {
+        let value = code.expose_secret();
+        if value.len() != AUTHORIZATION_CODE_LENGTH
+            || !value.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(eyre!("Invalid authorization code"));
+        }
+
+        Ok(Self(code))
+    }
+}
+
+impl Default for AuthorizationCode {
+    fn default() -> Self {
+        let code: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(AUTHORIZATION_CODE_LENGTH)
+            .map(char::from)
+            .collect();
+        AuthorizationCode(Secret::new(code))
+    }
+}
+
+impl AsRef<Secret<String>> for AuthorizationCode {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+// Only the S256 method of RFC 7636 is supported, `plain` would not protect a leaked code
+pub const PKCE_METHOD_S256: &str = "S256";
+
+// A code challenge is the unpadded base64url encoding of a SHA-256 digest
+pub fn is_valid_code_challenge(code_challenge: &str) -> bool {
+    code_challenge.len() == 43
+        && BASE64URL_NOPAD
+            .decode(code_challenge.as_bytes())
+            .is_ok_and(|digest| digest.len() == 32)
+}
+
+// Checks the verifier sent to the token endpoint against the challenge sent to `/authorize`
+pub fn verify_code_verifier(code_verifier: &str, code_challenge: &str) -> bool {
+    let is_valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~');
+    if !(43..=128).contains(&code_verifier.len()) || !code_verifier.chars().all(is_valid_char) {
+        return false;
+    }
+
+    BASE64URL_NOPAD.encode(&Sha256::digest(code_verifier.as_bytes())) == code_challenge
+}
+
+// Error codes of RFC 6749, returned by the token endpoint and in authorization redirects,
+// and of RFC 6750 for requests made with an access token
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OAuthError {
+    InvalidRequest,
+    InvalidClient,
+    InvalidGrant,
+    UnsupportedGrantType,
+    UnsupportedResponseType,
+    InvalidScope,
+    AccessDenied,
+    InsufficientScope,
+}
+
+impl OAuthError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuthError::InvalidRequest => "invalid_request",
+            OAuthError::InvalidClient => "invalid_client",
+            OAuthError::InvalidGrant => "invalid_grant",
+            OAuthError::UnsupportedGrantType => "unsupported_grant_type",
+            OAuthError::UnsupportedResponseType => "unsupported_response_type",
+            OAuthError::InvalidScope => "invalid_scope",
+            OAuthError::AccessDenied => "access_denied",
+            OAuthError::InsufficientScope => "insufficient_scope",
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            OAuthError::InvalidClient => StatusCode::UNAUTHORIZED,
+            OAuthError::InsufficientScope => StatusCode::FORBIDDEN,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_confidential_client_verifies_secret() {
+        // Arrange & Act
+        let (client, secret) = OAuthClient::new(
+            "Reports".to_owned(),
+            vec!["https://reports.example.com/callback".to_owned()],
+            true,
+        )
+        .unwrap();
+
+        // Assert
+        let secret = secret.unwrap();
+        assert!(client.is_confidential());
+        assert!(client.verify_secret(&secret));
+        assert!(!client.verify_secret(&Secret::new("wrong".to_owned())));
+    }
+
+    #[test]
+    fn test_new_public_client_has_no_secret() {
+        // Arrange & Act
+        let (client, secret) = OAuthClient::new(
+            "SPA".to_owned(),
+            vec!["http://localhost:8080/callback".to_owned()],
+            false,
+        )
+        .unwrap();
+
+        // Assert
+        assert!(secret.is_none());
+        assert!(!client.is_confidential());
+        assert!(!client.verify_secret(&Secret::new(String::new())));
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_redirect_uris() {
+        let cases = [
+            "not a url",
+            "http://reports.example.com/callback",
+            "https://reports.example.com/callback#fragment",
+            "javascript:alert(1)",
+        ];
+
+        for redirect_uri in cases {
+            // Act
+            let result =
+                OAuthClient::new("Reports".to_owned(), vec![redirect_uri.to_owned()], true);
+
+            // Assert
+            assert!(result.is_err(), "Failed for case: {}", redirect_uri);
+        }
+    }
+
+    #[test]
+    fn test_allows_redirect_uri_matches_exactly() {
+        // Arrange
+        let (client, _) = OAuthClient::new(
+            "Reports".to_owned(),
+            vec!["https://reports.example.com/callback".to_owned()],
+            true,
+        )
+        .unwrap();
+
+        // Act & Assert
+        assert!(client.allows_redirect_uri("https://reports.example.com/callback"));
+        assert!(!client.allows_redirect_uri("https://reports.example.com/callback/"));
+        assert!(!client.allows_redirect_uri("https://reports.example.com/callback?next=/"));
+    }
+
+    #[test]
+    fn test_parse_scope_sorts_and_dedups() {
+        // Arrange & Act
+        let result = parse_scope("profile  email profile").unwrap();
+
+        // Assert
+        assert_eq!(result, vec!["email", "profile"]);
+        assert!(parse_scope("bad\"scope").is_err());
+    }
+
+    #[test]
+    fn test_verify_code_verifier() {
+        // Arrange
+        // Example from RFC 7636 appendix B
+        let code_verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        let code_challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+        // Act & Assert
+        assert!(is_valid_code_challenge(code_challenge));
+        assert!(verify_code_verifier(code_verifier, code_challenge));
+        assert!(!verify_code_verifier(
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXx",
+            code_challenge
+        ));
+        assert!(!verify_code_verifier("too-short", code_challenge));
+    }
+
+    #[test]
+    fn test_authorization_code_parse() {
+        // Arrange
+        let code = AuthorizationCode::default();
+
+        // Act & Assert
+        assert!(AuthorizationCode::parse(code.as_ref().clone()).is_ok());
+        assert!(AuthorizationCode::parse(Secret::new("abc".to_owned())).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use color_eyre::eyre::{eyre, Result};
 use secrecy::{ExposeSecret, Secret};
+use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub struct Password(Secret<String>);
@@ -20,6 +21,13 @@ impl Password {
     }
 }
 
+// A random password nobody knows, which locks the user out until they reset it
+impl Default for Password {
+    fn default() -> Self {
+        Self(Secret::new(Uuid::new_v4().to_string()))
+    }
+}
+
 impl AsRef<Secret<String>> for Password {
     fn as_ref(&self) -> &Secret<String> {
         &self.0
@@ -0,0 +1,81 @@
+use color_eyre::eyre::{Context, Result};
+use secrecy::{ExposeSecret, Secret};
+use std::hash::Hash;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct PasswordResetToken(Secret<String>);
+
+impl PartialEq for PasswordResetToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+impl Hash for PasswordResetToken {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.expose_secret().hash(state);
+    }
+}
+impl Eq for PasswordResetToken {}
+
+impl PasswordResetToken {
+    pub fn parse(token: &Secret<String>) -> Result<Self> {
+        let token =
+            Uuid::parse_str(token.expose_secret()).wrap_err("Invalid password reset token")?;
+        Ok(PasswordResetToken(Secret::new(token.to_string())))
+    }
+}
+
+impl Default for PasswordResetToken {
+    fn default() -> Self {
+        PasswordResetToken(Secret::new(Uuid::new_v4().to_string()))
+    }
+}
+
+impl AsRef<Secret<String>> for PasswordResetToken {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_token() {
+        // Arrange
+        let token = Secret::new(Uuid::new_v4().to_string());
+
+        // Act
+        let result = PasswordResetToken::parse(&token);
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap().as_ref().expose_secret(),
+            token.expose_secret()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_token() {
+        // Arrange
+        let token = Secret::new("not-a-token".to_string());
+
+        // Act
+        let result = PasswordResetToken::parse(&token);
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_default_generates_valid_token() {
+        // Arrange & Act
+        let token = PasswordResetToken::default();
+
+        // Assert
+        assert!(PasswordResetToken::parse(token.as_ref()).is_ok());
+    }
+}
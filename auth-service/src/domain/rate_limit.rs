@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+// Token bucket that holds up to `capacity` requests and refills completely over `period_seconds`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub period_seconds: u64,
+}
+
+impl RateLimit {
+    fn tokens_per_ms(&self) -> f64 {
+        self.capacity as f64 / (self.period_seconds.max(1) * 1000) as f64
+    }
+}
+
+// What a bucket is kept for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitScope {
+    Ip,
+    // The authenticated user, falling back to the client IP for anonymous requests
+    Account,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteRateLimit {
+    pub limit: RateLimit,
+    pub scope: RateLimitScope,
+}
+
+// Rate limits keyed by route path, routes without an entry are not limited
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    routes: HashMap<String, RouteRateLimit>,
+}
+
+impl RateLimitConfig {
+    pub fn new(routes: HashMap<String, RouteRateLimit>) -> Self {
+        Self { routes }
+    }
+
+    // Parses a comma separated list such as "/login=20/60,/2fa/recovery-codes=5/60@account",
+    // where "20/60" means 20 requests per 60 seconds
+    pub fn parse(config: &str) -> Result<Self> {
+        let mut routes = HashMap::new();
+
+        for entry in config.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (path, limit) = entry
+                .split_once('=')
+                .ok_or_else(|| eyre!("Missing '=' in rate limit: {}", entry))?;
+            let (limit, scope) = match limit.split_once('@') {
+                Some((limit, "ip")) => (limit, RateLimitScope::Ip),
+                Some((limit, "account")) => (limit, RateLimitScope::Account),
+                Some((_, scope)) => return Err(eyre!("Unknown rate limit scope: {}", scope)),
+                None => (limit, RateLimitScope::Ip),
+            };
+            let (capacity, period_seconds) = limit
+                .split_once('/')
+                .ok_or_else(|| eyre!("Missing '/' in rate limit: {}", entry))?;
+            let limit = RateLimit {
+                capacity: capacity.trim().parse()?,
+                period_seconds: period_seconds.trim().parse()?,
+            };
+            if limit.capacity == 0 || limit.period_seconds == 0 {
+                return Err(eyre!(
+                    "Rate limit must allow at least one request: {}",
+                    entry
+                ));
+            }
+
+            routes.insert(path.trim().to_owned(), RouteRateLimit { limit, scope });
+        }
+
+        Ok(Self { routes })
+    }
+
+    pub fn get(&self, path: &str) -> Option<&RouteRateLimit> {
+        self.routes.get(path)
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self::parse(
+            "/signup=10/60,/login=20/60,/token=60/60,/verify-token=300/60,/introspect=300/60",
+        )
+        .expect("Default rate limits are valid")
+    }
+}
+
+// Outcome of taking a token from a bucket, reported in the RateLimit-* headers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    // Seconds until the bucket is full again
+    pub reset_seconds: u64,
+    // Seconds until the next request would be allowed, zero if it already is
+    pub retry_after_seconds: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated_at_ms: i64,
+}
+
+impl TokenBucket {
+    pub fn full(limit: &RateLimit, now_ms: i64) -> Self {
+        Self {
+            tokens: limit.capacity as f64,
+            updated_at_ms: now_ms,
+        }
+    }
+
+    pub fn take(&mut self, limit: &RateLimit, now_ms: i64) -> RateLimitDecision {
+        let rate = limit.tokens_per_ms();
+        let capacity = limit.capacity as f64;
+
+        let elapsed_ms = (now_ms - self.updated_at_ms).max(0) as f64;
+        self.tokens = (self.tokens + elapsed_ms * rate).min(capacity);
+        self.updated_at_ms = now_ms;
+
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+
+        let seconds_until = |tokens: f64| ((tokens.max(0.0) / rate) / 1000.0).ceil() as u64;
+        RateLimitDecision {
+            allowed,
+            limit: limit.capacity,
+            remaining: self.tokens.floor() as u32,
+            reset_seconds: seconds_until(capacity - self.tokens),
+            retry_after_seconds: seconds_until(1.0 - self.tokens),
+        }
+    }
+
+    // A bucket that would be full by `now_ms` is no different from a new one
+    pub fn is_full(&self, limit: &RateLimit, now_ms: i64) -> bool {
+        let elapsed_ms = (now_ms - self.updated_at_ms).max(0) as f64;
+        self.tokens + elapsed_ms * limit.tokens_per_ms() >= limit.capacity as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit {
+        capacity: 2,
+        period_seconds: 10,
+    };
+
+    #[test]
+    fn test_take_until_empty() {
+        // Arrange
+        let mut bucket = TokenBucket::full(&LIMIT, 0);
+
+        // Act
+        let first = bucket.take(&LIMIT, 0);
+        let second = bucket.take(&LIMIT, 0);
+        let third = bucket.take(&LIMIT, 0);
+
+        // Assert
+        assert!(first.allowed && second.allowed);
+        assert_eq!(first.remaining, 1);
+        assert_eq!(second.remaining, 0);
+        assert!(!third.allowed);
+        assert_eq!(third.reset_seconds, 10);
+        assert_eq!(third.retry_after_seconds, 5);
+    }
+
+    #[test]
+    fn test_take_refills_over_time() {
+        // Arrange
+        let mut bucket = TokenBucket::full(&LIMIT, 0);
+        bucket.take(&LIMIT, 0);
+        bucket.take(&LIMIT, 0);
+
+        // Act
+        let result = bucket.take(&LIMIT, 5_000);
+
+        // Assert
+        assert!(result.allowed);
+        assert_eq!(result.remaining, 0);
+        assert!(bucket.is_full(&LIMIT, 15_000));
+    }
+
+    #[test]
+    fn test_parse_config() {
+        // Arrange
+        let config = " /login=20/60, /2fa/recovery-codes=5/30@account ";
+
+        // Act
+        let result = RateLimitConfig::parse(config).unwrap();
+
+        // Assert
+        assert_eq!(
+            result.get("/login"),
+            Some(&RouteRateLimit {
+                limit: RateLimit {
+                    capacity: 20,
+                    period_seconds: 60
+                },
+                scope: RateLimitScope::Ip,
+            })
+        );
+        assert_eq!(
+            result.get("/2fa/recovery-codes").map(|r| r.scope),
+            Some(RateLimitScope::Account)
+        );
+        assert_eq!(result.get("/signup"), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_config() {
+        // Arrange
+        let cases = ["/login", "/login=20", "/login=0/60", "/login=20/60@user"];
+
+        for input in cases {
+            // Act
+            let result = RateLimitConfig::parse(input);
+
+            // Assert
+            assert!(result.is_err(), "Failed for input: {}", input);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use color_eyre::eyre::{eyre, Result};
+use rand::Rng;
+use secrecy::{ExposeSecret, Secret};
+
+// Lowercase letters and digits without look-alikes such as 0/o and 1/l
+const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const CODE_LENGTH: usize = 10;
+
+// Single-use code that stands in for a 2FA code, formatted as "xxxxx-xxxxx"
+#[derive(Debug, Clone)]
+pub struct RecoveryCode(Secret<String>);
+
+impl PartialEq for RecoveryCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+impl RecoveryCode {
+    pub fn parse(code: &Secret<String>) -> Result<Self> {
+        // Users retype these by hand, so case, spaces and the dash are forgiven
+        let normalized: String = code
+            .expose_secret()
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        if normalized.len() != CODE_LENGTH || !normalized.bytes().all(|b| ALPHABET.contains(&b)) {
+            return Err(eyre!("Invalid recovery code"));
+        }
+
+        let (first, second) = normalized.split_at(CODE_LENGTH / 2);
+        Ok(RecoveryCode(Secret::new(format!("{}-{}", first, second))))
+    }
+}
+
+impl Default for RecoveryCode {
+    fn default() -> Self {
+        let mut rng = rand::thread_rng();
+        let code: String = (0..CODE_LENGTH)
+            .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+            .collect();
+        let (first, second) = code.split_at(CODE_LENGTH / 2);
+        RecoveryCode(Secret::new(format!("{}-{}", first, second)))
+    }
+}
+
+impl AsRef<Secret<String>> for RecoveryCode {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_generates_valid_code() {
+        // Arrange & Act
+        let code = RecoveryCode::default();
+
+        // Assert
+        assert_eq!(RecoveryCode::parse(code.as_ref()).unwrap(), code);
+    }
+
+    #[test]
+    fn test_parse_normalizes_input() {
+        // Arrange
+        let code = Secret::new(" ABCDE fghjk ".to_string());
+
+        // Act
+        let result = RecoveryCode::parse(&code);
+
+        // Assert
+        assert_eq!(result.unwrap().as_ref().expose_secret(), "abcde-fghjk");
+    }
+
+    #[test]
+    fn test_parse_invalid_codes() {
+        // Arrange
+        let cases = ["123456", "abcde-fghj", "abcde-fghjkm", "abcde-fghi0"];
+
+        for input in cases {
+            // Act
+            let result = RecoveryCode::parse(&Secret::new(input.to_string()));
+
+            // Assert
+            assert!(result.is_err(), "Failed for input: {}", input);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Result};
+use rand::{distributions::Alphanumeric, Rng};
+use secrecy::{ExposeSecret, Secret};
+use std::hash::Hash;
+use uuid::Uuid;
+
+const REFRESH_TOKEN_LENGTH: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct RefreshToken(Secret<String>);
+
+impl PartialEq for RefreshToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+impl Hash for RefreshToken {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.expose_secret().hash(state);
+    }
+}
+impl Eq for RefreshToken {}
+
+impl RefreshToken {
+    pub fn parse(token: Secret<String>) -> Result<Self> {
+        let value = token.expose_secret();
+        if value.len() != REFRESH_TOKEN_LENGTH || !value.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(eyre!("Invalid refresh token"));
+        }
+
+        Ok(Self(token))
+    }
+}
+
+impl Default for RefreshToken {
+    fn default() -> Self {
+        // Refresh tokens are opaque, so a long random alphanumeric string is all we need
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(REFRESH_TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        RefreshToken(Secret::new(token))
+    }
+}
+
+impl AsRef<Secret<String>> for RefreshToken {
+    fn as_ref(&self) -> &Secret<String> {
+        &self.0
+    }
+}
+
+// Everything we know about an issued refresh token.
+// Tokens rotated from the same login share a `family_id` and `family_issued_at`,
+// so a replayed token or a revoked session can take down the whole chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshTokenRecord {
+    pub user_id: Uuid,
+    pub family_id: String,
+    pub family_issued_at: i64,
+    pub used: bool,
+    // Set for tokens issued to an OAuth client, which only that client can use
+    pub client_id: Option<String>,
+    pub scope: Option<String>,
+}
+
+impl RefreshTokenRecord {
+    // Start a new token family, i.e. a new login session
+    pub fn new(user_id: Uuid) -> Self {
+        Self {
+            user_id,
+            family_id: Uuid::new_v4().to_string(),
+            family_issued_at: Utc::now().timestamp(),
+            used: false,
+            client_id: None,
+            scope: None,
+        }
+    }
+
+    // Start a new token family for an OAuth client the user authorized
+    pub fn for_client(user_id: Uuid, client_id: String, scope: String) -> Self {
+        Self {
+            client_id: Some(client_id),
+            scope: Some(scope),
+            ..Self::new(user_id)
+        }
+    }
+
+    // Create the record for the next token of the same family
+    pub fn rotate(&self) -> Self {
+        Self {
+            used: false,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_generates_valid_token() {
+        // Arrange & Act
+        let token = RefreshToken::default();
+
+        // Assert
+        assert!(RefreshToken::parse(token.as_ref().clone()).is_ok());
+    }
+
+    #[test]
+    fn test_default_generates_unique_tokens() {
+        // Arrange & Act
+        let first = RefreshToken::default();
+        let second = RefreshToken::default();
+
+        // Assert
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_rotate_keeps_family() {
+        // Arrange
+        let mut record = RefreshTokenRecord::new(Uuid::new_v4());
+        record.used = true;
+
+        // Act
+        let rotated = record.rotate();
+
+        // Assert
+        assert_eq!(rotated.family_id, record.family_id);
+        assert_eq!(rotated.family_issued_at, record.family_issued_at);
+        assert!(!rotated.used);
+    }
+
+    #[test]
+    fn test_parse_rejects_wrong_length() {
+        // Arrange
+        let token = Secret::new("abc123".to_string());
+
+        // Act
+        let result = RefreshToken::parse(token);
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_non_alphanumeric() {
+        // Arrange
+        let token = Secret::new(format!("{}!", "a".repeat(REFRESH_TOKEN_LENGTH - 1)));
+
+        // Act
+        let result = RefreshToken::parse(token);
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
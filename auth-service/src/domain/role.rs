@@ -0,0 +1,132 @@
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+// The role every deployment has, it guards the admin API
+pub const ADMIN_ROLE: &str = "admin";
+
+// The longest role or permission name we accept
+const MAX_NAME_LENGTH: usize = 64;
+
+// A named set of permissions users can be given, e.g. `admin` or `support`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Role(String);
+
+impl Role {
+    pub fn parse(name: String) -> Result<Self> {
+        validate_name(&name).map_err(|_| eyre!("Invalid role: {}", name))?;
+        Ok(Self(name))
+    }
+
+    pub fn admin() -> Self {
+        Self(ADMIN_ROLE.to_owned())
+    }
+}
+
+impl AsRef<str> for Role {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// Something a role allows, e.g. `users:read`. Relying apps decide what their permissions mean.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Permission(String);
+
+impl Permission {
+    pub fn parse(name: String) -> Result<Self> {
+        validate_name(&name).map_err(|_| eyre!("Invalid permission: {}", name))?;
+        Ok(Self(name))
+    }
+}
+
+impl AsRef<str> for Permission {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// The roles of a user and the permissions they grant together, both sorted without duplicates
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserRoles {
+    pub roles: Vec<Role>,
+    pub permissions: Vec<Permission>,
+}
+
+impl UserRoles {
+    pub fn new(mut roles: Vec<Role>, mut permissions: Vec<Permission>) -> Self {
+        roles.sort();
+        roles.dedup();
+        permissions.sort();
+        permissions.dedup();
+        Self { roles, permissions }
+    }
+}
+
+// Names end up in JWT claims and `X-Auth-Roles` headers, so they stay short and plain
+fn validate_name(name: &str) -> Result<()> {
+    let is_valid_char = |c: char| {
+        c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | ':' | '.')
+    };
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH || !name.chars().all(is_valid_char) {
+        return Err(eyre!("Invalid name"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_names() {
+        // Arrange
+        let names = ["admin", "support-team", "users:read", "billing.invoices_v2"];
+
+        for name in names {
+            // Act
+            let role = Role::parse(name.to_owned());
+            let permission = Permission::parse(name.to_owned());
+
+            // Assert
+            assert!(role.is_ok(), "Failed for input: {}", name);
+            assert!(permission.is_ok(), "Failed for input: {}", name);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_names() {
+        // Arrange
+        let long_name = "a".repeat(MAX_NAME_LENGTH + 1);
+        let names = ["", "Admin", "two words", "a,b", long_name.as_str()];
+
+        for name in names {
+            // Act
+            let role = Role::parse(name.to_owned());
+            let permission = Permission::parse(name.to_owned());
+
+            // Assert
+            assert!(role.is_err(), "Failed for input: {}", name);
+            assert!(permission.is_err(), "Failed for input: {}", name);
+        }
+    }
+
+    #[test]
+    fn test_user_roles_are_sorted_without_duplicates() {
+        // Arrange
+        let role = |name: &str| Role::parse(name.to_owned()).unwrap();
+        let permission = |name: &str| Permission::parse(name.to_owned()).unwrap();
+
+        // Act
+        let user_roles = UserRoles::new(
+            vec![role("support"), role("admin"), role("support")],
+            vec![permission("users:write"), permission("users:read")],
+        );
+
+        // Assert
+        assert_eq!(user_roles.roles, vec![role("admin"), role("support")]);
+        assert_eq!(
+            user_roles.permissions,
+            vec![permission("users:read"), permission("users:write")]
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+// The longest user agent we keep, anything after it is cut off
+const MAX_USER_AGENT_LENGTH: usize = 256;
+
+// A device the user is logged in from. The id is the refresh token family id,
+// so the session lives exactly as long as its refresh token chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub id: String,
+    pub user_id: Uuid,
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub revoked: bool,
+    // Authentication methods used to log in, reported in id tokens
+    pub amr: Vec<String>,
+}
+
+impl Session {
+    pub fn new(
+        id: String,
+        user_id: Uuid,
+        amr: Vec<String>,
+        user_agent: Option<String>,
+        ip: Option<String>,
+    ) -> Self {
+        let now = Utc::now().timestamp();
+        Self {
+            id,
+            user_id,
+            created_at: now,
+            last_seen_at: now,
+            user_agent: user_agent.map(|user_agent| truncate(user_agent, MAX_USER_AGENT_LENGTH)),
+            ip,
+            revoked: false,
+            amr,
+        }
+    }
+}
+
+// Authentication method references (RFC 8176) of a password login,
+// with a 2FA or recovery code as the second factor if one was asked for
+pub fn login_amr(used_two_fa: bool) -> Vec<String> {
+    let methods: &[&str] = if used_two_fa {
+        &["pwd", "otp", "mfa"]
+    } else {
+        &["pwd"]
+    };
+    methods.iter().map(|method| method.to_string()).collect()
+}
+
+fn truncate(mut value: String, max_length: usize) -> String {
+    if value.len() > max_length {
+        let mut end = max_length;
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        value.truncate(end);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_truncates_long_user_agent() {
+        // Arrange
+        let user_agent = "é".repeat(MAX_USER_AGENT_LENGTH);
+
+        // Act
+        let session = Session::new(
+            "id".to_owned(),
+            Uuid::new_v4(),
+            vec![],
+            Some(user_agent),
+            None,
+        );
+
+        // Assert
+        let user_agent = session.user_agent.unwrap();
+        assert!(user_agent.len() <= MAX_USER_AGENT_LENGTH);
+        assert!(user_agent.chars().all(|c| c == 'é'));
+        assert_eq!(session.created_at, session.last_seen_at);
+        assert!(!session.revoked);
+    }
+
+    #[test]
+    fn test_login_amr_reports_second_factor() {
+        // Act & Assert
+        assert_eq!(login_amr(false), vec!["pwd"]);
+        assert_eq!(login_amr(true), vec!["pwd", "otp", "mfa"]);
+    }
+}
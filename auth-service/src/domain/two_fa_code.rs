@@ -1,4 +1,4 @@
-use color_eyre::eyre::{eyre, Context, Result};
+use color_eyre::eyre::{eyre, Result};
 use rand::Rng;
 use secrecy::{ExposeSecret, Secret};
 
@@ -13,11 +13,9 @@ impl PartialEq for TwoFACode {
 
 impl TwoFACode {
     pub fn parse(code: &Secret<String>) -> Result<Self> {
-        let code_as_u32 = code
-            .expose_secret()
-            .parse::<u32>()
-            .wrap_err("Invalid 2FA code")?;
-        if (100_000..=999_999).contains(&code_as_u32) {
+        // Codes are generated digit by digit, so leading zeros are valid
+        let code_str = code.expose_secret();
+        if code_str.len() == 6 && code_str.chars().all(|c| c.is_ascii_digit()) {
             Ok(Self(code.to_owned()))
         } else {
             Err(eyre!("Invalid 2FA code"))
@@ -73,6 +71,18 @@ mod tests {
         assert!(long_result.is_err());
     }
 
+    #[test]
+    fn test_parse_code_with_leading_zero() {
+        // Arrange
+        let code = "012345";
+
+        // Act
+        let result = TwoFACode::parse(&Secret::new(code.to_string()));
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
     #[test]
     fn test_parse_non_digits() {
         // Arrange
@@ -1,20 +1,201 @@
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
 use super::{Email, Password};
 
-// The User struct should contain 3 fields. email, which is a String;
-// password, which is also a String; and requires_2fa, which is a boolean.
+// The User struct should contain 7 fields. id, which stays the same when the email changes;
+// email, which is a String;
+// password, which is also a String; requires_2fa, which is a boolean;
+// two_fa_method, which is used when 2FA is required;
+// verified, which tells whether the user proved they own the email;
+// and disabled, which an admin sets to keep the user from logging in.
 #[derive(Clone, Debug, PartialEq)]
 pub struct User {
+    pub id: Uuid,
     pub email: Email,
     pub password: Password,
     pub requires_2fa: bool,
+    pub two_fa_method: TwoFAMethod,
+    pub verified: bool,
+    pub disabled: bool,
 }
 
 impl User {
+    // New users start unverified until they confirm their email address
     pub fn new(email: Email, password: Password, requires_2fa: bool) -> Self {
         User {
+            id: Uuid::new_v4(),
             email,
             password,
             requires_2fa,
+            two_fa_method: TwoFAMethod::default(),
+            verified: false,
+            disabled: false,
+        }
+    }
+
+    // Which second factor login asks for, if any
+    pub fn login_two_fa_method(&self, policy: UnverifiedLoginPolicy) -> Option<TwoFAMethod> {
+        // The emailed code at least proves access to the inbox
+        if !self.verified && policy == UnverifiedLoginPolicy::Restrict {
+            return Some(TwoFAMethod::Email);
+        }
+
+        self.requires_2fa.then_some(self.two_fa_method)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TwoFAMethod {
+    // 6-digit code sent through the email client
+    #[default]
+    Email,
+    // RFC 6238 code from an authenticator app
+    Totp,
+}
+
+impl TwoFAMethod {
+    pub fn parse(method: &str) -> Result<Self> {
+        match method {
+            "email" => Ok(Self::Email),
+            "totp" => Ok(Self::Totp),
+            _ => Err(eyre!("Invalid 2FA method: {}", method)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Totp => "totp",
+        }
+    }
+}
+
+// How login treats users who have not verified their email address yet
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnverifiedLoginPolicy {
+    // Unverified users log in like everyone else
+    #[default]
+    Allow,
+    // Unverified users must always complete login with a code sent to their email
+    Restrict,
+    // Unverified users cannot log in until they verify their email
+    Refuse,
+}
+
+impl UnverifiedLoginPolicy {
+    pub fn parse(policy: &str) -> Result<Self> {
+        match policy.to_lowercase().as_str() {
+            "allow" => Ok(Self::Allow),
+            "restrict" => Ok(Self::Restrict),
+            "refuse" => Ok(Self::Refuse),
+            _ => Err(eyre!("Invalid unverified login policy: {}", policy)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+
+    use super::*;
+
+    fn user(requires_2fa: bool, two_fa_method: TwoFAMethod, verified: bool) -> User {
+        User {
+            id: Uuid::new_v4(),
+            email: Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            password: Password::parse(Secret::new("password".to_string())).unwrap(),
+            requires_2fa,
+            two_fa_method,
+            verified,
+            disabled: false,
+        }
+    }
+
+    #[test]
+    fn test_login_two_fa_method() {
+        // Arrange
+        let cases = [
+            (
+                user(false, TwoFAMethod::Email, true),
+                UnverifiedLoginPolicy::Allow,
+                None,
+            ),
+            (
+                user(true, TwoFAMethod::Email, true),
+                UnverifiedLoginPolicy::Allow,
+                Some(TwoFAMethod::Email),
+            ),
+            (
+                user(true, TwoFAMethod::Totp, true),
+                UnverifiedLoginPolicy::Restrict,
+                Some(TwoFAMethod::Totp),
+            ),
+            (
+                user(false, TwoFAMethod::Email, false),
+                UnverifiedLoginPolicy::Allow,
+                None,
+            ),
+            (
+                user(true, TwoFAMethod::Totp, false),
+                UnverifiedLoginPolicy::Restrict,
+                Some(TwoFAMethod::Email),
+            ),
+        ];
+
+        for (user, policy, expected) in cases {
+            // Act
+            let result = user.login_two_fa_method(policy);
+
+            // Assert
+            assert_eq!(result, expected, "Failed for {:?} with {:?}", user, policy);
+        }
+    }
+
+    #[test]
+    fn test_parse_two_fa_method() {
+        // Arrange
+        let methods = [TwoFAMethod::Email, TwoFAMethod::Totp];
+
+        for method in methods {
+            // Act
+            let result = TwoFAMethod::parse(method.as_str());
+
+            // Assert
+            assert_eq!(result.unwrap(), method);
+        }
+        assert!(TwoFAMethod::parse("sms").is_err());
+    }
+
+    #[test]
+    fn test_parse_valid_policies() {
+        // Arrange
+        let cases = [
+            ("allow", UnverifiedLoginPolicy::Allow),
+            ("Restrict", UnverifiedLoginPolicy::Restrict),
+            ("REFUSE", UnverifiedLoginPolicy::Refuse),
+        ];
+
+        for (input, expected) in cases {
+            // Act
+            let result = UnverifiedLoginPolicy::parse(input);
+
+            // Assert
+            assert_eq!(result.unwrap(), expected, "Failed for input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_policy() {
+        // Arrange
+        let policy = "sometimes";
+
+        // Act
+        let result = UnverifiedLoginPolicy::parse(policy);
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
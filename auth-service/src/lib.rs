@@ -7,50 +7,104 @@ mod utils;
 
 use askama::Template;
 use axum::{
-    extract::Request,
+    extract::{connect_info::IntoMakeServiceWithConnectInfo, ConnectInfo, Request},
     http::{Method, StatusCode},
-    middleware::{self, Next},
+    middleware::{self, AddExtension, Next},
     response::{Html, IntoResponse, Response},
-    routing::{get, post},
+    routing::{delete, get, post, put},
     serve::Serve,
     Extension, Router,
 };
 use redis::{Client, RedisResult};
+use routes::audience_token;
+use routes::jwks;
 use routes::login;
-use routes::logout;
+use routes::refresh_token;
+use routes::regenerate_recovery_codes;
+use routes::rotate_signing_keys;
 use routes::verify_2fa;
+use routes::verify_email;
+use routes::{
+    admin_delete_user, admin_disable_user, admin_enable_user, admin_force_password_reset,
+    admin_get_user, admin_list_users, admin_set_requires_2fa,
+};
+use routes::{admin_logout_all, logout, logout_all};
+use routes::{authorize, authorize_decision, register_oauth_client, token, ConsentPrompt};
+use routes::{change_email, change_password, confirm_email_change, delete_account, export_account};
+use routes::{confirm_totp, enroll_totp};
+use routes::{end_session, openid_configuration, userinfo};
+use routes::{forward_auth, introspect, revoke};
+use routes::{list_sessions, revoke_session};
+use routes::{password_reset_confirm, password_reset_request};
+use routes::{set_role_permissions, set_user_roles};
 use routes::{signup, verify_token};
 use secrecy::{ExposeSecret, Secret};
 use sqlx::{postgres::PgPoolOptions, PgPool};
-use std::error::Error;
+use std::{error::Error, net::SocketAddr};
 use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
-use utils::{make_span_with_request_id, on_request, on_response};
+use utils::{
+    make_span_with_request_id, on_request, on_response, rate_limit, require_role, RequireRole,
+};
 
-pub use app_state::{AppState, BannedTokenStoreType, EmailClientType, TwoFACodeStoreType};
-pub use domain::{Email, ErrorResponse, LoginAttemptId, TwoFACode};
-pub use routes::TwoFactorAuthResponse;
+pub use app_state::{
+    AppState, AuthorizationCodeStoreType, BannedTokenStoreType, EmailClientType,
+    EmailVerificationTokenStoreType, JwtKeyRingType, LoginAttemptStoreType, OAuthClientStoreType,
+    PasswordResetTokenStoreType, RateLimitStoreType, RefreshTokenStoreType, SessionStoreType,
+    TwoFACodeStoreType, UserStoreType,
+};
+pub use domain::{
+    Email, EmailVerificationToken, ErrorResponse, LoginAttemptId, LoginThrottleConfig,
+    LoginThrottlePolicy, Password, PasswordResetToken, RateLimit, RateLimitConfig, RateLimitScope,
+    RecoveryCode, RefreshToken, Role, RouteRateLimit, TotpSecret, TwoFACode, TwoFAMethod,
+    UnverifiedLoginPolicy, User, UserRoles, UserStoreError, ADMIN_ROLE,
+};
+pub use routes::{
+    AccountExportResponse, AdminUserDetailsResponse, AdminUserResponse, AdminUsersResponse,
+    AudienceTokenResponse, ConfirmTotpResponse, EnrollTotpResponse, IntrospectionResponse,
+    OpenIdConfiguration, RecoveryCodesResponse, RegisterOAuthClientResponse,
+    RotateSigningKeysResponse, SessionResponse, SessionTokensResponse, SessionsResponse,
+    SignupResponse, TokenResponse, TwoFactorAuthResponse, UserInfoResponse, VerifyTokenResponse,
+};
 pub use services::{
-    HashMapTwoFACodeStore, HashMapUserStore, HashSetBannedTokenStore, MockEmailClient,
-    PostgresUserStore, RedisBannedTokenStore, RedisTwoFACodeStore, SlackMessageClient,
+    HashMapAuthorizationCodeStore, HashMapEmailVerificationTokenStore, HashMapLoginAttemptStore,
+    HashMapOAuthClientStore, HashMapPasswordResetTokenStore, HashMapRateLimitStore,
+    HashMapRefreshTokenStore, HashMapSessionStore, HashMapTwoFACodeStore, HashMapUserStore,
+    HashSetBannedTokenStore, MockEmailClient, PostgresOAuthClientStore, PostgresSessionStore,
+    PostgresUserStore, RedisAuthorizationCodeStore, RedisBannedTokenStore,
+    RedisEmailVerificationTokenStore, RedisLoginAttemptStore, RedisPasswordResetTokenStore,
+    RedisRateLimitStore, RedisRefreshTokenStore, RedisTwoFACodeStore, SlackMessageClient,
 };
 pub use utils::constants::{prod, test};
 pub use utils::init_tracing;
-pub use utils::{DATABASE_URL, JWT_COOKIE_NAME, REDIS_HOST_NAME, SLACK_WEBHOOK};
+pub use utils::{
+    configure_jwt_key_ring, reload_signing_keys_every, rotate_signing_key_every, JwtKeyRing,
+    JwtSigningKey, ADMIN_TOKEN, DATABASE_URL, JWT_COOKIE_NAME, JWT_KEY_ROTATION_INTERVAL,
+    JWT_SECRET, KEY_RELOAD_INTERVAL, LOGIN_THROTTLE, MAX_TWO_FA_ATTEMPTS, RATE_LIMITS,
+    REDIS_HOST_NAME, REFRESH_COOKIE_NAME, SLACK_WEBHOOK, UNVERIFIED_LOGIN_POLICY,
+};
 
 #[derive(Template)]
 #[template(path = "index.html")]
 struct IndexTemplate {
     prefix: String,
+    // Asks a logged in user to authorize an OAuth client instead of showing the login form
+    consent: Option<ConsentPrompt>,
 }
 
 async fn root(Extension(prefix): Extension<String>) -> impl axum::response::IntoResponse {
-    let template = IndexTemplate { prefix };
+    let template = IndexTemplate {
+        prefix,
+        consent: None,
+    };
     Html(template.render().unwrap())
 }
 
 // This struct encapsulates our application-related logic.
 pub struct Application {
-    server: Serve<Router, Router>,
+    server: Serve<
+        IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+        AddExtension<Router, ConnectInfo<SocketAddr>>,
+    >,
     // address is exposed as a public field,
     // so we have access to it in tests.
     pub address: String,
@@ -67,20 +121,75 @@ impl Application {
 
         let cors = CorsLayer::new()
             // Allow GET and POST requests
-            .allow_methods([Method::GET, Method::POST])
+            .allow_methods([Method::GET, Method::POST, Method::DELETE])
             // Allow cookies to be included in requests
             .allow_credentials(true)
             .allow_origin(allowed_origins);
 
+        // Open to the static admin token and to users holding the admin role
+        let admin_router = Router::new()
+            .route("/keys/rotate", post(rotate_signing_keys))
+            .route("/users", get(admin_list_users))
+            .route("/users/roles", put(set_user_roles))
+            .route("/users/:id", get(admin_get_user).delete(admin_delete_user))
+            .route("/users/:id/disable", post(admin_disable_user))
+            .route("/users/:id/enable", post(admin_enable_user))
+            .route("/users/:id/logout-all", post(admin_logout_all))
+            .route(
+                "/users/:id/password-reset",
+                post(admin_force_password_reset),
+            )
+            .route("/users/:id/2fa", put(admin_set_requires_2fa))
+            .route("/roles/:role", put(set_role_permissions))
+            .route("/oauth/clients", post(register_oauth_client))
+            .route_layer(middleware::from_fn_with_state(
+                RequireRole::new(ADMIN_ROLE, app_state.clone()),
+                require_role,
+            ));
+
         let router = Router::new()
             .route("/", get(root))
             .nest_service("/assets", ServeDir::new("assets"))
             .route("/signup", post(signup))
             .route("/login", post(login))
             .route("/logout", post(logout))
+            .route("/logout-all", post(logout_all))
             .route("/verify-2fa", post(verify_2fa))
+            .route("/2fa/totp/enroll", post(enroll_totp))
+            .route("/2fa/totp/confirm", post(confirm_totp))
+            .route("/2fa/recovery-codes", post(regenerate_recovery_codes))
+            .route("/verify-email", post(verify_email))
             .route("/verify-token", post(verify_token))
+            .route("/introspect", post(introspect))
+            .route("/revoke", post(revoke))
+            .route("/forward-auth", get(forward_auth))
+            .route("/token/refresh", post(refresh_token))
+            .route("/token/audience", post(audience_token))
+            .route("/authorize", get(authorize).post(authorize_decision))
+            .route("/token", post(token))
+            .route("/userinfo", get(userinfo).post(userinfo))
+            .route("/end-session", get(end_session))
+            .route("/sessions", get(list_sessions))
+            .route("/sessions/:id", delete(revoke_session))
+            .route("/account/password", post(change_password))
+            .route("/account/email", post(change_email))
+            .route("/account/email/confirm", post(confirm_email_change))
+            .route("/account/export", get(export_account))
+            .route("/account/delete", post(delete_account))
+            .route("/password-reset/request", post(password_reset_request))
+            .route("/password-reset/confirm", post(password_reset_confirm))
             .route("/health", get(health))
+            .route("/.well-known/jwks.json", get(jwks))
+            .route(
+                "/.well-known/openid-configuration",
+                get(openid_configuration),
+            )
+            .nest("/admin", admin_router)
+            // Runs after routing, so the limit can be picked by the matched route
+            .route_layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                rate_limit,
+            ))
             .with_state(app_state.clone())
             .layer(middleware::from_fn(handle_prefix))
             .layer(cors)
@@ -93,7 +202,11 @@ impl Application {
 
         let listener = tokio::net::TcpListener::bind(address).await?;
         let address = listener.local_addr()?.to_string();
-        let server = axum::serve(listener, router);
+        // The peer address is the fallback for the client IP when there is no proxy in front
+        let server = axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        );
 
         // Create a new Application instance and return it
         Ok(Application { server, address })
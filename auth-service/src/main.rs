@@ -1,7 +1,11 @@
 use auth_service::{
-    configure_redis, get_postgres_pool, init_tracing, prod, AppState, Application,
-    PostgresUserStore, RedisBannedTokenStore, RedisTwoFACodeStore, SlackMessageClient,
-    DATABASE_URL, SLACK_WEBHOOK,
+    configure_jwt_key_ring, configure_redis, get_postgres_pool, init_tracing, prod,
+    reload_signing_keys_every, rotate_signing_key_every, AppState, Application,
+    PostgresOAuthClientStore, PostgresSessionStore, PostgresUserStore, RedisAuthorizationCodeStore,
+    RedisBannedTokenStore, RedisEmailVerificationTokenStore, RedisLoginAttemptStore,
+    RedisPasswordResetTokenStore, RedisRateLimitStore, RedisRefreshTokenStore, RedisTwoFACodeStore,
+    SlackMessageClient, ADMIN_TOKEN, DATABASE_URL, JWT_KEY_ROTATION_INTERVAL, KEY_RELOAD_INTERVAL,
+    LOGIN_THROTTLE, RATE_LIMITS, SLACK_WEBHOOK, UNVERIFIED_LOGIN_POLICY,
 };
 use sqlx::PgPool;
 use std::sync::Arc;
@@ -16,16 +20,60 @@ async fn main() {
     let redis_conn = Arc::new(RwLock::new(configure_redis()));
     let slack_client = configure_slack_email_client();
 
-    let user_store = PostgresUserStore::new(pg_pool);
-    let banned_token_store = RedisBannedTokenStore::new(redis_conn.clone());
+    let user_store = PostgresUserStore::new(pg_pool.clone());
+    let session_store = PostgresSessionStore::new(pg_pool.clone());
+    let oauth_client_store = PostgresOAuthClientStore::new(pg_pool);
+    let mut banned_token_store = RedisBannedTokenStore::new(redis_conn.clone());
+    let migrated = banned_token_store
+        .migrate_legacy_keys()
+        .await
+        .expect("Failed to migrate banned tokens");
+    if migrated > 0 {
+        tracing::info!("Migrated {} legacy banned tokens", migrated);
+    }
+    let refresh_token_store = RedisRefreshTokenStore::new(redis_conn.clone());
     let two_fa_code_store = RedisTwoFACodeStore::new(redis_conn.clone());
+    let password_reset_token_store = RedisPasswordResetTokenStore::new(redis_conn.clone());
+    let email_verification_token_store = RedisEmailVerificationTokenStore::new(redis_conn.clone());
+    let authorization_code_store = RedisAuthorizationCodeStore::new(redis_conn.clone());
+    let login_attempt_store = RedisLoginAttemptStore::new(redis_conn.clone());
+    let rate_limit_store = RedisRateLimitStore::new(redis_conn.clone());
+    let jwt_key_ring = Arc::new(RwLock::new(configure_jwt_key_ring()));
     let app_state = AppState {
         user_store: Arc::from(RwLock::from(user_store)),
         banned_token_store: Arc::from(RwLock::from(banned_token_store)),
+        refresh_token_store: Arc::from(RwLock::from(refresh_token_store)),
+        session_store: Arc::from(RwLock::from(session_store)),
         two_fa_code_store: Arc::from(RwLock::from(two_fa_code_store)),
+        password_reset_token_store: Arc::from(RwLock::from(password_reset_token_store)),
+        email_verification_token_store: Arc::from(RwLock::from(email_verification_token_store)),
+        oauth_client_store: Arc::from(RwLock::from(oauth_client_store)),
+        authorization_code_store: Arc::from(RwLock::from(authorization_code_store)),
+        login_attempt_store: Arc::from(RwLock::from(login_attempt_store)),
+        rate_limit_store: Arc::from(RwLock::from(rate_limit_store)),
         email_client: Arc::from(RwLock::from(slack_client)),
+        jwt_key_ring: jwt_key_ring.clone(),
+        unverified_login_policy: *UNVERIFIED_LOGIN_POLICY,
+        login_throttle: *LOGIN_THROTTLE,
+        rate_limits: Arc::new(RATE_LIMITS.clone()),
+        admin_token: ADMIN_TOKEN.clone(),
     };
 
+    // Instances sharing the key directory sign with keys the others rotate in
+    if jwt_key_ring.read().await.can_rotate() {
+        tokio::spawn(reload_signing_keys_every(
+            jwt_key_ring.clone(),
+            KEY_RELOAD_INTERVAL,
+        ));
+    }
+
+    if let Some(interval) = *JWT_KEY_ROTATION_INTERVAL {
+        if !jwt_key_ring.read().await.can_rotate() {
+            panic!("JWT_KEY_ROTATION_INTERVAL_SECONDS needs an RS256 or EdDSA JWT_KEY_DIR");
+        }
+        tokio::spawn(rotate_signing_key_every(jwt_key_ring, interval));
+    }
+
     let app = Application::build(app_state, prod::APP_ADDRESS)
         .await
         .expect("Failed to build app");
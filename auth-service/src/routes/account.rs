@@ -0,0 +1,474 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        AuthAPIError, Email, EmailVerificationToken, EmailVerificationTokenStoreError,
+        LoginThrottleKey, Password, TwoFACodeStoreError, TwoFAMethod, UserStoreError,
+    },
+    routes::{remove_session_cookies, user_roles, SessionResponse},
+    utils::{
+        auth::{revoke_other_sessions, revoke_user_sessions},
+        constants::AUTH_BASE_URL,
+        AuthenticatedUser,
+    },
+    AppState,
+};
+
+// Changes the password of the logged in user. The current password is asked for again,
+// so a session left open on a shared device cannot lock the user out.
+#[tracing::instrument(name = "Changing password", skip_all)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    AuthenticatedUser {
+        user_id,
+        email,
+        claims,
+    }: AuthenticatedUser,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    let Ok(current_password) = Password::parse(request.current_password) else {
+        return Err(AuthAPIError::InvalidCredentials);
+    };
+    let Ok(new_password) = Password::parse(request.new_password) else {
+        return Err(AuthAPIError::InvalidCredentials);
+    };
+
+    verify_current_password(&email, &current_password, &state).await?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .update_password(&email, new_password)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    // Devices that may have learned the old password are logged out, this one stays
+    revoke_other_sessions(
+        &user_id,
+        &claims.sid,
+        state.session_store.clone(),
+        state.refresh_token_store.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Starts moving the logged in user to a new email address. The change is only made once the
+// link sent to the new address is used, until then the old address keeps signing in.
+#[tracing::instrument(name = "Changing email", skip_all)]
+pub async fn change_email(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, email, .. }: AuthenticatedUser,
+    Json(request): Json<ChangeEmailRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    let Ok(current_password) = Password::parse(request.current_password) else {
+        return Err(AuthAPIError::InvalidCredentials);
+    };
+    let Ok(new_email) = Email::parse(request.new_email) else {
+        return Err(AuthAPIError::InvalidCredentials);
+    };
+
+    verify_current_password(&email, &current_password, &state).await?;
+
+    match state.user_store.read().await.get_user(&new_email).await {
+        Ok(_) => return Err(AuthAPIError::UserAlreadyExists),
+        Err(UserStoreError::UserNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    send_email_change_link(&user_id, &new_email, &state).await?;
+    send_email_change_requested_notice(&email, &new_email, &state).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+// Moves the user to the address the link was sent to. Sessions signed in with the old
+// address all end, so the user signs in again with the new one.
+#[tracing::instrument(name = "Confirming email change", skip_all)]
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    Json(request): Json<ConfirmEmailChangeRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    let Ok(token) = EmailVerificationToken::parse(&request.token) else {
+        return Err(AuthAPIError::InvalidToken);
+    };
+
+    let (user_id, new_email) = match state
+        .email_verification_token_store
+        .write()
+        .await
+        .consume_email_change_token(&token)
+        .await
+    {
+        Ok(change) => change,
+        Err(EmailVerificationTokenStoreError::TokenNotFound) => {
+            return Err(AuthAPIError::InvalidToken)
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    let old_email = {
+        let mut user_store = state.user_store.write().await;
+        let user = match user_store.get_user_by_id(&user_id).await {
+            Ok(user) => user,
+            Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        };
+        match user_store.update_email(&user.email, &new_email).await {
+            Ok(()) => {}
+            Err(UserStoreError::UserAlreadyExists) => return Err(AuthAPIError::UserAlreadyExists),
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+        // Using the link proves the user owns the new address
+        user_store
+            .mark_verified(&new_email)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        user.email
+    };
+
+    revoke_user_sessions(
+        &user_id,
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+
+    send_email_changed_notice(&old_email, &state).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Returns everything stored about the logged in user as a JSON file, for data access requests.
+// Secrets such as the password hash, TOTP secret and recovery codes are left out.
+#[tracing::instrument(name = "Exporting account", skip_all)]
+pub async fn export_account(
+    State(state): State<AppState>,
+    AuthenticatedUser {
+        user_id, claims, ..
+    }: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let user = match state.user_store.read().await.get_user_by_id(&user_id).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    let user_roles = user_roles(&state, &user_id).await?;
+    let sessions = state
+        .session_store
+        .read()
+        .await
+        .list_sessions(&user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    let oauth_consents = state
+        .oauth_client_store
+        .read()
+        .await
+        .list_consents(&user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let export = AccountExportResponse {
+        exported_at: Utc::now().timestamp(),
+        user: AccountExportUser {
+            id: user.id,
+            email: user.email.as_ref().expose_secret().to_owned(),
+            verified: user.verified,
+            disabled: user.disabled,
+        },
+        two_fa: AccountExportTwoFA {
+            requires_2fa: user.requires_2fa,
+            method: user.two_fa_method,
+        },
+        roles: user_roles
+            .roles
+            .iter()
+            .map(|role| role.as_ref().to_owned())
+            .collect(),
+        permissions: user_roles
+            .permissions
+            .iter()
+            .map(|permission| permission.as_ref().to_owned())
+            .collect(),
+        sessions: sessions
+            .into_iter()
+            .map(|session| SessionResponse {
+                current: session.id == claims.sid,
+                id: session.id,
+                created_at: session.created_at,
+                last_seen_at: session.last_seen_at,
+                user_agent: session.user_agent,
+                ip: session.ip,
+            })
+            .collect(),
+        oauth_consents: oauth_consents
+            .into_iter()
+            .map(|consent| OAuthConsentResponse {
+                client_id: consent.client_id,
+                scopes: consent.scopes,
+            })
+            .collect(),
+    };
+
+    Ok((
+        [(
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"account-export.json\"",
+        )],
+        Json(export),
+    ))
+}
+
+// Deletes the logged in user for good. The password is asked for again,
+// as nothing can be restored afterwards.
+#[tracing::instrument(name = "Deleting account", skip_all)]
+pub async fn delete_account(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, email, .. }: AuthenticatedUser,
+    jar: CookieJar,
+    Json(request): Json<DeleteAccountRequest>,
+) -> Result<(StatusCode, CookieJar), AuthAPIError> {
+    let Ok(current_password) = Password::parse(request.current_password) else {
+        return Err(AuthAPIError::InvalidCredentials);
+    };
+
+    verify_current_password(&email, &current_password, &state).await?;
+
+    purge_user(&user_id, &email, &state).await?;
+
+    Ok((StatusCode::NO_CONTENT, remove_session_cookies(jar)))
+}
+
+// Removes the user and everything stored about them from every store, banning their tokens
+// first so none of them outlives the user. Single-use tokens emailed to the user are keyed by
+// the token, not the user, and expire on their own.
+pub(crate) async fn purge_user(
+    user_id: &Uuid,
+    email: &Email,
+    state: &AppState,
+) -> Result<(), AuthAPIError> {
+    revoke_user_sessions(
+        user_id,
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .delete_user_tokens(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    state
+        .session_store
+        .write()
+        .await
+        .delete_user_sessions(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    state
+        .oauth_client_store
+        .write()
+        .await
+        .delete_consents(user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    match state
+        .two_fa_code_store
+        .write()
+        .await
+        .remove_code(email)
+        .await
+    {
+        Ok(()) | Err(TwoFACodeStoreError::LoginAttemptIdNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+    // Failed logins are remembered by address, which someone else may sign up with next
+    state
+        .login_attempt_store
+        .write()
+        .await
+        .clear(&LoginThrottleKey::Email(email.clone()))
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    match state.user_store.write().await.delete_user(email).await {
+        Ok(()) => Ok(()),
+        Err(UserStoreError::UserNotFound) => Err(AuthAPIError::UserNotFound),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+pub(crate) async fn verify_current_password(
+    email: &Email,
+    password: &Password,
+    state: &AppState,
+) -> Result<(), AuthAPIError> {
+    match state
+        .user_store
+        .read()
+        .await
+        .validate_user(email, password)
+        .await
+    {
+        Ok(()) => Ok(()),
+        Err(UserStoreError::InvalidCredentials) => Err(AuthAPIError::IncorrectCredentials),
+        Err(UserStoreError::UserNotFound) => Err(AuthAPIError::InvalidToken),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+// Issues an email change token and emails the confirmation link to the new address
+async fn send_email_change_link(
+    user_id: &Uuid,
+    new_email: &Email,
+    state: &AppState,
+) -> Result<(), AuthAPIError> {
+    let token = EmailVerificationToken::default();
+    state
+        .email_verification_token_store
+        .write()
+        .await
+        .add_email_change_token(token.clone(), *user_id, new_email.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let confirmation_link = format!(
+        "{}/?email_change_token={}",
+        AUTH_BASE_URL.as_str(),
+        token.as_ref().expose_secret()
+    );
+    state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            new_email,
+            "Confirm your new email address",
+            &format!(
+                "Use this link to make this the email address of your account: {}",
+                confirmation_link
+            ),
+        )
+        .await
+        .map_err(AuthAPIError::UnexpectedError)
+}
+
+async fn send_email_change_requested_notice(
+    email: &Email,
+    new_email: &Email,
+    state: &AppState,
+) -> Result<(), AuthAPIError> {
+    state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            email,
+            "A change of your email address was requested",
+            &format!(
+                "A confirmation link was sent to {}. This address keeps signing in until the \
+                 change is confirmed. If you did not request it, change your password right away.",
+                new_email.as_ref().expose_secret()
+            ),
+        )
+        .await
+        .map_err(AuthAPIError::UnexpectedError)
+}
+
+async fn send_email_changed_notice(email: &Email, state: &AppState) -> Result<(), AuthAPIError> {
+    state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            email,
+            "Your email address was changed",
+            "The email address of your account was changed and this address no longer signs in. \
+             If you did not make this change, contact support right away.",
+        )
+        .await
+        .map_err(AuthAPIError::UnexpectedError)
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    #[serde(rename = "currentPassword")]
+    pub current_password: Secret<String>,
+    #[serde(rename = "newPassword")]
+    pub new_password: Secret<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangeEmailRequest {
+    #[serde(rename = "currentPassword")]
+    pub current_password: Secret<String>,
+    #[serde(rename = "newEmail")]
+    pub new_email: Secret<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmEmailChangeRequest {
+    pub token: Secret<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteAccountRequest {
+    #[serde(rename = "currentPassword")]
+    pub current_password: Secret<String>,
+}
+
+// The service keeps no audit log, so there are no audit entries to export
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountExportResponse {
+    #[serde(rename = "exportedAt")]
+    pub exported_at: i64,
+    pub user: AccountExportUser,
+    #[serde(rename = "twoFA")]
+    pub two_fa: AccountExportTwoFA,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    pub sessions: Vec<SessionResponse>,
+    #[serde(rename = "oauthConsents")]
+    pub oauth_consents: Vec<OAuthConsentResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountExportUser {
+    pub id: Uuid,
+    pub email: String,
+    pub verified: bool,
+    pub disabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountExportTwoFA {
+    #[serde(rename = "requires2FA")]
+    pub requires_2fa: bool,
+    pub method: TwoFAMethod,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OAuthConsentResponse {
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+    pub scopes: Vec<String>,
+}
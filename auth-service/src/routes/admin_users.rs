@@ -0,0 +1,314 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::{AuthAPIError, Email, Password, TwoFAMethod, User, UserStoreError},
+    routes::{purge_user, send_password_reset_email},
+    utils::auth::revoke_user_sessions,
+    AppState,
+};
+
+// Page size of the user list when the request does not ask for one, and the largest allowed
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 100;
+
+// Lists users ordered by email. `nextCursor` is set while more users follow.
+#[tracing::instrument(name = "Listing users", skip_all)]
+pub async fn admin_list_users(
+    State(state): State<AppState>,
+    Query(params): Query<ListUsersParams>,
+) -> Result<Json<AdminUsersResponse>, AuthAPIError> {
+    let cursor = params
+        .cursor
+        .map(|cursor| Email::parse(Secret::new(cursor)))
+        .transpose()
+        .map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let search = params.search.as_deref().filter(|search| !search.is_empty());
+
+    let user_store = state.user_store.read().await;
+    // One extra user tells whether there is a next page
+    let mut users = user_store
+        .list_users(cursor.as_ref(), limit + 1, search)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    let total = user_store
+        .count_users(search)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let next_cursor = if users.len() > limit {
+        users.truncate(limit);
+        users
+            .last()
+            .map(|user| user.email.as_ref().expose_secret().to_owned())
+    } else {
+        None
+    };
+
+    Ok(Json(AdminUsersResponse {
+        users: users.iter().map(AdminUserResponse::from).collect(),
+        total,
+        next_cursor,
+    }))
+}
+
+#[tracing::instrument(name = "Retrieving user", skip_all)]
+pub async fn admin_get_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<Json<AdminUserDetailsResponse>, AuthAPIError> {
+    let user_id = parse_user_id(user_id)?;
+
+    let (user, user_roles) = {
+        let user_store = state.user_store.read().await;
+        let user = user_store
+            .get_user_by_id(&user_id)
+            .await
+            .map_err(user_store_error)?;
+        let user_roles = user_store
+            .get_user_roles(&user.email)
+            .await
+            .map_err(user_store_error)?;
+        (user, user_roles)
+    };
+    let active_sessions = state
+        .session_store
+        .read()
+        .await
+        .list_sessions(&user.id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+        .len();
+
+    Ok(Json(AdminUserDetailsResponse {
+        user: AdminUserResponse::from(&user),
+        roles: user_roles
+            .roles
+            .iter()
+            .map(|role| role.as_ref().to_owned())
+            .collect(),
+        permissions: user_roles
+            .permissions
+            .iter()
+            .map(|permission| permission.as_ref().to_owned())
+            .collect(),
+        active_sessions,
+    }))
+}
+
+// Keeps the user from logging in and ends the sessions they already have
+#[tracing::instrument(name = "Disabling user", skip_all)]
+pub async fn admin_disable_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    let user = get_user(&state, user_id).await?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .set_disabled(&user.email, true)
+        .await
+        .map_err(user_store_error)?;
+
+    revoke_user_sessions(
+        &user.id,
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Ends every session of a user, e.g. when the account was compromised
+#[tracing::instrument(name = "Logging out user everywhere", skip_all)]
+pub async fn admin_logout_all(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    let user = get_user(&state, user_id).await?;
+
+    revoke_user_sessions(
+        &user.id,
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok(StatusCode::OK)
+}
+
+#[tracing::instrument(name = "Enabling user", skip_all)]
+pub async fn admin_enable_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    let user = get_user(&state, user_id).await?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .set_disabled(&user.email, false)
+        .await
+        .map_err(user_store_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Replaces the password with a random one and emails the user a reset link,
+// for accounts whose password may have leaked
+#[tracing::instrument(name = "Forcing password reset", skip_all)]
+pub async fn admin_force_password_reset(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    let user = get_user(&state, user_id).await?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .update_password(&user.email, Password::default())
+        .await
+        .map_err(user_store_error)?;
+
+    revoke_user_sessions(
+        &user.id,
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+
+    send_password_reset_email(&user.email, &state).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Turns 2FA on or off. The user keeps the 2FA method they had.
+#[tracing::instrument(name = "Setting user 2FA requirement", skip_all)]
+pub async fn admin_set_requires_2fa(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    Json(request): Json<SetRequires2FARequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    let user = get_user(&state, user_id).await?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .set_requires_2fa(&user.email, request.requires_2fa)
+        .await
+        .map_err(user_store_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(name = "Deleting user", skip_all)]
+pub async fn admin_delete_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    let user = get_user(&state, user_id).await?;
+
+    purge_user(&user.id, &user.email, &state).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Admin routes name the user by id, which unlike the email never changes
+fn parse_user_id(user_id: String) -> Result<Uuid, AuthAPIError> {
+    Uuid::parse_str(&user_id).map_err(|_| AuthAPIError::InvalidCredentials)
+}
+
+async fn get_user(state: &AppState, user_id: String) -> Result<User, AuthAPIError> {
+    let user_id = parse_user_id(user_id)?;
+
+    state
+        .user_store
+        .read()
+        .await
+        .get_user_by_id(&user_id)
+        .await
+        .map_err(user_store_error)
+}
+
+fn user_store_error(error: UserStoreError) -> AuthAPIError {
+    match error {
+        UserStoreError::UserNotFound => AuthAPIError::UserNotFound,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListUsersParams {
+    pub search: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct SetRequires2FARequest {
+    #[serde(rename = "requires2FA")]
+    pub requires_2fa: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUserResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub verified: bool,
+    pub disabled: bool,
+    #[serde(rename = "requires2FA")]
+    pub requires_2fa: bool,
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: TwoFAMethod,
+}
+
+impl From<&User> for AdminUserResponse {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id,
+            email: user.email.as_ref().expose_secret().to_owned(),
+            verified: user.verified,
+            disabled: user.disabled,
+            requires_2fa: user.requires_2fa,
+            two_fa_method: user.two_fa_method,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUsersResponse {
+    pub users: Vec<AdminUserResponse>,
+    // How many users match the search, across all pages
+    pub total: u64,
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminUserDetailsResponse {
+    #[serde(flatten)]
+    pub user: AdminUserResponse,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    #[serde(rename = "activeSessions")]
+    pub active_sessions: usize,
+}
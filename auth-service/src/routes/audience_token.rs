@@ -0,0 +1,47 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::AuthAPIError,
+    routes::user_roles,
+    utils::{auth::generate_audience_token, AuthenticatedUser, JWT_AUDIENCES},
+    AppState,
+};
+
+// Issues a token for another relying app, in exchange for the auth token
+#[tracing::instrument(name = "Issuing audience token", skip_all)]
+pub async fn audience_token(
+    State(state): State<AppState>,
+    AuthenticatedUser {
+        user_id, claims, ..
+    }: AuthenticatedUser,
+    Json(request): Json<AudienceTokenRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    if !JWT_AUDIENCES.contains(&request.audience) {
+        return Err(AuthAPIError::UnknownAudience);
+    }
+
+    // The token belongs to the same session, so revoking the session revokes it too
+    let roles = user_roles(&state, &user_id).await?;
+    let token = generate_audience_token(
+        &user_id,
+        &claims.sid,
+        &roles,
+        &request.audience,
+        state.jwt_key_ring.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok((StatusCode::OK, Json(AudienceTokenResponse { token })))
+}
+
+#[derive(Deserialize)]
+pub struct AudienceTokenRequest {
+    pub audience: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AudienceTokenResponse {
+    pub token: String,
+}
@@ -0,0 +1,46 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+use secrecy::ExposeSecret;
+
+use crate::{
+    domain::AuthAPIError,
+    utils::{auth::validate_token, request_auth_token, token_user},
+    AppState,
+};
+
+// Lets a reverse proxy guard any upstream (nginx `auth_request`, Traefik `forwardAuth`).
+// Answers 200 with the user in `X-Auth-*` headers for the proxy to pass on, or 401.
+#[tracing::instrument(name = "Forward auth", skip_all)]
+pub async fn forward_auth(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    // Proxies only tell 2xx, 401 and 403 apart, so a missing token is a 401 as well
+    let token = request_auth_token(&headers).ok_or(AuthAPIError::InvalidToken)?;
+
+    let claims = validate_token(
+        &token,
+        state.jwt_key_ring.clone(),
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .map_err(|_| AuthAPIError::InvalidToken)?;
+    let user = token_user(&state, &claims).await?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            ("X-Auth-User", claims.sub.clone()),
+            (
+                "X-Auth-Email",
+                user.email.as_ref().expose_secret().to_owned(),
+            ),
+            ("X-Auth-Roles", claims.roles.join(",")),
+        ],
+    ))
+}
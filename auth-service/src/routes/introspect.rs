@@ -0,0 +1,200 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Form, Json,
+};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{AuthAPIError, OAuthClient, OAuthError, RefreshToken, RefreshTokenStoreError},
+    routes::authenticate_client,
+    utils::auth::{validate_access_token, validate_client_access_token, Claims},
+    AppState,
+};
+
+// Tells a resource server whether a token is active and whom it was issued to (RFC 7662).
+// Unlike `/verify-token`, the caller must be a confidential client.
+#[tracing::instrument(name = "Introspecting token", skip_all)]
+pub async fn introspect(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<IntrospectRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let client = authenticate_client(
+        &state,
+        &headers,
+        request.client_id.as_ref(),
+        request.client_secret.as_ref(),
+    )
+    .await?;
+    if !client.is_confidential() {
+        return Err(AuthAPIError::OAuth(OAuthError::InvalidClient));
+    }
+
+    let Some(token) = request.token else {
+        return Err(AuthAPIError::OAuth(OAuthError::InvalidRequest));
+    };
+
+    // Refresh tokens and anything else we did not issue as an access token are inactive
+    let response = match validate_access_token(
+        token.expose_secret(),
+        state.jwt_key_ring.clone(),
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    {
+        Ok(claims) => IntrospectionResponse::from(claims),
+        Err(_) => IntrospectionResponse::inactive(),
+    };
+
+    Ok(([(header::CACHE_CONTROL, "no-store")], Json(response)))
+}
+
+// Lets a client revoke one of its own access or refresh tokens (RFC 7009). Unknown tokens
+// and tokens of other clients are ignored, so the response tells nothing about them.
+#[tracing::instrument(name = "Revoking token", skip_all)]
+pub async fn revoke(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<RevokeRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    let client = authenticate_client(
+        &state,
+        &headers,
+        request.client_id.as_ref(),
+        request.client_secret.as_ref(),
+    )
+    .await?;
+
+    let Some(token) = request.token else {
+        return Err(AuthAPIError::OAuth(OAuthError::InvalidRequest));
+    };
+
+    // Refresh tokens are opaque, access tokens are JWTs
+    match RefreshToken::parse(token.clone()) {
+        Ok(refresh_token) => revoke_refresh_token(&state, &client, &refresh_token).await?,
+        Err(_) => revoke_access_token(&state, &client, &token).await?,
+    }
+
+    Ok(StatusCode::OK)
+}
+
+// Revoking a refresh token ends the client's session, taking its access tokens along
+async fn revoke_refresh_token(
+    state: &AppState,
+    client: &OAuthClient,
+    token: &RefreshToken,
+) -> Result<(), AuthAPIError> {
+    let mut refresh_token_store = state.refresh_token_store.write().await;
+    let record = match refresh_token_store.get_refresh_token(token).await {
+        Ok(record) => record,
+        Err(RefreshTokenStoreError::TokenNotFound) => return Ok(()),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    if record.client_id.as_deref() != Some(client.client_id.as_str()) {
+        return Ok(());
+    }
+
+    refresh_token_store
+        .revoke_family(&record.family_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    state
+        .session_store
+        .write()
+        .await
+        .revoke_session(&record.family_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+async fn revoke_access_token(
+    state: &AppState,
+    client: &OAuthClient,
+    token: &Secret<String>,
+) -> Result<(), AuthAPIError> {
+    let Ok(claims) = validate_client_access_token(
+        token.expose_secret(),
+        state.jwt_key_ring.clone(),
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    else {
+        return Ok(());
+    };
+    if claims.client_id.as_deref() != Some(client.client_id.as_str()) {
+        return Ok(());
+    }
+
+    state
+        .banned_token_store
+        .write()
+        .await
+        .add_banned_token(&claims.jti, claims.exp as i64)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))
+}
+
+// A `token_type_hint` is ignored, only access tokens can be active
+#[derive(Deserialize)]
+pub struct IntrospectRequest {
+    pub token: Option<Secret<String>>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<Secret<String>>,
+}
+
+// A `token_type_hint` is ignored, the token's format tells refresh and access tokens apart
+#[derive(Deserialize)]
+pub struct RevokeRequest {
+    pub token: Option<Secret<String>>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<Secret<String>>,
+}
+
+// Field names follow RFC 7662 section 2.2. An inactive token has only `active` set.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+}
+
+impl IntrospectionResponse {
+    pub fn inactive() -> Self {
+        Self::default()
+    }
+}
+
+impl From<Claims> for IntrospectionResponse {
+    fn from(claims: Claims) -> Self {
+        Self {
+            active: true,
+            scope: claims.scope,
+            client_id: claims.client_id,
+            token_type: Some("Bearer".to_owned()),
+            sub: Some(claims.sub),
+            aud: Some(claims.aud),
+            iss: Some(claims.iss),
+            exp: Some(claims.exp),
+            iat: Some(claims.iat),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use axum::{extract::State, http::header, response::IntoResponse, Json};
+
+use crate::AppState;
+
+// Public keys for verifying auth tokens locally, empty while tokens are signed with a shared secret
+#[tracing::instrument(name = "Publishing JWKS", skip_all)]
+pub async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(state.jwt_key_ring.read().await.jwks()),
+    )
+}
@@ -1,22 +1,32 @@
+use std::net::IpAddr;
+
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use axum_extra::extract::CookieJar;
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::{
-    domain::{AuthAPIError, Email, LoginAttemptId, Password, TwoFACode},
-    utils::auth::generate_auth_cookie,
+    domain::{
+        login_amr, AuthAPIError, Email, LoginAttemptId, LoginThrottleKey, Password, TwoFACode,
+        TwoFAMethod, UnverifiedLoginPolicy,
+    },
+    routes::{send_verification_email, start_session, ResponseMode, SessionTokensResponse},
+    utils::{ClientIp, UserAgent},
     AppState,
 };
 
 #[tracing::instrument(name = "Logging in", skip_all)]
 pub async fn login(
     State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    UserAgent(user_agent): UserAgent,
     jar: CookieJar,
     Json(request): Json<LoginRequest>,
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
     let email = request.email;
     let password = request.password;
+    let response_mode = request.response_mode;
 
     // Validate input
     let Ok(email) = Email::parse(email) else {
@@ -27,20 +37,107 @@ pub async fn login(
         return Err(AuthAPIError::InvalidCredentials);
     };
 
-    let user_store = &state.user_store.read().await;
-    if user_store.validate_user(&email, &password).await.is_err() {
-        return Err(AuthAPIError::IncorrectCredentials);
+    // Throttled clients are turned away before the expensive password check
+    let throttle_keys: Vec<LoginThrottleKey> = [
+        Some(LoginThrottleKey::Email(email.clone())),
+        client_ip.map(LoginThrottleKey::Ip),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    check_login_throttle(&throttle_keys, &state).await?;
+
+    // The user store is only held for the checks, starting the session reads it again
+    let user = {
+        let user_store = state.user_store.read().await;
+        if user_store.validate_user(&email, &password).await.is_err() {
+            drop(user_store);
+            record_login_failure(&throttle_keys, &state).await?;
+            return Err(AuthAPIError::IncorrectCredentials);
+        }
+        user_store.get_user(&email).await
+    };
+
+    // The address keeps its count, otherwise any valid account could reset it
+    if let Err(e) = state
+        .login_attempt_store
+        .write()
+        .await
+        .clear(&LoginThrottleKey::Email(email.clone()))
+        .await
+    {
+        return Err(AuthAPIError::UnexpectedError(e.into()));
     }
 
-    let Ok(user) = user_store.get_user(&email).await else {
+    let Ok(user) = user else {
         return Err(AuthAPIError::IncorrectCredentials);
     };
 
+    // Only tell the user about it once they proved they know the password
+    if user.disabled {
+        return Err(AuthAPIError::UserDisabled);
+    }
+
+    if !user.verified && state.unverified_login_policy == UnverifiedLoginPolicy::Refuse {
+        send_verification_email(&user.email, &state).await?;
+        return Err(AuthAPIError::EmailNotVerified);
+    }
+
     // Handle request based on user's 2FA configuration
-    match user.requires_2fa {
-        true => handle_2fa(&user.email, &state, jar).await,
-        false => handle_no_2fa(&user.email, jar).await,
+    match user.login_two_fa_method(state.unverified_login_policy) {
+        Some(TwoFAMethod::Email) => handle_2fa(&user.email, &state, jar).await,
+        Some(TwoFAMethod::Totp) => handle_totp(&user.email, &state, jar).await,
+        None => handle_no_2fa(&user.id, user_agent, client_ip, response_mode, &state, jar).await,
+    }
+}
+
+#[tracing::instrument(name = "Checking login throttle", skip_all)]
+async fn check_login_throttle(
+    keys: &[LoginThrottleKey],
+    state: &AppState,
+) -> Result<(), AuthAPIError> {
+    let login_attempt_store = state.login_attempt_store.read().await;
+
+    let mut retry_after_seconds = None;
+    for key in keys {
+        let lockout = login_attempt_store
+            .get_lockout(key)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        retry_after_seconds = retry_after_seconds.max(lockout);
+    }
+
+    match retry_after_seconds {
+        Some(retry_after_seconds) => Err(AuthAPIError::TooManyLoginAttempts {
+            retry_after_seconds,
+        }),
+        None => Ok(()),
+    }
+}
+
+#[tracing::instrument(name = "Recording login failure", skip_all)]
+async fn record_login_failure(
+    keys: &[LoginThrottleKey],
+    state: &AppState,
+) -> Result<(), AuthAPIError> {
+    let mut login_attempt_store = state.login_attempt_store.write().await;
+
+    for key in keys {
+        let policy = state.login_throttle.policy_for(key);
+        let failures = login_attempt_store
+            .record_failure(key, policy.lockout_seconds)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+        if let Some(delay) = policy.delay_for(failures) {
+            login_attempt_store
+                .lock_out(key, delay)
+                .await
+                .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        }
     }
+
+    Ok(())
 }
 
 #[tracing::instrument(name = "Logging in with 2fa", skip_all)]
@@ -64,6 +161,32 @@ async fn handle_2fa(
     if let Err(e) = email_client
         .send_email(email, "Your 2FA Code", two_fa_code.as_ref().expose_secret())
         .await
+    {
+        return Err(AuthAPIError::UnexpectedError(e));
+    }
+
+    let response = Json(LoginResponse::TwoFactorAuth(TwoFactorAuthResponse {
+        message: "2FA required".to_owned(),
+        login_attempt_id: login_atempt_id.as_ref().expose_secret().to_owned(),
+        two_fa_method: TwoFAMethod::Email,
+    }));
+
+    Ok((jar, (StatusCode::PARTIAL_CONTENT, response)))
+}
+
+#[tracing::instrument(name = "Logging in with totp", skip_all)]
+async fn handle_totp(
+    email: &Email,
+    state: &AppState,
+    jar: CookieJar,
+) -> Result<(CookieJar, (StatusCode, Json<LoginResponse>)), AuthAPIError> {
+    let login_atempt_id = LoginAttemptId::default();
+
+    // The stored code is never sent, `verify_2fa` checks the authenticator app code instead
+    let mut two_fa_code_store = state.two_fa_code_store.write().await;
+    if let Err(e) = two_fa_code_store
+        .add_code(email.clone(), login_atempt_id.clone(), TwoFACode::default())
+        .await
     {
         return Err(AuthAPIError::UnexpectedError(e.into()));
     }
@@ -71,6 +194,7 @@ async fn handle_2fa(
     let response = Json(LoginResponse::TwoFactorAuth(TwoFactorAuthResponse {
         message: "2FA required".to_owned(),
         login_attempt_id: login_atempt_id.as_ref().expose_secret().to_owned(),
+        two_fa_method: TwoFAMethod::Totp,
     }));
 
     Ok((jar, (StatusCode::PARTIAL_CONTENT, response)))
@@ -78,27 +202,38 @@ async fn handle_2fa(
 
 #[tracing::instrument(name = "Logging in without 2fa", skip_all)]
 async fn handle_no_2fa(
-    email: &Email,
+    user_id: &Uuid,
+    user_agent: Option<String>,
+    client_ip: Option<IpAddr>,
+    response_mode: ResponseMode,
+    state: &AppState,
     jar: CookieJar,
 ) -> Result<(CookieJar, (StatusCode, Json<LoginResponse>)), AuthAPIError> {
+    let (updated_jar, tokens) = start_session(
+        user_id,
+        login_amr(false),
+        user_agent,
+        client_ip,
+        response_mode,
+        state,
+        jar,
+    )
+    .await?;
+
     // Return success response
-    let auth_cookie = match generate_auth_cookie(email) {
-        Ok(cookie) => cookie,
-        Err(e) => return Err(AuthAPIError::UnexpectedError(e)),
+    let response = match tokens {
+        Some(tokens) => LoginResponse::Tokens(tokens),
+        None => LoginResponse::RegularAuth,
     };
-
-    let updated_jar = jar.add(auth_cookie);
-
-    Ok((
-        updated_jar,
-        (StatusCode::OK, Json(LoginResponse::RegularAuth)),
-    ))
+    Ok((updated_jar, (StatusCode::OK, Json(response))))
 }
 
 #[derive(Deserialize)]
 pub struct LoginRequest {
     pub email: Secret<String>,
     pub password: Secret<String>,
+    #[serde(default, rename = "responseMode")]
+    pub response_mode: ResponseMode,
 }
 
 // The login route can return 2 possible success responses.
@@ -108,6 +243,8 @@ pub struct LoginRequest {
 pub enum LoginResponse {
     RegularAuth,
     TwoFactorAuth(TwoFactorAuthResponse),
+    // Returned instead of the cookies in the token response mode
+    Tokens(SessionTokensResponse),
 }
 
 // If a user requires 2FA, this JSON body should be returned!
@@ -116,4 +253,6 @@ pub struct TwoFactorAuthResponse {
     pub message: String,
     #[serde(rename = "loginAttemptId")]
     pub login_attempt_id: String,
+    #[serde(rename = "twoFAMethod")]
+    pub two_fa_method: TwoFAMethod,
 }
@@ -1,44 +1,108 @@
 use axum::{extract::State, http::StatusCode};
 use axum_extra::extract::cookie::Cookie;
 use axum_extra::extract::CookieJar;
+use secrecy::Secret;
 
 use crate::{
-    domain::AuthAPIError,
-    utils::{auth::validate_token, constants::JWT_COOKIE_NAME},
+    domain::{AuthAPIError, RefreshToken, RefreshTokenStoreError},
+    utils::{
+        auth::{revoke_user_sessions, Claims},
+        constants::{JWT_COOKIE_NAME, REFRESH_COOKIE_NAME},
+        AuthenticatedUser,
+    },
     AppState,
 };
 
 #[tracing::instrument(name = "Logging out", skip_all)]
 pub async fn logout(
     State(state): State<AppState>,
+    AuthenticatedUser { claims, .. }: AuthenticatedUser,
     jar: CookieJar,
 ) -> Result<(StatusCode, CookieJar), AuthAPIError> {
-    // Retrieve JWT cookie from the `CookieJar`
-    let Some(cookie) = jar.get(JWT_COOKIE_NAME) else {
-        return Err(AuthAPIError::MissingToken);
-    };
+    let updated_jar = log_out_session(&state, jar, &claims).await?;
 
-    // Validate JWT
-    let token = cookie.value().to_owned();
-    let Ok(_claims) = validate_token(&token, state.banned_token_store.clone()).await else {
-        return Err(AuthAPIError::InvalidToken);
-    };
+    Ok((StatusCode::OK, updated_jar))
+}
 
+// Ends the session of the auth token with the given claims and removes the session cookies
+pub(crate) async fn log_out_session(
+    state: &AppState,
+    jar: CookieJar,
+    claims: &Claims,
+) -> Result<CookieJar, AuthAPIError> {
     // Add the token to the banned token store
     if let Err(e) = state
         .banned_token_store
         .write()
         .await
-        .add_banned_token(token)
+        .add_banned_token(&claims.jti, claims.exp as i64)
         .await
     {
         return Err(AuthAPIError::UnexpectedError(e.into()));
     }
 
-    // Delete JWT cookie from the `CookieJar`
+    // Revoke the refresh token family so the session cannot be silently renewed
+    revoke_refresh_token_family(state, &jar).await?;
+    state
+        .session_store
+        .write()
+        .await
+        .revoke_session(&claims.sid)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(remove_session_cookies(jar))
+}
+
+// Ends every session of the user, on all devices
+#[tracing::instrument(name = "Logging out everywhere", skip_all)]
+pub async fn logout_all(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    jar: CookieJar,
+) -> Result<(StatusCode, CookieJar), AuthAPIError> {
+    revoke_user_sessions(
+        &user_id,
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+    revoke_refresh_token_family(&state, &jar).await?;
+
+    Ok((StatusCode::OK, remove_session_cookies(jar)))
+}
+
+async fn revoke_refresh_token_family(
+    state: &AppState,
+    jar: &CookieJar,
+) -> Result<(), AuthAPIError> {
+    let Some(refresh_cookie) = jar.get(REFRESH_COOKIE_NAME) else {
+        return Ok(());
+    };
+    let Ok(refresh_token) = RefreshToken::parse(Secret::new(refresh_cookie.value().to_owned()))
+    else {
+        return Ok(());
+    };
+
+    let mut refresh_token_store = state.refresh_token_store.write().await;
+    match refresh_token_store.get_refresh_token(&refresh_token).await {
+        Ok(record) => refresh_token_store
+            .revoke_family(&record.family_id)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into())),
+        Err(RefreshTokenStoreError::TokenNotFound) => Ok(()),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+// Delete JWT and refresh token cookies from the `CookieJar`
+pub(crate) fn remove_session_cookies(jar: CookieJar) -> CookieJar {
     let mut cookie_for_removal = Cookie::from(JWT_COOKIE_NAME);
     cookie_for_removal.set_path("/"); // Needed for https context removal
-    let jar = jar.remove(cookie_for_removal);
+    let mut refresh_cookie_for_removal = Cookie::from(REFRESH_COOKIE_NAME);
+    refresh_cookie_for_removal.set_path("/");
 
-    Ok((StatusCode::OK, jar))
+    jar.remove(cookie_for_removal)
+        .remove(refresh_cookie_for_removal)
 }
@@ -1,12 +1,44 @@
+mod account;
+mod admin_users;
+mod audience_token;
+mod forward_auth;
+mod introspect;
+mod jwks;
 mod login;
 mod logout;
+mod oauth;
+mod oidc;
+mod password_reset;
+mod recovery_codes;
+mod refresh_token;
+mod roles;
+mod sessions;
+mod signing_keys;
 mod signup;
+mod totp;
 mod verify_2fa;
+mod verify_email;
 mod verify_token;
 
 // re-export items from sub-modules
+pub use account::*;
+pub use admin_users::*;
+pub use audience_token::*;
+pub use forward_auth::*;
+pub use introspect::*;
+pub use jwks::*;
 pub use login::*;
 pub use logout::*;
+pub use oauth::*;
+pub use oidc::*;
+pub use password_reset::*;
+pub use recovery_codes::*;
+pub use refresh_token::*;
+pub use roles::*;
+pub use sessions::*;
+pub use signing_keys::*;
 pub use signup::*;
+pub use totp::*;
 pub use verify_2fa::*;
+pub use verify_email::*;
 pub use verify_token::*;
@@ -0,0 +1,597 @@
+use std::net::IpAddr;
+
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    Extension, Form, Json,
+};
+use data_encoding::BASE64;
+use reqwest::Url;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        is_valid_code_challenge, parse_scope, verify_code_verifier, AuthAPIError,
+        AuthorizationCode, AuthorizationCodeStoreError, AuthorizationGrant, OAuthClient,
+        OAuthClientStoreError, OAuthError, RefreshToken, RefreshTokenRecord, Session,
+        SessionStoreError, OPENID_SCOPE, PKCE_METHOD_S256,
+    },
+    routes::use_refresh_token,
+    utils::{
+        auth::{
+            generate_client_access_token, generate_id_token, generate_refresh_token,
+            TOKEN_TTL_SECONDS,
+        },
+        AuthenticatedUser, ClientIp, UserAgent,
+    },
+    AppState, IndexTemplate,
+};
+
+#[tracing::instrument(name = "Registering OAuth client", skip_all)]
+pub async fn register_oauth_client(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterOAuthClientRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let Ok((client, client_secret)) =
+        OAuthClient::new(request.name, request.redirect_uris, request.confidential)
+    else {
+        return Err(AuthAPIError::InvalidOAuthClient);
+    };
+
+    let client_id = client.client_id.clone();
+    state
+        .oauth_client_store
+        .write()
+        .await
+        .add_client(client)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let response = Json(RegisterOAuthClientResponse {
+        client_id,
+        client_secret: client_secret.map(|secret| secret.expose_secret().to_owned()),
+    });
+
+    Ok((StatusCode::CREATED, response))
+}
+
+// Starts the authorization code flow (RFC 6749 section 4.1). Users who are not logged in
+// get the login form, which reloads this page once they are, then they are asked for consent.
+#[tracing::instrument(name = "Authorizing OAuth client", skip_all)]
+pub async fn authorize(
+    State(state): State<AppState>,
+    Extension(prefix): Extension<String>,
+    user: Option<AuthenticatedUser>,
+    Query(params): Query<AuthorizeParams>,
+) -> Result<Response, AuthAPIError> {
+    let request = match AuthorizationRequest::check(&state, params).await? {
+        Ok(request) => request,
+        Err(redirect) => return Ok(redirect),
+    };
+
+    let Some(AuthenticatedUser {
+        user_id, claims, ..
+    }) = user
+    else {
+        return Ok(render_index(prefix, None));
+    };
+
+    let has_consent = state
+        .oauth_client_store
+        .read()
+        .await
+        .has_consent(&user_id, &request.client.client_id, &request.scopes)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    if !has_consent {
+        return Ok(render_index(prefix, Some(ConsentPrompt::new(&request))));
+    }
+
+    issue_authorization_code(&state, request, user_id, claims.sid).await
+}
+
+// Handles the user's answer to the consent prompt. The auth cookie is SameSite=Lax,
+// so other sites cannot post a decision on the user's behalf.
+#[tracing::instrument(name = "Deciding on OAuth consent", skip_all)]
+pub async fn authorize_decision(
+    State(state): State<AppState>,
+    user: Result<AuthenticatedUser, AuthAPIError>,
+    Form(form): Form<AuthorizeDecision>,
+) -> Result<Response, AuthAPIError> {
+    let request = match AuthorizationRequest::check(&state, form.params).await? {
+        Ok(request) => request,
+        Err(redirect) => return Ok(redirect),
+    };
+
+    let AuthenticatedUser {
+        user_id, claims, ..
+    } = user?;
+
+    if form.decision != "allow" {
+        return redirect_to_client(
+            &request.redirect_uri,
+            &[("error", OAuthError::AccessDenied.as_str())],
+            request.state.as_deref(),
+        );
+    }
+
+    state
+        .oauth_client_store
+        .write()
+        .await
+        .grant_consent(&user_id, &request.client.client_id, &request.scopes)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    issue_authorization_code(&state, request, user_id, claims.sid).await
+}
+
+// Exchanges an authorization code or a refresh token for tokens (RFC 6749 sections 4.1.3 and 6)
+#[tracing::instrument(name = "Issuing OAuth tokens", skip_all)]
+pub async fn token(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    UserAgent(user_agent): UserAgent,
+    headers: HeaderMap,
+    Form(request): Form<TokenRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let client = authenticate_client(
+        &state,
+        &headers,
+        request.client_id.as_ref(),
+        request.client_secret.as_ref(),
+    )
+    .await?;
+
+    let response = match request.grant_type.as_deref() {
+        Some("authorization_code") => {
+            exchange_authorization_code(&state, &client, request, user_agent, client_ip).await?
+        }
+        Some("refresh_token") => refresh_client_token(&state, &client, request).await?,
+        Some(_) => return Err(AuthAPIError::OAuth(OAuthError::UnsupportedGrantType)),
+        None => return Err(AuthAPIError::OAuth(OAuthError::InvalidRequest)),
+    };
+
+    // Responses carrying tokens must not be cached (RFC 6749 section 5.1)
+    Ok((
+        [
+            (header::CACHE_CONTROL, "no-store"),
+            (header::PRAGMA, "no-cache"),
+        ],
+        Json(response),
+    ))
+}
+
+// An authorization request whose client, redirect URI and parameters were checked
+struct AuthorizationRequest {
+    client: OAuthClient,
+    redirect_uri: String,
+    scopes: Vec<String>,
+    state: Option<String>,
+    code_challenge: String,
+    nonce: Option<String>,
+}
+
+impl AuthorizationRequest {
+    // Problems with the client or its redirect URI are shown to the user, as redirecting to
+    // an unregistered URI would make this an open redirector. Other problems are redirected
+    // back to the client.
+    async fn check(
+        state: &AppState,
+        params: AuthorizeParams,
+    ) -> Result<Result<Self, Response>, AuthAPIError> {
+        let client_id = params
+            .client_id
+            .ok_or(AuthAPIError::OAuth(OAuthError::InvalidRequest))?;
+        let client = match state
+            .oauth_client_store
+            .read()
+            .await
+            .get_client(&client_id)
+            .await
+        {
+            Ok(client) => client,
+            Err(OAuthClientStoreError::ClientNotFound) => {
+                return Err(AuthAPIError::OAuth(OAuthError::InvalidClient))
+            }
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        };
+        let redirect_uri = match params.redirect_uri {
+            Some(redirect_uri) if client.allows_redirect_uri(&redirect_uri) => redirect_uri,
+            _ => return Err(AuthAPIError::OAuth(OAuthError::InvalidRequest)),
+        };
+
+        let error = if params.response_type.as_deref() != Some("code") {
+            Some(OAuthError::UnsupportedResponseType)
+        } else if params.code_challenge_method.as_deref() != Some(PKCE_METHOD_S256)
+            || !params
+                .code_challenge
+                .as_deref()
+                .is_some_and(is_valid_code_challenge)
+        {
+            // PKCE is required from every client
+            Some(OAuthError::InvalidRequest)
+        } else {
+            None
+        };
+        let scopes = parse_scope(params.scope.as_deref().unwrap_or_default());
+
+        match (error, scopes, params.code_challenge) {
+            (None, Ok(scopes), Some(code_challenge)) => Ok(Ok(Self {
+                client,
+                redirect_uri,
+                scopes,
+                state: params.state,
+                code_challenge,
+                nonce: params.nonce,
+            })),
+            (error, _, _) => {
+                let error = error.unwrap_or(OAuthError::InvalidScope);
+                redirect_to_client(
+                    &redirect_uri,
+                    &[("error", error.as_str())],
+                    params.state.as_deref(),
+                )
+                .map(Err)
+            }
+        }
+    }
+}
+
+// The codes are short-lived and single-use, they only stand in for the user's consent
+async fn issue_authorization_code(
+    state: &AppState,
+    request: AuthorizationRequest,
+    user_id: Uuid,
+    session_id: String,
+) -> Result<Response, AuthAPIError> {
+    // Id tokens tell the client when and how the user logged in
+    let session = state
+        .session_store
+        .read()
+        .await
+        .get_session(&session_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let code = AuthorizationCode::default();
+    let grant = AuthorizationGrant {
+        client_id: request.client.client_id,
+        user_id,
+        redirect_uri: request.redirect_uri.clone(),
+        scope: request.scopes.join(" "),
+        code_challenge: request.code_challenge,
+        session_id,
+        nonce: request.nonce,
+        auth_time: session.created_at,
+        amr: session.amr,
+    };
+    state
+        .authorization_code_store
+        .write()
+        .await
+        .add_code(code.clone(), grant)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    redirect_to_client(
+        &request.redirect_uri,
+        &[("code", code.as_ref().expose_secret())],
+        request.state.as_deref(),
+    )
+}
+
+// Sends the browser back to the client with the given query parameters and the client's state
+pub(crate) fn redirect_to_client(
+    redirect_uri: &str,
+    params: &[(&str, &str)],
+    state: Option<&str>,
+) -> Result<Response, AuthAPIError> {
+    let mut url = Url::parse(redirect_uri).map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    {
+        let mut query = url.query_pairs_mut();
+        query.extend_pairs(params);
+        if let Some(state) = state {
+            query.append_pair("state", state);
+        }
+    }
+    Ok(Redirect::to(url.as_str()).into_response())
+}
+
+fn render_index(prefix: String, consent: Option<ConsentPrompt>) -> Response {
+    let template = IndexTemplate { prefix, consent };
+    match template.render() {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => AuthAPIError::UnexpectedError(e.into()).into_response(),
+    }
+}
+
+// Confidential clients authenticate with HTTP Basic or with the secret in the form body,
+// public clients only identify themselves (RFC 6749 section 2.3.1)
+pub(crate) async fn authenticate_client(
+    state: &AppState,
+    headers: &HeaderMap,
+    client_id: Option<&String>,
+    client_secret: Option<&Secret<String>>,
+) -> Result<OAuthClient, AuthAPIError> {
+    let invalid_client = AuthAPIError::OAuth(OAuthError::InvalidClient);
+
+    let basic_credentials = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "));
+    let (client_id, client_secret) = match basic_credentials {
+        Some(credentials) => {
+            let Some(credentials) = BASE64
+                .decode(credentials.as_bytes())
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+            else {
+                return Err(invalid_client);
+            };
+            let Some((client_id, client_secret)) = credentials.split_once(':') else {
+                return Err(invalid_client);
+            };
+            (
+                client_id.to_owned(),
+                Some(Secret::new(client_secret.to_owned())),
+            )
+        }
+        None => match client_id {
+            Some(client_id) => (client_id.clone(), client_secret.cloned()),
+            None => return Err(invalid_client),
+        },
+    };
+
+    let client = match state
+        .oauth_client_store
+        .read()
+        .await
+        .get_client(&client_id)
+        .await
+    {
+        Ok(client) => client,
+        Err(OAuthClientStoreError::ClientNotFound) => return Err(invalid_client),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    let is_authenticated = match &client_secret {
+        Some(client_secret) => client.verify_secret(client_secret),
+        None => !client.is_confidential(),
+    };
+    if !is_authenticated {
+        return Err(invalid_client);
+    }
+
+    Ok(client)
+}
+
+async fn exchange_authorization_code(
+    state: &AppState,
+    client: &OAuthClient,
+    request: TokenRequest,
+    user_agent: Option<String>,
+    client_ip: Option<IpAddr>,
+) -> Result<TokenResponse, AuthAPIError> {
+    let invalid_grant = AuthAPIError::OAuth(OAuthError::InvalidGrant);
+
+    let (Some(code), Some(code_verifier)) = (request.code, request.code_verifier) else {
+        return Err(AuthAPIError::OAuth(OAuthError::InvalidRequest));
+    };
+    let Ok(code) = AuthorizationCode::parse(code) else {
+        return Err(invalid_grant);
+    };
+
+    let grant = match state
+        .authorization_code_store
+        .write()
+        .await
+        .consume_code(&code)
+        .await
+    {
+        Ok(grant) => grant,
+        Err(AuthorizationCodeStoreError::CodeNotFound) => return Err(invalid_grant),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    // The code is bound to the client and redirect URI it was issued for
+    if grant.client_id != client.client_id
+        || request.redirect_uri.as_deref() != Some(grant.redirect_uri.as_str())
+        || !verify_code_verifier(code_verifier.expose_secret(), &grant.code_challenge)
+    {
+        return Err(invalid_grant);
+    }
+
+    // The user may have logged out since authorizing the client
+    match state
+        .session_store
+        .read()
+        .await
+        .get_session(&grant.session_id)
+        .await
+    {
+        Ok(session) if !session.revoked => {}
+        Ok(_) | Err(SessionStoreError::SessionNotFound) => return Err(invalid_grant),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    // Each authorized client gets a session of its own, which the user can revoke like a device
+    let record = RefreshTokenRecord::for_client(
+        grant.user_id,
+        client.client_id.clone(),
+        grant.scope.clone(),
+    );
+    let session_id = record.family_id.clone();
+    let session = Session::new(
+        session_id.clone(),
+        grant.user_id,
+        grant.amr.clone(),
+        user_agent,
+        client_ip.map(|ip| ip.to_string()),
+    );
+    state
+        .session_store
+        .write()
+        .await
+        .add_session(session)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let mut response = issue_client_tokens(state, client, record).await?;
+
+    // OpenID Connect clients also learn who logged in
+    if response.scope.split(' ').any(|scope| scope == OPENID_SCOPE) {
+        let id_token = generate_id_token(&grant, &session_id, state.jwt_key_ring.clone())
+            .await
+            .map_err(AuthAPIError::UnexpectedError)?;
+        response.id_token = Some(id_token);
+    }
+
+    Ok(response)
+}
+
+async fn refresh_client_token(
+    state: &AppState,
+    client: &OAuthClient,
+    request: TokenRequest,
+) -> Result<TokenResponse, AuthAPIError> {
+    let Some(token) = request.refresh_token else {
+        return Err(AuthAPIError::OAuth(OAuthError::InvalidRequest));
+    };
+    let Ok(token) = RefreshToken::parse(token) else {
+        return Err(AuthAPIError::OAuth(OAuthError::InvalidGrant));
+    };
+
+    let record = match use_refresh_token(state, &token, Some(&client.client_id)).await {
+        Ok(record) => record,
+        Err(AuthAPIError::InvalidToken) => {
+            return Err(AuthAPIError::OAuth(OAuthError::InvalidGrant))
+        }
+        Err(e) => return Err(e),
+    };
+
+    issue_client_tokens(state, client, record.rotate()).await
+}
+
+async fn issue_client_tokens(
+    state: &AppState,
+    client: &OAuthClient,
+    record: RefreshTokenRecord,
+) -> Result<TokenResponse, AuthAPIError> {
+    let scope = record.scope.clone().unwrap_or_default();
+    let access_token = generate_client_access_token(
+        &record.user_id,
+        &record.family_id,
+        &client.client_id,
+        &scope,
+        state.jwt_key_ring.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+    let refresh_token = generate_refresh_token(record, state.refresh_token_store.clone())
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: TOKEN_TTL_SECONDS,
+        refresh_token: refresh_token.as_ref().expose_secret().to_owned(),
+        scope,
+        id_token: None,
+    })
+}
+
+// Shown in place of the login form to a logged in user the client has not been authorized by.
+// The hidden fields post the authorization request back along with the decision.
+pub(crate) struct ConsentPrompt {
+    pub client_name: String,
+    pub scopes: Vec<String>,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub state: Option<String>,
+    pub code_challenge: String,
+    pub nonce: Option<String>,
+}
+
+impl ConsentPrompt {
+    fn new(request: &AuthorizationRequest) -> Self {
+        Self {
+            client_name: request.client.name.clone(),
+            scopes: request.scopes.clone(),
+            client_id: request.client.client_id.clone(),
+            redirect_uri: request.redirect_uri.clone(),
+            scope: request.scopes.join(" "),
+            state: request.state.clone(),
+            code_challenge: request.code_challenge.clone(),
+            nonce: request.nonce.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RegisterOAuthClientRequest {
+    pub name: String,
+    #[serde(rename = "redirectUris")]
+    pub redirect_uris: Vec<String>,
+    pub confidential: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegisterOAuthClientResponse {
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+    // Only returned once, for confidential clients
+    #[serde(rename = "clientSecret", skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+}
+
+// Parameters of an authorization request, also posted back by the consent form
+#[derive(Deserialize)]
+pub struct AuthorizeParams {
+    pub response_type: Option<String>,
+    pub client_id: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    // OpenID Connect clients bind the id token to their login attempt with a nonce
+    pub nonce: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AuthorizeDecision {
+    #[serde(flatten)]
+    pub params: AuthorizeParams,
+    pub decision: String,
+}
+
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: Option<String>,
+    pub code: Option<Secret<String>>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<Secret<String>>,
+    pub refresh_token: Option<Secret<String>>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<Secret<String>>,
+}
+
+// Field names follow RFC 6749 section 5.1 rather than the camelCase of the other routes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub refresh_token: String,
+    pub scope: String,
+    // Issued for the `openid` scope
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
@@ -0,0 +1,217 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Redirect, Response},
+    Extension, Json,
+};
+use axum_extra::extract::CookieJar;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{AuthAPIError, OAuthClientStoreError, OAuthError, OPENID_SCOPE},
+    routes::{log_out_session, redirect_to_client},
+    utils::{
+        auth::{id_token_hint_client_id, validate_client_access_token},
+        bearer_token,
+        constants::{AUTH_BASE_URL, JWT_ISSUER},
+        token_user, AuthenticatedUser,
+    },
+    AppState,
+};
+
+// The scope giving clients the user's email address from `/userinfo`
+const EMAIL_SCOPE: &str = "email";
+
+// Lets relying party libraries configure themselves (OpenID Connect Discovery 1.0)
+#[tracing::instrument(name = "Publishing OpenID configuration", skip_all)]
+pub async fn openid_configuration(State(state): State<AppState>) -> impl IntoResponse {
+    let base_url = AUTH_BASE_URL.as_str();
+    let signing_algorithm = format!("{:?}", state.jwt_key_ring.read().await.algorithm());
+
+    let configuration = OpenIdConfiguration {
+        issuer: JWT_ISSUER.to_owned(),
+        authorization_endpoint: format!("{}/authorize", base_url),
+        token_endpoint: format!("{}/token", base_url),
+        userinfo_endpoint: format!("{}/userinfo", base_url),
+        jwks_uri: format!("{}/.well-known/jwks.json", base_url),
+        end_session_endpoint: format!("{}/end-session", base_url),
+        scopes_supported: vec![OPENID_SCOPE.to_owned(), EMAIL_SCOPE.to_owned()],
+        response_types_supported: vec!["code".to_owned()],
+        grant_types_supported: vec!["authorization_code".to_owned(), "refresh_token".to_owned()],
+        subject_types_supported: vec!["public".to_owned()],
+        id_token_signing_alg_values_supported: vec![signing_algorithm],
+        token_endpoint_auth_methods_supported: vec![
+            "client_secret_basic".to_owned(),
+            "client_secret_post".to_owned(),
+            "none".to_owned(),
+        ],
+        code_challenge_methods_supported: vec!["S256".to_owned()],
+        claims_supported: [
+            "sub",
+            "iss",
+            "aud",
+            "exp",
+            "iat",
+            "auth_time",
+            "nonce",
+            "amr",
+            "sid",
+            "email",
+            "email_verified",
+        ]
+        .iter()
+        .map(|claim| claim.to_string())
+        .collect(),
+    };
+
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(configuration),
+    )
+}
+
+// Tells a client with an `openid` access token who the user is, and their email address
+// if the client was granted the `email` scope
+#[tracing::instrument(name = "Returning user info", skip_all)]
+pub async fn userinfo(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token = bearer_token(&headers).ok_or(AuthAPIError::MissingToken)?;
+
+    let claims = validate_client_access_token(
+        token,
+        state.jwt_key_ring.clone(),
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    let scope = claims.scope.as_deref().unwrap_or_default();
+    let has_scope = |name: &str| scope.split(' ').any(|scope| scope == name);
+    if !has_scope(OPENID_SCOPE) {
+        return Err(AuthAPIError::OAuth(OAuthError::InsufficientScope));
+    }
+
+    let user = token_user(&state, &claims).await?;
+
+    let sub = claims.sub;
+    let response = if has_scope(EMAIL_SCOPE) {
+        UserInfoResponse {
+            email: Some(user.email.as_ref().expose_secret().to_owned()),
+            email_verified: Some(user.verified),
+            sub,
+        }
+    } else {
+        UserInfoResponse {
+            sub,
+            email: None,
+            email_verified: None,
+        }
+    };
+
+    Ok(Json(response))
+}
+
+// RP-initiated logout (OpenID Connect RP-Initiated Logout 1.0). Logs the browser out like
+// `/logout`, then sends it back to the client if it asked for one of its redirect URIs.
+#[tracing::instrument(name = "Ending session for relying party", skip_all)]
+pub async fn end_session(
+    State(state): State<AppState>,
+    Extension(prefix): Extension<String>,
+    user: Option<AuthenticatedUser>,
+    jar: CookieJar,
+    Query(params): Query<EndSessionParams>,
+) -> Result<(CookieJar, Response), AuthAPIError> {
+    if let Some(redirect_uri) = &params.post_logout_redirect_uri {
+        check_post_logout_redirect_uri(&state, &params, redirect_uri).await?;
+    }
+
+    // Users who are already logged out are sent on right away
+    let jar = match user {
+        Some(user) => log_out_session(&state, jar, &user.claims).await?,
+        None => jar,
+    };
+
+    let response = match &params.post_logout_redirect_uri {
+        Some(redirect_uri) => redirect_to_client(redirect_uri, &[], params.state.as_deref())?,
+        None => Redirect::to(&format!("{}/", prefix)).into_response(),
+    };
+
+    Ok((jar, response))
+}
+
+// The client is named by `client_id` or by the id token it sends as a hint, and may only
+// send the browser to one of its own redirect URIs
+async fn check_post_logout_redirect_uri(
+    state: &AppState,
+    params: &EndSessionParams,
+    redirect_uri: &str,
+) -> Result<(), AuthAPIError> {
+    let invalid_request = AuthAPIError::OAuth(OAuthError::InvalidRequest);
+
+    let client_id = match (&params.client_id, &params.id_token_hint) {
+        (Some(client_id), _) => client_id.clone(),
+        (None, Some(id_token_hint)) => {
+            match id_token_hint_client_id(id_token_hint, state.jwt_key_ring.clone()).await {
+                Ok(client_id) => client_id,
+                Err(_) => return Err(invalid_request),
+            }
+        }
+        (None, None) => return Err(invalid_request),
+    };
+
+    let client = match state
+        .oauth_client_store
+        .read()
+        .await
+        .get_client(&client_id)
+        .await
+    {
+        Ok(client) => client,
+        Err(OAuthClientStoreError::ClientNotFound) => return Err(invalid_request),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    if !client.allows_redirect_uri(redirect_uri) {
+        return Err(invalid_request);
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct EndSessionParams {
+    pub id_token_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub post_logout_redirect_uri: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenIdConfiguration {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub end_session_endpoint: String,
+    pub scopes_supported: Vec<String>,
+    pub response_types_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserInfoResponse {
+    pub sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+}
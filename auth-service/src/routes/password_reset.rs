@@ -0,0 +1,146 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{
+        AuthAPIError, Email, Password, PasswordResetToken, PasswordResetTokenStoreError,
+        UserStoreError,
+    },
+    utils::{auth::revoke_user_sessions, constants::AUTH_BASE_URL},
+    AppState,
+};
+
+#[tracing::instrument(name = "Requesting password reset", skip_all)]
+pub async fn password_reset_request(
+    State(state): State<AppState>,
+    Json(request): Json<PasswordResetRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    // Validate input
+    let Ok(email) = Email::parse(request.email) else {
+        return Err(AuthAPIError::InvalidCredentials);
+    };
+
+    // The response must not reveal whether the email is registered
+    let response = Json(PasswordResetResponse {
+        message: "If the account exists, a password reset link has been sent".to_owned(),
+    });
+
+    match state.user_store.read().await.get_user(&email).await {
+        Ok(_) => {}
+        Err(UserStoreError::UserNotFound) => return Ok((StatusCode::OK, response)),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    send_password_reset_email(&email, &state).await?;
+
+    Ok((StatusCode::OK, response))
+}
+
+#[tracing::instrument(name = "Confirming password reset", skip_all)]
+pub async fn password_reset_confirm(
+    State(state): State<AppState>,
+    Json(request): Json<PasswordResetConfirmRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    // Validate input
+    let Ok(token) = PasswordResetToken::parse(&request.token) else {
+        return Err(AuthAPIError::InvalidToken);
+    };
+
+    let Ok(password) = Password::parse(request.new_password) else {
+        return Err(AuthAPIError::InvalidCredentials);
+    };
+
+    // Consuming the token makes sure the link works only once
+    let email = match state
+        .password_reset_token_store
+        .write()
+        .await
+        .consume_token(&token)
+        .await
+    {
+        Ok(email) => email,
+        Err(PasswordResetTokenStoreError::TokenNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    let user = {
+        let mut user_store = state.user_store.write().await;
+        match user_store.update_password(&email, password).await {
+            Ok(()) => {}
+            Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+        user_store
+            .get_user(&email)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+    };
+
+    // Log the user out everywhere, the old password may have been compromised
+    revoke_user_sessions(
+        &user.id,
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .map_err(AuthAPIError::UnexpectedError)?;
+
+    let response = Json(PasswordResetResponse {
+        message: "Password reset successfully".to_owned(),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+// Emails the user a single-use link to choose a new password
+#[tracing::instrument(name = "Sending password reset email", skip_all)]
+pub(crate) async fn send_password_reset_email(
+    email: &Email,
+    state: &AppState,
+) -> Result<(), AuthAPIError> {
+    let token = PasswordResetToken::default();
+    state
+        .password_reset_token_store
+        .write()
+        .await
+        .add_token(token.clone(), email.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let reset_link = format!(
+        "{}/?reset_token={}",
+        AUTH_BASE_URL.as_str(),
+        token.as_ref().expose_secret()
+    );
+    state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            email,
+            "Reset your password",
+            &format!("Use this link to reset your password: {}", reset_link),
+        )
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetRequest {
+    pub email: Secret<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetConfirmRequest {
+    pub token: Secret<String>,
+    #[serde(rename = "newPassword")]
+    pub new_password: Secret<String>,
+}
+
+#[derive(Serialize)]
+pub struct PasswordResetResponse {
+    pub message: String,
+}
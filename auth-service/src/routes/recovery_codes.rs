@@ -0,0 +1,80 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{AuthAPIError, Email, Password, RecoveryCode, UserStoreError},
+    routes::verify_current_password,
+    utils::{constants::RECOVERY_CODE_COUNT, AuthenticatedUser},
+    AppState,
+};
+
+// Recovery codes stand in for the second factor, so a stolen session alone must not be
+// enough to replace them. The current password is asked for again.
+#[tracing::instrument(name = "Regenerating recovery codes", skip_all)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    AuthenticatedUser { email, .. }: AuthenticatedUser,
+    Json(request): Json<RegenerateRecoveryCodesRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let Ok(current_password) = Password::parse(request.current_password) else {
+        return Err(AuthAPIError::InvalidCredentials);
+    };
+
+    verify_current_password(&email, &current_password, &state).await?;
+
+    let user = match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    if !user.requires_2fa {
+        return Err(AuthAPIError::TwoFANotEnabled);
+    }
+
+    let recovery_codes = generate_recovery_codes(&email, &state).await?;
+
+    let response = Json(RecoveryCodesResponse { recovery_codes });
+
+    Ok((StatusCode::OK, response))
+}
+
+// Stores a fresh batch of recovery codes, replacing the old one, and returns them for display.
+// This is the only time the plain codes are available.
+#[tracing::instrument(name = "Generating recovery codes", skip_all)]
+pub async fn generate_recovery_codes(
+    email: &Email,
+    state: &AppState,
+) -> Result<Vec<String>, AuthAPIError> {
+    let codes: Vec<RecoveryCode> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| RecoveryCode::default())
+        .collect();
+    let plain_codes = codes
+        .iter()
+        .map(|code| code.as_ref().expose_secret().to_owned())
+        .collect();
+
+    match state
+        .user_store
+        .write()
+        .await
+        .set_recovery_codes(email, codes)
+        .await
+    {
+        Ok(()) => Ok(plain_codes),
+        Err(UserStoreError::UserNotFound) => Err(AuthAPIError::InvalidToken),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RegenerateRecoveryCodesRequest {
+    #[serde(rename = "currentPassword")]
+    pub current_password: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
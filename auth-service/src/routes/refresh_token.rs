@@ -0,0 +1,126 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use chrono::Utc;
+use secrecy::Secret;
+use serde::Deserialize;
+
+use crate::{
+    domain::{
+        AuthAPIError, RefreshToken, RefreshTokenRecord, RefreshTokenStoreError, SessionStoreError,
+    },
+    routes::{issue_session_tokens, ResponseMode},
+    utils::constants::REFRESH_COOKIE_NAME,
+    AppState,
+};
+
+#[tracing::instrument(name = "Refreshing token", skip_all)]
+pub async fn refresh_token(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    request: Option<Json<RefreshTokenRequest>>,
+) -> Result<(CookieJar, Response), AuthAPIError> {
+    // Browsers send the refresh cookie, clients in the token response mode send the token itself
+    // and get the new tokens back the same way
+    let (token, response_mode) = match (jar.get(REFRESH_COOKIE_NAME), request) {
+        (Some(cookie), _) => (Secret::new(cookie.value().to_owned()), ResponseMode::Cookie),
+        (None, Some(Json(request))) => (request.refresh_token, ResponseMode::Token),
+        (None, None) => return Err(AuthAPIError::MissingToken),
+    };
+
+    let Ok(token) = RefreshToken::parse(token) else {
+        return Err(AuthAPIError::InvalidToken);
+    };
+
+    let record = use_refresh_token(&state, &token, None).await?;
+
+    // Return success response with a fresh auth token and the rotated refresh token
+    let (updated_jar, tokens) =
+        issue_session_tokens(record.rotate(), response_mode, &state, jar).await?;
+
+    let response = match tokens {
+        Some(tokens) => (StatusCode::OK, Json(tokens)).into_response(),
+        None => StatusCode::OK.into_response(),
+    };
+    Ok((updated_jar, response))
+}
+
+// Checks the refresh token belongs to `client_id`, or to the cookie flow if there is none,
+// and that its session is still active, then marks it as used and returns its record
+pub(crate) async fn use_refresh_token(
+    state: &AppState,
+    token: &RefreshToken,
+    client_id: Option<&str>,
+) -> Result<RefreshTokenRecord, AuthAPIError> {
+    let mut refresh_token_store = state.refresh_token_store.write().await;
+
+    let record = match refresh_token_store.get_refresh_token(token).await {
+        Ok(record) => record,
+        Err(RefreshTokenStoreError::TokenNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    // Tokens of OAuth clients and of the cookie flow cannot stand in for each other
+    if record.client_id.as_deref() != client_id {
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    // A refresh token can only be used once. Seeing it again means it was stolen,
+    // so every token rotated from the same login is revoked.
+    if record.used {
+        refresh_token_store
+            .revoke_family(&record.family_id)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    let is_revoked = refresh_token_store
+        .is_family_revoked(&record.family_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    if is_revoked {
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    // Sessions started before the user's tokens were revoked cannot be refreshed either
+    let is_user_banned = state
+        .banned_token_store
+        .read()
+        .await
+        .check_user_tokens_banned(&record.user_id, record.family_issued_at)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    if is_user_banned {
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    // A revoked session cannot be renewed, refreshing counts as the device being seen
+    let mut session_store = state.session_store.write().await;
+    match session_store.get_session(&record.family_id).await {
+        Ok(session) if !session.revoked => {}
+        Ok(_) | Err(SessionStoreError::SessionNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+    session_store
+        .touch_session(&record.family_id, Utc::now().timestamp())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    refresh_token_store
+        .mark_refresh_token_used(token)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(record)
+}
+
+#[derive(Deserialize)]
+pub struct RefreshTokenRequest {
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: Secret<String>,
+}
@@ -0,0 +1,78 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use secrecy::Secret;
+use serde::Deserialize;
+
+use crate::{
+    domain::{AuthAPIError, Email, Permission, Role, UserStoreError},
+    AppState,
+};
+
+// Creates a role or replaces what it allows. Users holding it get the new permissions
+// with their next auth token.
+#[tracing::instrument(name = "Setting role permissions", skip_all)]
+pub async fn set_role_permissions(
+    State(state): State<AppState>,
+    Path(role): Path<String>,
+    Json(request): Json<SetRolePermissionsRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    let role = Role::parse(role).map_err(|_| AuthAPIError::InvalidRole)?;
+    let permissions = request
+        .permissions
+        .into_iter()
+        .map(Permission::parse)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| AuthAPIError::InvalidRole)?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .set_role_permissions(&role, permissions)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Replaces the roles of a user. They reach the user's auth tokens on the next login or refresh.
+#[tracing::instrument(name = "Setting user roles", skip_all)]
+pub async fn set_user_roles(
+    State(state): State<AppState>,
+    Json(request): Json<SetUserRolesRequest>,
+) -> Result<StatusCode, AuthAPIError> {
+    let email = Email::parse(request.email).map_err(|_| AuthAPIError::InvalidCredentials)?;
+    let roles = request
+        .roles
+        .into_iter()
+        .map(Role::parse)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| AuthAPIError::InvalidRole)?;
+
+    match state
+        .user_store
+        .write()
+        .await
+        .set_user_roles(&email, roles)
+        .await
+    {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(UserStoreError::UserNotFound) => Err(AuthAPIError::UserNotFound),
+        Err(UserStoreError::RoleNotFound) => Err(AuthAPIError::InvalidRole),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SetRolePermissionsRequest {
+    pub permissions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetUserRolesRequest {
+    pub email: Secret<String>,
+    pub roles: Vec<String>,
+}
@@ -0,0 +1,229 @@
+use std::net::IpAddr;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::CookieJar;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        AuthAPIError, RefreshTokenRecord, Session, SessionStoreError, UserRoles, UserStoreError,
+    },
+    utils::{
+        auth::{
+            generate_auth_cookie, generate_refresh_cookie, generate_refresh_token,
+            generate_session_auth_token, TOKEN_TTL_SECONDS,
+        },
+        AuthenticatedUser,
+    },
+    AppState,
+};
+
+// Records a new session for the device the user just logged in from and hands out its tokens
+#[tracing::instrument(name = "Starting session", skip_all)]
+pub(crate) async fn start_session(
+    user_id: &Uuid,
+    amr: Vec<String>,
+    user_agent: Option<String>,
+    client_ip: Option<IpAddr>,
+    response_mode: ResponseMode,
+    state: &AppState,
+    jar: CookieJar,
+) -> Result<(CookieJar, Option<SessionTokensResponse>), AuthAPIError> {
+    // The refresh token family identifies the session
+    let record = RefreshTokenRecord::new(*user_id);
+    let session = Session::new(
+        record.family_id.clone(),
+        *user_id,
+        amr,
+        user_agent,
+        client_ip.map(|ip| ip.to_string()),
+    );
+    state
+        .session_store
+        .write()
+        .await
+        .add_session(session)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    issue_session_tokens(record, response_mode, state, jar).await
+}
+
+// Where the session's tokens go. Browsers get cookies, clients that cannot keep cookies
+// ask for the tokens in the response body and send them back as bearer tokens.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseMode {
+    #[default]
+    Cookie,
+    Token,
+}
+
+// Issues an auth token and a refresh token for the record's session, added to the jar as
+// cookies or returned for the response body.
+// Use `RefreshTokenRecord::new` to start a new session and `RefreshTokenRecord::rotate` to continue one.
+pub(crate) async fn issue_session_tokens(
+    record: RefreshTokenRecord,
+    response_mode: ResponseMode,
+    state: &AppState,
+    jar: CookieJar,
+) -> Result<(CookieJar, Option<SessionTokensResponse>), AuthAPIError> {
+    // Read on every refresh, so role changes reach the user within one token lifetime
+    let roles = user_roles(state, &record.user_id).await?;
+
+    match response_mode {
+        ResponseMode::Cookie => {
+            let auth_cookie = generate_auth_cookie(
+                &record.user_id,
+                &record.family_id,
+                &roles,
+                state.jwt_key_ring.clone(),
+            )
+            .await
+            .map_err(AuthAPIError::UnexpectedError)?;
+            let refresh_cookie = generate_refresh_cookie(record, state.refresh_token_store.clone())
+                .await
+                .map_err(AuthAPIError::UnexpectedError)?;
+
+            Ok((jar.add(auth_cookie).add(refresh_cookie), None))
+        }
+        ResponseMode::Token => {
+            let access_token = generate_session_auth_token(
+                &record.user_id,
+                &record.family_id,
+                &roles,
+                state.jwt_key_ring.clone(),
+            )
+            .await
+            .map_err(AuthAPIError::UnexpectedError)?;
+            let refresh_token = generate_refresh_token(record, state.refresh_token_store.clone())
+                .await
+                .map_err(AuthAPIError::UnexpectedError)?;
+
+            let response = SessionTokensResponse {
+                access_token,
+                refresh_token: refresh_token.as_ref().expose_secret().to_owned(),
+                expires_in: TOKEN_TTL_SECONDS,
+            };
+            Ok((jar, Some(response)))
+        }
+    }
+}
+
+// The roles and permissions auth tokens of the user carry
+pub(crate) async fn user_roles(
+    state: &AppState,
+    user_id: &Uuid,
+) -> Result<UserRoles, AuthAPIError> {
+    let user_store = state.user_store.read().await;
+    let roles = match user_store.get_user_by_id(user_id).await {
+        Ok(user) => user_store.get_user_roles(&user.email).await,
+        Err(e) => Err(e),
+    };
+
+    match roles {
+        Ok(roles) => Ok(roles),
+        Err(UserStoreError::UserNotFound) => Err(AuthAPIError::UserNotFound),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+#[tracing::instrument(name = "Listing sessions", skip_all)]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    AuthenticatedUser {
+        user_id, claims, ..
+    }: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let sessions = state
+        .session_store
+        .read()
+        .await
+        .list_sessions(&user_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?
+        .into_iter()
+        .map(|session| SessionResponse {
+            current: session.id == claims.sid,
+            id: session.id,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            user_agent: session.user_agent,
+            ip: session.ip,
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(SessionsResponse { sessions })))
+}
+
+// Logs out one of the user's devices
+#[tracing::instrument(name = "Revoking session", skip_all)]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, AuthAPIError> {
+    // Sessions of other users are reported as missing, so their ids cannot be probed
+    {
+        let mut session_store = state.session_store.write().await;
+        match session_store.get_session(&session_id).await {
+            Ok(session) if session.user_id == user_id => {}
+            Ok(_) | Err(SessionStoreError::SessionNotFound) => {
+                return Err(AuthAPIError::SessionNotFound)
+            }
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        }
+
+        session_store
+            .revoke_session(&session_id)
+            .await
+            .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    }
+
+    // The session store is released first, refreshing takes the two stores the other way round
+    state
+        .refresh_token_store
+        .write()
+        .await
+        .revoke_family(&session_id)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionTokensResponse {
+    #[serde(rename = "accessToken")]
+    pub access_token: String,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
+    #[serde(rename = "expiresIn")]
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionsResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionResponse {
+    pub id: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    #[serde(rename = "lastSeenAt")]
+    pub last_seen_at: i64,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    // Whether this is the session the request was made from
+    pub current: bool,
+}
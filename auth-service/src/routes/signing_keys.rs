@@ -0,0 +1,26 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+use crate::{domain::AuthAPIError, utils::rotate_signing_key, AppState};
+
+// Starts signing tokens with a new key. Tokens signed with the previous key stay valid until they expire.
+#[tracing::instrument(name = "Rotating signing key", skip_all)]
+pub async fn rotate_signing_keys(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    if !state.jwt_key_ring.read().await.can_rotate() {
+        return Err(AuthAPIError::KeyRotationUnavailable);
+    }
+
+    let kid = rotate_signing_key(&state.jwt_key_ring)
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+    tracing::info!("Rotated JWT signing key, new kid: {}", kid);
+
+    Ok((StatusCode::OK, Json(RotateSigningKeysResponse { kid })))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RotateSigningKeysResponse {
+    pub kid: String,
+}
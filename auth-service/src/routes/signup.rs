@@ -1,5 +1,6 @@
 use crate::{
     domain::{AuthAPIError, Email, Password, User},
+    routes::{generate_recovery_codes, send_verification_email},
     AppState,
 };
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
@@ -30,19 +31,32 @@ pub async fn signup(
     let mut user_store = state.user_store.write().await;
 
     // Check if user already exists
-    if let Ok(_) = user_store.get_user(&user.email).await {
+    if user_store.get_user(&user.email).await.is_ok() {
         return Err(AuthAPIError::UserAlreadyExists);
     }
 
     // Add user to store
+    let email = user.email.clone();
     user_store
         .add_user(user)
         .await
         .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    drop(user_store);
+
+    // Ask the user to prove they own the email address
+    send_verification_email(&email, &state).await?;
+
+    // 2FA users get recovery codes in case they lose access to their second factor
+    let recovery_codes = if request.requires_2fa {
+        Some(generate_recovery_codes(&email, &state).await?)
+    } else {
+        None
+    };
 
     // Return success response
     let response = Json(SignupResponse {
         message: "User created successfully".to_string(),
+        recovery_codes,
     });
 
     Ok((StatusCode::CREATED, response))
@@ -56,7 +70,9 @@ pub struct SignupRequest {
     pub requires_2fa: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SignupResponse {
     pub message: String,
+    #[serde(rename = "recoveryCodes", skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
 }
@@ -0,0 +1,98 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use chrono::Utc;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{AuthAPIError, TotpSecret, TwoFACode, UserStoreError},
+    routes::generate_recovery_codes,
+    utils::{constants::TOTP_ISSUER, AuthenticatedUser},
+    AppState,
+};
+
+#[tracing::instrument(name = "Enrolling TOTP", skip_all)]
+pub async fn enroll_totp(
+    State(state): State<AppState>,
+    AuthenticatedUser { email, .. }: AuthenticatedUser,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    // The secret only becomes active once the user proves their app generates valid codes
+    let secret = TotpSecret::default();
+    match state
+        .user_store
+        .write()
+        .await
+        .set_pending_totp_secret(&email, secret.clone())
+        .await
+    {
+        Ok(()) => {}
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    let response = Json(EnrollTotpResponse {
+        secret: secret.as_ref().expose_secret().to_owned(),
+        otpauth_uri: secret.otpauth_uri(TOTP_ISSUER, &email),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+#[tracing::instrument(name = "Confirming TOTP enrollment", skip_all)]
+pub async fn confirm_totp(
+    State(state): State<AppState>,
+    AuthenticatedUser { email, .. }: AuthenticatedUser,
+    Json(request): Json<ConfirmTotpRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    // Validate input
+    let Ok(code) = TwoFACode::parse(&request.code) else {
+        return Err(AuthAPIError::InvalidCredentials);
+    };
+
+    let mut user_store = state.user_store.write().await;
+    let secret = match user_store.get_pending_totp_secret(&email).await {
+        Ok(secret) => secret,
+        Err(UserStoreError::UserNotFound | UserStoreError::TotpSecretNotFound) => {
+            return Err(AuthAPIError::IncorrectCredentials)
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    if !secret.verify_code(&code, Utc::now().timestamp()) {
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    user_store
+        .confirm_totp_secret(&email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    drop(user_store);
+
+    // Enabling 2FA hands out a new batch of recovery codes
+    let recovery_codes = generate_recovery_codes(&email, &state).await?;
+
+    let response = Json(ConfirmTotpResponse {
+        message: "TOTP enabled successfully".to_owned(),
+        recovery_codes,
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmTotpRequest {
+    pub code: Secret<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnrollTotpResponse {
+    pub secret: String,
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmTotpResponse {
+    pub message: String,
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
@@ -1,17 +1,24 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use axum_extra::extract::CookieJar;
+use chrono::Utc;
 use secrecy::Secret;
 use serde::Deserialize;
 
 use crate::{
-    domain::{AuthAPIError, LoginAttemptId, TwoFACode},
-    utils::auth,
+    domain::{
+        login_amr, AuthAPIError, LoginAttemptId, RecoveryCode, TwoFACode, TwoFACodeStoreError,
+        TwoFAMethod, UserStoreError,
+    },
+    routes::{start_session, ResponseMode},
+    utils::{constants::MAX_TWO_FA_ATTEMPTS, ClientIp, UserAgent},
     AppState, Email,
 };
 
 #[tracing::instrument(name = "Verifying 2fa", skip_all)]
 pub async fn verify_2fa(
     State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    UserAgent(user_agent): UserAgent,
     jar: CookieJar,
     Json(request): Json<Verify2FARequest>,
 ) -> Result<(CookieJar, impl IntoResponse), AuthAPIError> {
@@ -26,35 +33,111 @@ pub async fn verify_2fa(
     let Ok(login_attempt_id) = LoginAttemptId::parse(&login_attempt_id) else {
         return Err(AuthAPIError::InvalidCredentials);
     };
-    let Ok(two_fa_code) = TwoFACode::parse(&two_fa_code) else {
+    // A recovery code can be sent in place of the 2FA code
+    let code = if let Ok(two_fa_code) = TwoFACode::parse(&two_fa_code) {
+        SubmittedCode::TwoFA(two_fa_code)
+    } else if let Ok(recovery_code) = RecoveryCode::parse(&two_fa_code) {
+        SubmittedCode::Recovery(recovery_code)
+    } else {
         return Err(AuthAPIError::InvalidCredentials);
     };
 
-    // Validate 2fa
-    let mut two_fa_code_store = state.two_fa_code_store.write().await;
-    let Ok(found_two_fa_tuple) = two_fa_code_store.get_code(&email).await else {
+    // The same decision as in `login` tells which kind of code to expect
+    let Ok(user) = state.user_store.read().await.get_user(&email).await else {
+        return Err(AuthAPIError::IncorrectCredentials);
+    };
+    // The user may have been disabled since the login started
+    if user.disabled {
+        return Err(AuthAPIError::UserDisabled);
+    }
+    let two_fa_method = user.login_two_fa_method(state.unverified_login_policy);
+
+    // Validate 2fa. The code store is not held while the user store is used below,
+    // as `login` takes the two the other way round.
+    let Ok(found_two_fa_tuple) = state.two_fa_code_store.read().await.get_code(&email).await else {
         return Err(AuthAPIError::IncorrectCredentials);
     };
 
-    if found_two_fa_tuple.0 != login_attempt_id || found_two_fa_tuple.1 != two_fa_code {
+    if found_two_fa_tuple.0 != login_attempt_id {
         return Err(AuthAPIError::IncorrectCredentials);
     }
 
+    let is_code_valid = match (code, two_fa_method) {
+        (SubmittedCode::Recovery(recovery_code), _) => {
+            match state
+                .user_store
+                .write()
+                .await
+                .use_recovery_code(&email, &recovery_code)
+                .await
+            {
+                Ok(()) => true,
+                Err(UserStoreError::InvalidCredentials) => false,
+                Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+            }
+        }
+        (SubmittedCode::TwoFA(two_fa_code), Some(TwoFAMethod::Totp)) => {
+            let Ok(secret) = state.user_store.read().await.get_totp_secret(&email).await else {
+                return Err(AuthAPIError::IncorrectCredentials);
+            };
+            secret.verify_code(&two_fa_code, Utc::now().timestamp())
+        }
+        (SubmittedCode::TwoFA(two_fa_code), _) => found_two_fa_tuple.1 == two_fa_code,
+    };
+
+    let mut two_fa_code_store = state.two_fa_code_store.write().await;
+    if !is_code_valid {
+        // A concurrent request may have consumed or replaced the attempt since it was read
+        match two_fa_code_store.get_code(&email).await {
+            Ok((current_attempt_id, _)) if current_attempt_id == login_attempt_id => {}
+            _ => return Err(AuthAPIError::IncorrectCredentials),
+        }
+        // Every wrong guess is counted, so the 6-digit code cannot be brute-forced
+        let failed_attempts = match two_fa_code_store.record_failed_attempt(&email).await {
+            Ok(failed_attempts) => failed_attempts,
+            Err(TwoFACodeStoreError::LoginAttemptIdNotFound) => {
+                return Err(AuthAPIError::IncorrectCredentials)
+            }
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        };
+        if failed_attempts >= MAX_TWO_FA_ATTEMPTS {
+            two_fa_code_store
+                .remove_code(&email)
+                .await
+                .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+            return Err(AuthAPIError::TooManyTwoFAAttempts);
+        }
+        return Err(AuthAPIError::IncorrectCredentials);
+    }
+
+    // Only one of several requests with the same code gets to remove it
     if two_fa_code_store.remove_code(&email).await.is_err() {
         return Err(AuthAPIError::IncorrectCredentials);
     }
+    drop(two_fa_code_store);
+
+    let (updated_jar, tokens) = start_session(
+        &user.id,
+        login_amr(true),
+        user_agent,
+        client_ip,
+        request.response_mode,
+        &state,
+        jar,
+    )
+    .await?;
 
     // Return success response
-    let auth_cookie = match auth::generate_auth_cookie(&email) {
-        Ok(auth_cookie) => auth_cookie,
-        Err(e) => {
-            return Err(AuthAPIError::UnexpectedError(e));
-        }
+    let response = match tokens {
+        Some(tokens) => (StatusCode::OK, Json(tokens)).into_response(),
+        None => StatusCode::OK.into_response(),
     };
+    Ok((updated_jar, response))
+}
 
-    let updated_jar = jar.add(auth_cookie);
-
-    Ok((updated_jar, StatusCode::OK.into_response()))
+enum SubmittedCode {
+    TwoFA(TwoFACode),
+    Recovery(RecoveryCode),
 }
 
 #[derive(Deserialize)]
@@ -64,4 +147,6 @@ pub struct Verify2FARequest {
     pub login_attempt_id: Secret<String>,
     #[serde(rename = "2FACode")]
     pub two_fa_code: Secret<String>,
+    #[serde(default, rename = "responseMode")]
+    pub response_mode: ResponseMode,
 }
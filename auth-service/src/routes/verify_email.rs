@@ -0,0 +1,93 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    domain::{
+        AuthAPIError, Email, EmailVerificationToken, EmailVerificationTokenStoreError,
+        UserStoreError,
+    },
+    utils::constants::AUTH_BASE_URL,
+    AppState,
+};
+
+#[tracing::instrument(name = "Verifying email", skip_all)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(request): Json<VerifyEmailRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    // Validate input
+    let Ok(token) = EmailVerificationToken::parse(&request.token) else {
+        return Err(AuthAPIError::InvalidToken);
+    };
+
+    // Consuming the token makes sure the link works only once
+    let email = match state
+        .email_verification_token_store
+        .write()
+        .await
+        .consume_token(&token)
+        .await
+    {
+        Ok(email) => email,
+        Err(EmailVerificationTokenStoreError::TokenNotFound) => {
+            return Err(AuthAPIError::InvalidToken)
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    match state.user_store.write().await.mark_verified(&email).await {
+        Ok(()) => {}
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    let response = Json(VerifyEmailResponse {
+        message: "Email verified successfully".to_owned(),
+    });
+
+    Ok((StatusCode::OK, response))
+}
+
+// Issues a new verification token and emails the link to the user
+#[tracing::instrument(name = "Sending verification email", skip_all)]
+pub async fn send_verification_email(email: &Email, state: &AppState) -> Result<(), AuthAPIError> {
+    let token = EmailVerificationToken::default();
+    state
+        .email_verification_token_store
+        .write()
+        .await
+        .add_token(token.clone(), email.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let verification_link = format!(
+        "{}/?verify_token={}",
+        AUTH_BASE_URL.as_str(),
+        token.as_ref().expose_secret()
+    );
+    state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            email,
+            "Verify your email address",
+            &format!(
+                "Use this link to verify your email address: {}",
+                verification_link
+            ),
+        )
+        .await
+        .map_err(AuthAPIError::UnexpectedError)
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: Secret<String>,
+}
+
+#[derive(Serialize)]
+pub struct VerifyEmailResponse {
+    pub message: String,
+}
@@ -1,8 +1,8 @@
-use crate::utils::auth::validate_token;
+use crate::utils::auth::{default_audience, validate_audience_token};
 use crate::{domain::AuthAPIError, AppState};
 use axum::extract::State;
 use axum::{http::StatusCode, response::IntoResponse, Json};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[tracing::instrument(name = "Verifying token", skip_all)]
 pub async fn verify_token(
@@ -11,13 +11,38 @@ pub async fn verify_token(
 ) -> Result<impl IntoResponse, AuthAPIError> {
     // Validate incoming JWT
     let token = request.token;
-    let Ok(_claims) = validate_token(&token, state.banned_token_store.clone()).await else {
+    let audience = request.audience.as_deref().unwrap_or(default_audience());
+    let Ok(claims) = validate_audience_token(
+        &token,
+        audience,
+        state.jwt_key_ring.clone(),
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    else {
         return Err(AuthAPIError::VerificationFailed);
     };
-    Ok(StatusCode::OK)
+
+    // Relying apps authorize the request with the user's roles and permissions
+    let response = VerifyTokenResponse {
+        sub: claims.sub,
+        roles: claims.roles,
+        permissions: claims.permissions,
+    };
+    Ok((StatusCode::OK, Json(response)))
 }
 
 #[derive(Deserialize)]
 pub struct VerifyTokenRequest {
     pub token: String,
+    // Relying apps other than the default one name themselves
+    pub audience: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyTokenResponse {
+    pub sub: String,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
 }
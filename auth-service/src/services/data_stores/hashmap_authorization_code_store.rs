@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::{
+    domain::{
+        AuthorizationCode, AuthorizationCodeStore, AuthorizationCodeStoreError, AuthorizationGrant,
+    },
+    utils::constants::AUTHORIZATION_CODE_TTL_SECONDS,
+};
+
+#[derive(Default)]
+pub struct HashMapAuthorizationCodeStore {
+    // Each code maps to its grant and the unix timestamp it expires at
+    codes: HashMap<AuthorizationCode, (AuthorizationGrant, i64)>,
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for HashMapAuthorizationCodeStore {
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationGrant,
+    ) -> Result<(), AuthorizationCodeStoreError> {
+        let expires_at = Utc::now().timestamp() + AUTHORIZATION_CODE_TTL_SECONDS;
+        self.codes.insert(code, (grant, expires_at));
+        Ok(())
+    }
+
+    async fn consume_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationGrant, AuthorizationCodeStoreError> {
+        match self.codes.remove(code) {
+            Some((grant, expires_at)) if expires_at > Utc::now().timestamp() => Ok(grant),
+            _ => Err(AuthorizationCodeStoreError::CodeNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn grant() -> AuthorizationGrant {
+        AuthorizationGrant {
+            client_id: "client".to_owned(),
+            user_id: Uuid::new_v4(),
+            redirect_uri: "https://reports.example.com/callback".to_owned(),
+            scope: "email".to_owned(),
+            code_challenge: "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM".to_owned(),
+            session_id: "session".to_owned(),
+            nonce: None,
+            auth_time: 0,
+            amr: vec!["pwd".to_owned()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consume_code_only_once() {
+        // Arrange
+        let mut store = HashMapAuthorizationCodeStore::default();
+        let code = AuthorizationCode::default();
+        let grant = grant();
+        store.add_code(code.clone(), grant.clone()).await.unwrap();
+
+        // Act
+        let first = store.consume_code(&code).await;
+        let second = store.consume_code(&code).await;
+
+        // Assert
+        assert_eq!(first.unwrap(), grant);
+        assert_eq!(second, Err(AuthorizationCodeStoreError::CodeNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_consume_expired_code() {
+        // Arrange
+        let mut store = HashMapAuthorizationCodeStore::default();
+        let code = AuthorizationCode::default();
+        store
+            .codes
+            .insert(code.clone(), (grant(), Utc::now().timestamp() - 1));
+
+        // Act
+        let result = store.consume_code(&code).await;
+
+        // Assert
+        assert_eq!(result, Err(AuthorizationCodeStoreError::CodeNotFound));
+    }
+}
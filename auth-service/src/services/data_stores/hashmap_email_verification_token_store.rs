@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        Email, EmailVerificationToken, EmailVerificationTokenStore,
+        EmailVerificationTokenStoreError,
+    },
+    utils::constants::EMAIL_VERIFICATION_TOKEN_TTL_SECONDS,
+};
+
+#[derive(Default)]
+pub struct HashMapEmailVerificationTokenStore {
+    // Each token maps to its email and the unix timestamp it expires at
+    tokens: HashMap<EmailVerificationToken, (Email, i64)>,
+    // Each email change token maps to the user, their new email and the expiry
+    email_changes: HashMap<EmailVerificationToken, (Uuid, Email, i64)>,
+}
+
+impl HashMapEmailVerificationTokenStore {
+    pub fn new() -> Self {
+        Self {
+            tokens: HashMap::new(),
+            email_changes: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailVerificationTokenStore for HashMapEmailVerificationTokenStore {
+    async fn add_token(
+        &mut self,
+        token: EmailVerificationToken,
+        email: Email,
+    ) -> Result<(), EmailVerificationTokenStoreError> {
+        let expires_at = Utc::now().timestamp() + EMAIL_VERIFICATION_TOKEN_TTL_SECONDS;
+        self.tokens.insert(token, (email, expires_at));
+        Ok(())
+    }
+
+    async fn consume_token(
+        &mut self,
+        token: &EmailVerificationToken,
+    ) -> Result<Email, EmailVerificationTokenStoreError> {
+        match self.tokens.remove(token) {
+            Some((email, expires_at)) if expires_at > Utc::now().timestamp() => Ok(email),
+            _ => Err(EmailVerificationTokenStoreError::TokenNotFound),
+        }
+    }
+
+    async fn add_email_change_token(
+        &mut self,
+        token: EmailVerificationToken,
+        user_id: Uuid,
+        new_email: Email,
+    ) -> Result<(), EmailVerificationTokenStoreError> {
+        let expires_at = Utc::now().timestamp() + EMAIL_VERIFICATION_TOKEN_TTL_SECONDS;
+        self.email_changes
+            .insert(token, (user_id, new_email, expires_at));
+        Ok(())
+    }
+
+    async fn consume_email_change_token(
+        &mut self,
+        token: &EmailVerificationToken,
+    ) -> Result<(Uuid, Email), EmailVerificationTokenStoreError> {
+        match self.email_changes.remove(token) {
+            Some((user_id, new_email, expires_at)) if expires_at > Utc::now().timestamp() => {
+                Ok((user_id, new_email))
+            }
+            _ => Err(EmailVerificationTokenStoreError::TokenNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_consume_token_successfully() {
+        // Arrange
+        let mut store = HashMapEmailVerificationTokenStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = EmailVerificationToken::default();
+        store.add_token(token.clone(), email.clone()).await.unwrap();
+
+        // Act
+        let result = store.consume_token(&token).await;
+
+        // Assert
+        assert_eq!(result.unwrap(), email);
+    }
+
+    #[tokio::test]
+    async fn test_consume_token_only_once() {
+        // Arrange
+        let mut store = HashMapEmailVerificationTokenStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = EmailVerificationToken::default();
+        store.add_token(token.clone(), email).await.unwrap();
+        store.consume_token(&token).await.unwrap();
+
+        // Act
+        let result = store.consume_token(&token).await;
+
+        // Assert
+        assert_eq!(result, Err(EmailVerificationTokenStoreError::TokenNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_consume_expired_token() {
+        // Arrange
+        let mut store = HashMapEmailVerificationTokenStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = EmailVerificationToken::default();
+        store
+            .tokens
+            .insert(token.clone(), (email, Utc::now().timestamp() - 1));
+
+        // Act
+        let result = store.consume_token(&token).await;
+
+        // Assert
+        assert_eq!(result, Err(EmailVerificationTokenStoreError::TokenNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_consume_email_change_token_only_once() {
+        // Arrange
+        let mut store = HashMapEmailVerificationTokenStore::default();
+        let user_id = Uuid::new_v4();
+        let new_email = Email::parse(Secret::new("new@example.com".to_string())).unwrap();
+        let token = EmailVerificationToken::default();
+        store
+            .add_email_change_token(token.clone(), user_id, new_email.clone())
+            .await
+            .unwrap();
+
+        // Act
+        let first = store.consume_email_change_token(&token).await;
+        let second = store.consume_email_change_token(&token).await;
+
+        // Assert
+        assert_eq!(first.unwrap(), (user_id, new_email));
+        assert_eq!(second, Err(EmailVerificationTokenStoreError::TokenNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_email_change_token_does_not_verify_email() {
+        // Arrange
+        let mut store = HashMapEmailVerificationTokenStore::default();
+        let new_email = Email::parse(Secret::new("new@example.com".to_string())).unwrap();
+        let token = EmailVerificationToken::default();
+        store
+            .add_email_change_token(token.clone(), Uuid::new_v4(), new_email)
+            .await
+            .unwrap();
+
+        // Act
+        let result = store.consume_token(&token).await;
+
+        // Assert
+        assert_eq!(result, Err(EmailVerificationTokenStoreError::TokenNotFound));
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::domain::{LoginAttemptStore, LoginAttemptStoreError, LoginThrottleKey};
+
+#[derive(Default)]
+pub struct HashMapLoginAttemptStore {
+    // Each key maps to its failure count and the unix timestamp the count expires at
+    failures: HashMap<String, (u32, i64)>,
+    // Each key maps to the unix timestamp its lockout ends at
+    lockouts: HashMap<String, i64>,
+}
+
+impl HashMapLoginAttemptStore {
+    pub fn new() -> Self {
+        Self {
+            failures: HashMap::new(),
+            lockouts: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LoginAttemptStore for HashMapLoginAttemptStore {
+    async fn record_failure(
+        &mut self,
+        key: &LoginThrottleKey,
+        window_seconds: u64,
+    ) -> Result<u32, LoginAttemptStoreError> {
+        let now = Utc::now().timestamp();
+        let entry = self.failures.entry(key.as_key()).or_insert((0, now));
+        if entry.1 <= now {
+            entry.0 = 0;
+        }
+
+        entry.0 += 1;
+        entry.1 = now + window_seconds as i64;
+        Ok(entry.0)
+    }
+
+    async fn lock_out(
+        &mut self,
+        key: &LoginThrottleKey,
+        seconds: u64,
+    ) -> Result<(), LoginAttemptStoreError> {
+        let locked_until = Utc::now().timestamp() + seconds as i64;
+        self.lockouts.insert(key.as_key(), locked_until);
+        Ok(())
+    }
+
+    async fn get_lockout(
+        &self,
+        key: &LoginThrottleKey,
+    ) -> Result<Option<u64>, LoginAttemptStoreError> {
+        let now = Utc::now().timestamp();
+        match self.lockouts.get(&key.as_key()) {
+            Some(locked_until) if *locked_until > now => Ok(Some((locked_until - now) as u64)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn clear(&mut self, key: &LoginThrottleKey) -> Result<(), LoginAttemptStoreError> {
+        let key = key.as_key();
+        self.failures.remove(&key);
+        self.lockouts.remove(&key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use secrecy::Secret;
+
+    use super::*;
+    use crate::domain::Email;
+
+    fn email_key() -> LoginThrottleKey {
+        LoginThrottleKey::Email(Email::parse(Secret::new("test@example.com".to_string())).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_record_failure_counts_per_key() {
+        // Arrange
+        let mut store = HashMapLoginAttemptStore::default();
+        let ip_key = LoginThrottleKey::Ip(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        store.record_failure(&email_key(), 60).await.unwrap();
+
+        // Act
+        let email_failures = store.record_failure(&email_key(), 60).await.unwrap();
+        let ip_failures = store.record_failure(&ip_key, 60).await.unwrap();
+
+        // Assert
+        assert_eq!(email_failures, 2);
+        assert_eq!(ip_failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_record_failure_restarts_after_window() {
+        // Arrange
+        let mut store = HashMapLoginAttemptStore::default();
+        store.record_failure(&email_key(), 0).await.unwrap();
+
+        // Act
+        let result = store.record_failure(&email_key(), 60).await.unwrap();
+
+        // Assert
+        assert_eq!(result, 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_lockout() {
+        // Arrange
+        let mut store = HashMapLoginAttemptStore::default();
+        store.lock_out(&email_key(), 60).await.unwrap();
+
+        // Act
+        let result = store.get_lockout(&email_key()).await.unwrap();
+
+        // Assert
+        assert!(matches!(result, Some(seconds) if seconds > 0 && seconds <= 60));
+    }
+
+    #[tokio::test]
+    async fn test_clear_removes_failures_and_lockout() {
+        // Arrange
+        let mut store = HashMapLoginAttemptStore::default();
+        store.record_failure(&email_key(), 60).await.unwrap();
+        store.lock_out(&email_key(), 60).await.unwrap();
+
+        // Act
+        store.clear(&email_key()).await.unwrap();
+
+        // Assert
+        assert_eq!(store.get_lockout(&email_key()).await.unwrap(), None);
+        assert_eq!(store.record_failure(&email_key(), 60).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::{BTreeSet, HashMap};
+
+use uuid::Uuid;
+
+use crate::domain::{OAuthClient, OAuthClientStore, OAuthClientStoreError, OAuthConsent};
+
+#[derive(Default)]
+pub struct HashMapOAuthClientStore {
+    clients: HashMap<String, OAuthClient>,
+    // Scopes granted per user and client id
+    consents: HashMap<(Uuid, String), BTreeSet<String>>,
+}
+
+#[async_trait::async_trait]
+impl OAuthClientStore for HashMapOAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        self.clients.insert(client.client_id.clone(), client);
+        Ok(())
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        self.clients
+            .get(client_id)
+            .cloned()
+            .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+
+    async fn grant_consent(
+        &mut self,
+        user_id: &Uuid,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthClientStoreError> {
+        if !self.clients.contains_key(client_id) {
+            return Err(OAuthClientStoreError::ClientNotFound);
+        }
+        self.consents
+            .entry((*user_id, client_id.to_owned()))
+            .or_default()
+            .extend(scopes.iter().cloned());
+        Ok(())
+    }
+
+    async fn has_consent(
+        &self,
+        user_id: &Uuid,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<bool, OAuthClientStoreError> {
+        let Some(granted) = self.consents.get(&(*user_id, client_id.to_owned())) else {
+            return Ok(false);
+        };
+        Ok(scopes.iter().all(|scope| granted.contains(scope)))
+    }
+
+    async fn list_consents(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<OAuthConsent>, OAuthClientStoreError> {
+        let mut consents: Vec<OAuthConsent> = self
+            .consents
+            .iter()
+            .filter(|((consent_user_id, _), _)| consent_user_id == user_id)
+            .map(|((_, client_id), scopes)| OAuthConsent {
+                client_id: client_id.clone(),
+                scopes: scopes.iter().cloned().collect(),
+            })
+            .collect();
+        consents.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        Ok(consents)
+    }
+
+    async fn delete_consents(&mut self, user_id: &Uuid) -> Result<(), OAuthClientStoreError> {
+        self.consents
+            .retain(|(consent_user_id, _), _| consent_user_id != user_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> OAuthClient {
+        OAuthClient::new(
+            "Reports".to_owned(),
+            vec!["https://reports.example.com/callback".to_owned()],
+            true,
+        )
+        .unwrap()
+        .0
+    }
+
+    fn scopes(scopes: &[&str]) -> Vec<String> {
+        scopes.iter().map(|scope| scope.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_client() {
+        // Arrange
+        let mut store = HashMapOAuthClientStore::default();
+        let client = client();
+
+        // Act
+        store.add_client(client.clone()).await.unwrap();
+
+        // Assert
+        assert_eq!(store.get_client(&client.client_id).await.unwrap(), client);
+        assert_eq!(
+            store.get_client("unknown").await,
+            Err(OAuthClientStoreError::ClientNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_consent_covers_granted_scopes() {
+        // Arrange
+        let mut store = HashMapOAuthClientStore::default();
+        let client = client();
+        let user_id = Uuid::new_v4();
+        store.add_client(client.clone()).await.unwrap();
+
+        // Act
+        store
+            .grant_consent(&user_id, &client.client_id, &scopes(&["email"]))
+            .await
+            .unwrap();
+        store
+            .grant_consent(&user_id, &client.client_id, &scopes(&["profile"]))
+            .await
+            .unwrap();
+
+        // Assert
+        for (requested, expected) in [
+            (scopes(&["email", "profile"]), true),
+            (scopes(&[]), true),
+            (scopes(&["email", "admin"]), false),
+        ] {
+            let result = store
+                .has_consent(&user_id, &client.client_id, &requested)
+                .await
+                .unwrap();
+            assert_eq!(result, expected, "Failed for scopes: {:?}", requested);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grant_consent_for_unknown_client() {
+        // Arrange
+        let mut store = HashMapOAuthClientStore::default();
+        let user_id = Uuid::new_v4();
+
+        // Act
+        let result = store
+            .grant_consent(&user_id, "unknown", &scopes(&["email"]))
+            .await;
+
+        // Assert
+        assert_eq!(result, Err(OAuthClientStoreError::ClientNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_delete_consents_of_user() {
+        // Arrange
+        let mut store = HashMapOAuthClientStore::default();
+        let client = client();
+        let user_id = Uuid::new_v4();
+        let other_user_id = Uuid::new_v4();
+        store.add_client(client.clone()).await.unwrap();
+        for user_id in [&user_id, &other_user_id] {
+            store
+                .grant_consent(user_id, &client.client_id, &scopes(&["profile", "email"]))
+                .await
+                .unwrap();
+        }
+
+        // Act
+        let consents = store.list_consents(&user_id).await.unwrap();
+        store.delete_consents(&user_id).await.unwrap();
+
+        // Assert
+        assert_eq!(
+            consents,
+            vec![OAuthConsent {
+                client_id: client.client_id.clone(),
+                scopes: scopes(&["email", "profile"]),
+            }]
+        );
+        assert_eq!(store.list_consents(&user_id).await, Ok(vec![]));
+        assert_eq!(store.list_consents(&other_user_id).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::{
+    domain::{Email, PasswordResetToken, PasswordResetTokenStore, PasswordResetTokenStoreError},
+    utils::constants::PASSWORD_RESET_TOKEN_TTL_SECONDS,
+};
+
+#[derive(Default)]
+pub struct HashMapPasswordResetTokenStore {
+    // Each token maps to its email and the unix timestamp it expires at
+    tokens: HashMap<PasswordResetToken, (Email, i64)>,
+}
+
+impl HashMapPasswordResetTokenStore {
+    pub fn new() -> Self {
+        Self {
+            tokens: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PasswordResetTokenStore for HashMapPasswordResetTokenStore {
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+        email: Email,
+    ) -> Result<(), PasswordResetTokenStoreError> {
+        let expires_at = Utc::now().timestamp() + PASSWORD_RESET_TOKEN_TTL_SECONDS;
+        self.tokens.insert(token, (email, expires_at));
+        Ok(())
+    }
+
+    async fn consume_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> Result<Email, PasswordResetTokenStoreError> {
+        match self.tokens.remove(token) {
+            Some((email, expires_at)) if expires_at > Utc::now().timestamp() => Ok(email),
+            _ => Err(PasswordResetTokenStoreError::TokenNotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_consume_token_successfully() {
+        // Arrange
+        let mut store = HashMapPasswordResetTokenStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = PasswordResetToken::default();
+        store.add_token(token.clone(), email.clone()).await.unwrap();
+
+        // Act
+        let result = store.consume_token(&token).await;
+
+        // Assert
+        assert_eq!(result.unwrap(), email);
+    }
+
+    #[tokio::test]
+    async fn test_consume_token_only_once() {
+        // Arrange
+        let mut store = HashMapPasswordResetTokenStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = PasswordResetToken::default();
+        store.add_token(token.clone(), email).await.unwrap();
+        store.consume_token(&token).await.unwrap();
+
+        // Act
+        let result = store.consume_token(&token).await;
+
+        // Assert
+        assert_eq!(result, Err(PasswordResetTokenStoreError::TokenNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_consume_expired_token() {
+        // Arrange
+        let mut store = HashMapPasswordResetTokenStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = PasswordResetToken::default();
+        store
+            .tokens
+            .insert(token.clone(), (email, Utc::now().timestamp() - 1));
+
+        // Act
+        let result = store.consume_token(&token).await;
+
+        // Assert
+        assert_eq!(result, Err(PasswordResetTokenStoreError::TokenNotFound));
+    }
+}
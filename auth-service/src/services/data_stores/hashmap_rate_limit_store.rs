@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::domain::{
+    RateLimit, RateLimitDecision, RateLimitStore, RateLimitStoreError, TokenBucket,
+};
+
+// Past this many buckets, full ones are dropped before a new one is added
+const MAX_BUCKETS: usize = 10_000;
+
+#[derive(Default)]
+pub struct HashMapRateLimitStore {
+    buckets: HashMap<String, (TokenBucket, RateLimit)>,
+}
+
+impl HashMapRateLimitStore {
+    pub fn new() -> Self {
+        Self {
+            buckets: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for HashMapRateLimitStore {
+    async fn take_token(
+        &mut self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<RateLimitDecision, RateLimitStoreError> {
+        let now_ms = Utc::now().timestamp_millis();
+
+        if self.buckets.len() >= MAX_BUCKETS && !self.buckets.contains_key(key) {
+            self.buckets
+                .retain(|_, (bucket, limit)| !bucket.is_full(limit, now_ms));
+        }
+
+        let (bucket, _) = self
+            .buckets
+            .entry(key.to_owned())
+            .or_insert_with(|| (TokenBucket::full(limit, now_ms), *limit));
+        Ok(bucket.take(limit, now_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit {
+        capacity: 2,
+        period_seconds: 60,
+    };
+
+    #[tokio::test]
+    async fn test_take_token_limits_per_key() {
+        // Arrange
+        let mut store = HashMapRateLimitStore::default();
+        store.take_token("a", &LIMIT).await.unwrap();
+        store.take_token("a", &LIMIT).await.unwrap();
+
+        // Act
+        let exhausted = store.take_token("a", &LIMIT).await.unwrap();
+        let other_key = store.take_token("b", &LIMIT).await.unwrap();
+
+        // Assert
+        assert!(!exhausted.allowed);
+        assert!(exhausted.retry_after_seconds > 0);
+        assert!(other_key.allowed);
+        assert_eq!(other_key.remaining, 1);
+    }
+}
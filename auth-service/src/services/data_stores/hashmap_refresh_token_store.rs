@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::domain::{RefreshToken, RefreshTokenRecord, RefreshTokenStore, RefreshTokenStoreError};
+
+#[derive(Default)]
+pub struct HashMapRefreshTokenStore {
+    tokens: HashMap<RefreshToken, RefreshTokenRecord>,
+    revoked_families: HashSet<String>,
+}
+
+impl HashMapRefreshTokenStore {
+    pub fn new() -> Self {
+        Self {
+            tokens: HashMap::new(),
+            revoked_families: HashSet::new(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for HashMapRefreshTokenStore {
+    async fn add_refresh_token(
+        &mut self,
+        token: RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError> {
+        self.tokens.insert(token, record);
+        Ok(())
+    }
+
+    async fn get_refresh_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        self.tokens
+            .get(token)
+            .cloned()
+            .ok_or(RefreshTokenStoreError::TokenNotFound)
+    }
+
+    async fn mark_refresh_token_used(
+        &mut self,
+        token: &RefreshToken,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let record = self
+            .tokens
+            .get_mut(token)
+            .ok_or(RefreshTokenStoreError::TokenNotFound)?;
+        record.used = true;
+        Ok(())
+    }
+
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        self.revoked_families.insert(family_id.to_owned());
+        Ok(())
+    }
+
+    async fn is_family_revoked(&self, family_id: &str) -> Result<bool, RefreshTokenStoreError> {
+        Ok(self.revoked_families.contains(family_id))
+    }
+
+    async fn delete_user_tokens(&mut self, user_id: &Uuid) -> Result<(), RefreshTokenStoreError> {
+        self.tokens.retain(|_, record| record.user_id != *user_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> RefreshTokenRecord {
+        RefreshTokenRecord::new(Uuid::new_v4())
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_refresh_token() {
+        // Arrange
+        let mut store = HashMapRefreshTokenStore::default();
+        let token = RefreshToken::default();
+        let record = record();
+
+        // Act
+        store
+            .add_refresh_token(token.clone(), record.clone())
+            .await
+            .unwrap();
+        let result = store.get_refresh_token(&token).await;
+
+        // Assert
+        assert_eq!(result, Ok(record));
+    }
+
+    #[tokio::test]
+    async fn test_get_refresh_token_not_found() {
+        // Arrange
+        let store = HashMapRefreshTokenStore::default();
+
+        // Act
+        let result = store.get_refresh_token(&RefreshToken::default()).await;
+
+        // Assert
+        assert_eq!(result, Err(RefreshTokenStoreError::TokenNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_mark_refresh_token_used() {
+        // Arrange
+        let mut store = HashMapRefreshTokenStore::default();
+        let token = RefreshToken::default();
+        store
+            .add_refresh_token(token.clone(), record())
+            .await
+            .unwrap();
+
+        // Act
+        let result = store.mark_refresh_token_used(&token).await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(store.get_refresh_token(&token).await.unwrap().used);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_family() {
+        // Arrange
+        let mut store = HashMapRefreshTokenStore::default();
+
+        // Act
+        store.revoke_family("family").await.unwrap();
+
+        // Assert
+        assert!(store.is_family_revoked("family").await.unwrap());
+        assert!(!store.is_family_revoked("other_family").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_tokens() {
+        // Arrange
+        let mut store = HashMapRefreshTokenStore::default();
+        let user_record = record();
+        let used_token = RefreshToken::default();
+        let token = RefreshToken::default();
+        let other_token = RefreshToken::default();
+        store
+            .add_refresh_token(used_token.clone(), user_record.clone())
+            .await
+            .unwrap();
+        store.mark_refresh_token_used(&used_token).await.unwrap();
+        store
+            .add_refresh_token(token.clone(), user_record.rotate())
+            .await
+            .unwrap();
+        store
+            .add_refresh_token(other_token.clone(), record())
+            .await
+            .unwrap();
+
+        // Act
+        store
+            .delete_user_tokens(&user_record.user_id)
+            .await
+            .unwrap();
+
+        // Assert
+        for token in [used_token, token] {
+            assert_eq!(
+                store.get_refresh_token(&token).await,
+                Err(RefreshTokenStoreError::TokenNotFound)
+            );
+        }
+        assert!(store.get_refresh_token(&other_token).await.is_ok());
+    }
+}
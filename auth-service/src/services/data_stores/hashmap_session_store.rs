@@ -0,0 +1,209 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    domain::{Session, SessionStore, SessionStoreError},
+    utils::auth::REFRESH_TOKEN_TTL_SECONDS,
+};
+
+#[derive(Default)]
+pub struct HashMapSessionStore {
+    sessions: HashMap<String, Session>,
+}
+
+#[async_trait::async_trait]
+impl SessionStore for HashMapSessionStore {
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError> {
+        self.sessions.insert(session.id.clone(), session);
+        Ok(())
+    }
+
+    async fn get_session(&self, id: &str) -> Result<Session, SessionStoreError> {
+        self.sessions
+            .get(id)
+            .cloned()
+            .ok_or(SessionStoreError::SessionNotFound)
+    }
+
+    async fn list_sessions(&self, user_id: &Uuid) -> Result<Vec<Session>, SessionStoreError> {
+        let active_since = Utc::now().timestamp() - REFRESH_TOKEN_TTL_SECONDS;
+        let mut sessions: Vec<Session> = self
+            .sessions
+            .values()
+            .filter(|session| {
+                session.user_id == *user_id
+                    && !session.revoked
+                    && session.last_seen_at > active_since
+            })
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| Reverse(session.last_seen_at));
+        Ok(sessions)
+    }
+
+    async fn touch_session(
+        &mut self,
+        id: &str,
+        last_seen_at: i64,
+    ) -> Result<(), SessionStoreError> {
+        let session = self
+            .sessions
+            .get_mut(id)
+            .ok_or(SessionStoreError::SessionNotFound)?;
+        session.last_seen_at = last_seen_at;
+        Ok(())
+    }
+
+    async fn revoke_session(&mut self, id: &str) -> Result<(), SessionStoreError> {
+        let session = self
+            .sessions
+            .get_mut(id)
+            .ok_or(SessionStoreError::SessionNotFound)?;
+        session.revoked = true;
+        Ok(())
+    }
+
+    async fn revoke_user_sessions(&mut self, user_id: &Uuid) -> Result<(), SessionStoreError> {
+        self.sessions
+            .values_mut()
+            .filter(|session| session.user_id == *user_id)
+            .for_each(|session| session.revoked = true);
+        Ok(())
+    }
+
+    async fn delete_user_sessions(&mut self, user_id: &Uuid) -> Result<(), SessionStoreError> {
+        self.sessions
+            .retain(|_, session| session.user_id != *user_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::login_amr;
+
+    fn session(id: &str, user_id: Uuid) -> Session {
+        Session::new(
+            id.to_owned(),
+            user_id,
+            login_amr(false),
+            Some("Firefox".to_owned()),
+            Some("203.0.113.7".to_owned()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_add_and_get_session() {
+        // Arrange
+        let mut store = HashMapSessionStore::default();
+        let user_id = Uuid::new_v4();
+        let session = session("first", user_id);
+
+        // Act
+        store.add_session(session.clone()).await.unwrap();
+        let result = store.get_session("first").await;
+
+        // Assert
+        assert_eq!(result.unwrap(), session);
+        assert_eq!(
+            store.get_session("missing").await,
+            Err(SessionStoreError::SessionNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_sessions_returns_active_sessions_of_user() {
+        // Arrange
+        let mut store = HashMapSessionStore::default();
+        let user_id = Uuid::new_v4();
+        let now = Utc::now().timestamp();
+        store.add_session(session("older", user_id)).await.unwrap();
+        store.add_session(session("newer", user_id)).await.unwrap();
+        store
+            .add_session(session("revoked", user_id))
+            .await
+            .unwrap();
+        store
+            .add_session(session("expired", user_id))
+            .await
+            .unwrap();
+        store
+            .add_session(session("other", Uuid::new_v4()))
+            .await
+            .unwrap();
+        store.touch_session("older", now - 10).await.unwrap();
+        store.revoke_session("revoked").await.unwrap();
+        store
+            .touch_session("expired", now - REFRESH_TOKEN_TTL_SECONDS - 1)
+            .await
+            .unwrap();
+
+        // Act
+        let sessions = store.list_sessions(&user_id).await.unwrap();
+
+        // Assert
+        let ids: Vec<&str> = sessions.iter().map(|session| session.id.as_str()).collect();
+        assert_eq!(ids, vec!["newer", "older"]);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user_sessions() {
+        // Arrange
+        let mut store = HashMapSessionStore::default();
+        let user_id = Uuid::new_v4();
+        store.add_session(session("first", user_id)).await.unwrap();
+        store
+            .add_session(session("other", Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        // Act
+        store.revoke_user_sessions(&user_id).await.unwrap();
+
+        // Assert
+        assert!(store.get_session("first").await.unwrap().revoked);
+        assert!(!store.get_session("other").await.unwrap().revoked);
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_sessions() {
+        // Arrange
+        let mut store = HashMapSessionStore::default();
+        let user_id = Uuid::new_v4();
+        store.add_session(session("first", user_id)).await.unwrap();
+        store.revoke_session("first").await.unwrap();
+        store
+            .add_session(session("other", Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        // Act
+        store.delete_user_sessions(&user_id).await.unwrap();
+
+        // Assert
+        assert_eq!(
+            store.get_session("first").await,
+            Err(SessionStoreError::SessionNotFound)
+        );
+        assert!(store.get_session("other").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_touch_or_revoke_missing_session() {
+        // Arrange
+        let mut store = HashMapSessionStore::default();
+
+        // Act & Assert
+        assert_eq!(
+            store.touch_session("missing", 0).await,
+            Err(SessionStoreError::SessionNotFound)
+        );
+        assert_eq!(
+            store.revoke_session("missing").await,
+            Err(SessionStoreError::SessionNotFound)
+        );
+    }
+}
@@ -6,12 +6,14 @@ use crate::domain::{Email, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeS
 #[derive(Default)]
 pub struct HashMapTwoFACodeStore {
     codes: HashMap<Email, (LoginAttemptId, TwoFACode)>,
+    failed_attempts: HashMap<Email, u32>,
 }
 
 impl HashMapTwoFACodeStore {
     pub fn new() -> Self {
         Self {
             codes: HashMap::new(),
+            failed_attempts: HashMap::new(),
         }
     }
 }
@@ -24,13 +26,16 @@ impl TwoFACodeStore for HashMapTwoFACodeStore {
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
     ) -> Result<(), TwoFACodeStoreError> {
+        // A new login attempt starts with a clean slate
+        self.failed_attempts.remove(&email);
         self.codes.insert(email, (login_attempt_id, code));
         Ok(())
     }
 
     async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
         // Remove the code entry and return an error if it doesn't exist
-        if let Some(_) = self.codes.remove(email) {
+        self.failed_attempts.remove(email);
+        if self.codes.remove(email).is_some() {
             Ok(())
         } else {
             Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
@@ -46,6 +51,15 @@ impl TwoFACodeStore for HashMapTwoFACodeStore {
             .cloned()
             .ok_or(TwoFACodeStoreError::LoginAttemptIdNotFound)
     }
+
+    async fn record_failed_attempt(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+        if !self.codes.contains_key(email) {
+            return Err(TwoFACodeStoreError::LoginAttemptIdNotFound);
+        }
+        let attempts = self.failed_attempts.entry(email.clone()).or_insert(0);
+        *attempts += 1;
+        Ok(*attempts)
+    }
 }
 
 #[cfg(test)]
@@ -88,7 +102,7 @@ mod tests {
 
         // Assert
         assert!(result.is_ok());
-        assert!(store.codes.get(&email).is_none());
+        assert!(!store.codes.contains_key(&email));
     }
 
     #[tokio::test]
@@ -143,4 +157,67 @@ mod tests {
             Err(TwoFACodeStoreError::LoginAttemptIdNotFound)
         ));
     }
+
+    #[tokio::test]
+    async fn test_record_failed_attempt_counts_failures() {
+        // Arrange
+        let mut store = HashMapTwoFACodeStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let _ = store
+            .add_code(
+                email.clone(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+            )
+            .await;
+
+        // Act
+        let first = store.record_failed_attempt(&email).await;
+        let second = store.record_failed_attempt(&email).await;
+
+        // Assert
+        assert_eq!(first, Ok(1));
+        assert_eq!(second, Ok(2));
+    }
+
+    #[tokio::test]
+    async fn test_add_code_resets_failed_attempts() {
+        // Arrange
+        let mut store = HashMapTwoFACodeStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let _ = store
+            .add_code(
+                email.clone(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+            )
+            .await;
+        let _ = store.record_failed_attempt(&email).await;
+
+        // Act
+        let _ = store
+            .add_code(
+                email.clone(),
+                LoginAttemptId::default(),
+                TwoFACode::default(),
+            )
+            .await;
+        let result = store.record_failed_attempt(&email).await;
+
+        // Assert
+        assert_eq!(result, Ok(1));
+    }
+
+    #[tokio::test]
+    async fn test_record_failed_attempt_returns_error_when_email_not_found() {
+        // Arrange
+        let mut store = HashMapTwoFACodeStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+
+        // Act
+        let result = store.record_failed_attempt(&email).await;
+
+        // Assert
+        assert_eq!(result, Err(TwoFACodeStoreError::LoginAttemptIdNotFound));
+    }
 }
@@ -1,20 +1,41 @@
-use crate::domain::{Email, Password, User, UserStore, UserStoreError};
+use crate::domain::{
+    Email, Password, Permission, RecoveryCode, Role, TotpSecret, TwoFAMethod, User, UserRoles,
+    UserStore, UserStoreError,
+};
 use async_trait::async_trait;
+use secrecy::ExposeSecret;
 use std::collections::HashMap;
+use uuid::Uuid;
 
-#[derive(Default)]
 pub struct HashMapUserStore {
     users: HashMap<Email, User>,
+    totp_secrets: HashMap<Email, TotpSecret>,
+    pending_totp_secrets: HashMap<Email, TotpSecret>,
+    recovery_codes: HashMap<Email, Vec<RecoveryCode>>,
+    role_permissions: HashMap<Role, Vec<Permission>>,
+    user_roles: HashMap<Email, Vec<Role>>,
 }
 
 impl HashMapUserStore {
     pub fn new() -> Self {
         Self {
             users: HashMap::new(),
+            totp_secrets: HashMap::new(),
+            pending_totp_secrets: HashMap::new(),
+            recovery_codes: HashMap::new(),
+            // Like the roles migration, start out with the admin role
+            role_permissions: HashMap::from([(Role::admin(), Vec::new())]),
+            user_roles: HashMap::new(),
         }
     }
 }
 
+impl Default for HashMapUserStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl UserStore for HashMapUserStore {
     async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
@@ -33,6 +54,14 @@ impl UserStore for HashMapUserStore {
             .ok_or(UserStoreError::UserNotFound)
     }
 
+    async fn get_user_by_id(&self, id: &Uuid) -> Result<User, UserStoreError> {
+        self.users
+            .values()
+            .find(|user| user.id == *id)
+            .cloned()
+            .ok_or(UserStoreError::UserNotFound)
+    }
+
     async fn validate_user(
         &self,
         email: &Email,
@@ -49,6 +78,247 @@ impl UserStore for HashMapUserStore {
             Err(err) => Err(err),
         }
     }
+
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.password = password;
+        Ok(())
+    }
+
+    async fn mark_verified(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.verified = true;
+        Ok(())
+    }
+
+    async fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        if self.users.contains_key(new_email) {
+            return Err(UserStoreError::UserAlreadyExists);
+        }
+        let mut user = self
+            .users
+            .remove(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.email = new_email.clone();
+        user.verified = false;
+        self.users.insert(new_email.clone(), user);
+
+        if let Some(secret) = self.totp_secrets.remove(email) {
+            self.totp_secrets.insert(new_email.clone(), secret);
+        }
+        if let Some(secret) = self.pending_totp_secrets.remove(email) {
+            self.pending_totp_secrets.insert(new_email.clone(), secret);
+        }
+        if let Some(codes) = self.recovery_codes.remove(email) {
+            self.recovery_codes.insert(new_email.clone(), codes);
+        }
+        if let Some(roles) = self.user_roles.remove(email) {
+            self.user_roles.insert(new_email.clone(), roles);
+        }
+        Ok(())
+    }
+
+    async fn set_pending_totp_secret(
+        &mut self,
+        email: &Email,
+        secret: TotpSecret,
+    ) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.pending_totp_secrets.insert(email.clone(), secret);
+        Ok(())
+    }
+
+    async fn get_pending_totp_secret(&self, email: &Email) -> Result<TotpSecret, UserStoreError> {
+        self.pending_totp_secrets
+            .get(email)
+            .cloned()
+            .ok_or(UserStoreError::TotpSecretNotFound)
+    }
+
+    async fn confirm_totp_secret(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        let secret = self
+            .pending_totp_secrets
+            .remove(email)
+            .ok_or(UserStoreError::TotpSecretNotFound)?;
+        self.totp_secrets.insert(email.clone(), secret);
+        user.requires_2fa = true;
+        user.two_fa_method = TwoFAMethod::Totp;
+        Ok(())
+    }
+
+    async fn get_totp_secret(&self, email: &Email) -> Result<TotpSecret, UserStoreError> {
+        self.totp_secrets
+            .get(email)
+            .cloned()
+            .ok_or(UserStoreError::TotpSecretNotFound)
+    }
+
+    async fn set_recovery_codes(
+        &mut self,
+        email: &Email,
+        codes: Vec<RecoveryCode>,
+    ) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.recovery_codes.insert(email.clone(), codes);
+        Ok(())
+    }
+
+    async fn use_recovery_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<(), UserStoreError> {
+        let codes = self
+            .recovery_codes
+            .get_mut(email)
+            .ok_or(UserStoreError::InvalidCredentials)?;
+        let position = codes
+            .iter()
+            .position(|c| c == code)
+            .ok_or(UserStoreError::InvalidCredentials)?;
+        codes.remove(position);
+        Ok(())
+    }
+
+    async fn set_role_permissions(
+        &mut self,
+        role: &Role,
+        permissions: Vec<Permission>,
+    ) -> Result<(), UserStoreError> {
+        self.role_permissions.insert(role.clone(), permissions);
+        Ok(())
+    }
+
+    async fn set_user_roles(
+        &mut self,
+        email: &Email,
+        roles: Vec<Role>,
+    ) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        if roles
+            .iter()
+            .any(|role| !self.role_permissions.contains_key(role))
+        {
+            return Err(UserStoreError::RoleNotFound);
+        }
+        self.user_roles.insert(email.clone(), roles);
+        Ok(())
+    }
+
+    async fn get_user_roles(&self, email: &Email) -> Result<UserRoles, UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        let roles = self.user_roles.get(email).cloned().unwrap_or_default();
+        let permissions = roles
+            .iter()
+            .filter_map(|role| self.role_permissions.get(role))
+            .flatten()
+            .cloned()
+            .collect();
+        Ok(UserRoles::new(roles, permissions))
+    }
+
+    async fn list_users(
+        &self,
+        cursor: Option<&Email>,
+        limit: usize,
+        search: Option<&str>,
+    ) -> Result<Vec<User>, UserStoreError> {
+        let cursor = cursor.map(|email| email.as_ref().expose_secret().as_str());
+        let mut users: Vec<User> = self
+            .users
+            .values()
+            .filter(|user| matches_search(user, search))
+            .filter(|user| cursor.is_none_or(|cursor| email_str(user) > cursor))
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| email_str(a).cmp(email_str(b)));
+        users.truncate(limit);
+        Ok(users)
+    }
+
+    async fn count_users(&self, search: Option<&str>) -> Result<u64, UserStoreError> {
+        let count = self
+            .users
+            .values()
+            .filter(|user| matches_search(user, search))
+            .count();
+        Ok(count as u64)
+    }
+
+    async fn set_disabled(&mut self, email: &Email, disabled: bool) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.disabled = disabled;
+        Ok(())
+    }
+
+    async fn set_requires_2fa(
+        &mut self,
+        email: &Email,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.requires_2fa = requires_2fa;
+        Ok(())
+    }
+
+    async fn delete_user(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        self.users
+            .remove(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        self.totp_secrets.remove(email);
+        self.pending_totp_secrets.remove(email);
+        self.recovery_codes.remove(email);
+        self.user_roles.remove(email);
+        Ok(())
+    }
+}
+
+fn email_str(user: &User) -> &str {
+    user.email.as_ref().expose_secret()
+}
+
+// Case-insensitive substring match, like ILIKE in the Postgres store
+fn matches_search(user: &User, search: Option<&str>) -> bool {
+    search.is_none_or(|search| {
+        email_str(user)
+            .to_lowercase()
+            .contains(&search.to_lowercase())
+    })
 }
 
 // TODO: Add unit tests for your `HashmapUserStore` implementation
@@ -187,4 +457,331 @@ mod tests {
         // Assert
         assert_eq!(result, Err(UserStoreError::UserNotFound));
     }
+
+    #[tokio::test]
+    async fn test_update_password_ok() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let user = User::new(
+            Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            Password::parse(Secret::new("password".to_string())).unwrap(),
+            false,
+        );
+        store.add_user(user.clone()).await.unwrap();
+        let new_password = Password::parse(Secret::new("new_password".to_string())).unwrap();
+
+        // Act
+        let result = store
+            .update_password(&user.email, new_password.clone())
+            .await;
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            store.validate_user(&user.email, &new_password).await,
+            Ok(())
+        );
+        assert_eq!(
+            store.validate_user(&user.email, &user.password).await,
+            Err(UserStoreError::InvalidCredentials)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_password_user_not_found() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let email = Email::parse(Secret::new("not_found@example.com".to_string())).unwrap();
+        let password = Password::parse(Secret::new("new_password".to_string())).unwrap();
+
+        // Act
+        let result = store.update_password(&email, password).await;
+
+        // Assert
+        assert_eq!(result, Err(UserStoreError::UserNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_mark_verified_ok() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let user = User::new(
+            Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            Password::parse(Secret::new("password".to_string())).unwrap(),
+            false,
+        );
+        store.add_user(user.clone()).await.unwrap();
+
+        // Act
+        let result = store.mark_verified(&user.email).await;
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        assert!(store.get_user(&user.email).await.unwrap().verified);
+    }
+
+    #[tokio::test]
+    async fn test_mark_verified_user_not_found() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let email = Email::parse(Secret::new("not_found@example.com".to_string())).unwrap();
+
+        // Act
+        let result = store.mark_verified(&email).await;
+
+        // Assert
+        assert_eq!(result, Err(UserStoreError::UserNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_confirm_totp_secret_ok() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let user = User::new(
+            Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            Password::parse(Secret::new("password".to_string())).unwrap(),
+            false,
+        );
+        store.add_user(user.clone()).await.unwrap();
+        let secret = TotpSecret::default();
+        store
+            .set_pending_totp_secret(&user.email, secret.clone())
+            .await
+            .unwrap();
+
+        // Act
+        let result = store.confirm_totp_secret(&user.email).await;
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        assert_eq!(store.get_totp_secret(&user.email).await, Ok(secret));
+        assert_eq!(
+            store.get_pending_totp_secret(&user.email).await,
+            Err(UserStoreError::TotpSecretNotFound)
+        );
+        let updated_user = store.get_user(&user.email).await.unwrap();
+        assert!(updated_user.requires_2fa);
+        assert_eq!(updated_user.two_fa_method, TwoFAMethod::Totp);
+    }
+
+    #[tokio::test]
+    async fn test_confirm_totp_secret_without_pending_secret() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let user = User::new(
+            Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            Password::parse(Secret::new("password".to_string())).unwrap(),
+            false,
+        );
+        store.add_user(user.clone()).await.unwrap();
+
+        // Act
+        let result = store.confirm_totp_secret(&user.email).await;
+
+        // Assert
+        assert_eq!(result, Err(UserStoreError::TotpSecretNotFound));
+        assert!(!store.get_user(&user.email).await.unwrap().requires_2fa);
+    }
+
+    #[tokio::test]
+    async fn test_use_recovery_code_only_once() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let user = User::new(
+            Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            Password::parse(Secret::new("password".to_string())).unwrap(),
+            true,
+        );
+        store.add_user(user.clone()).await.unwrap();
+        let code = RecoveryCode::default();
+        store
+            .set_recovery_codes(&user.email, vec![code.clone(), RecoveryCode::default()])
+            .await
+            .unwrap();
+
+        // Act
+        let first_result = store.use_recovery_code(&user.email, &code).await;
+        let second_result = store.use_recovery_code(&user.email, &code).await;
+
+        // Assert
+        assert_eq!(first_result, Ok(()));
+        assert_eq!(second_result, Err(UserStoreError::InvalidCredentials));
+    }
+
+    #[tokio::test]
+    async fn test_set_recovery_codes_invalidates_previous_batch() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let user = User::new(
+            Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            Password::parse(Secret::new("password".to_string())).unwrap(),
+            true,
+        );
+        store.add_user(user.clone()).await.unwrap();
+        let old_code = RecoveryCode::default();
+        store
+            .set_recovery_codes(&user.email, vec![old_code.clone()])
+            .await
+            .unwrap();
+
+        // Act
+        store
+            .set_recovery_codes(&user.email, vec![RecoveryCode::default()])
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            store.use_recovery_code(&user.email, &old_code).await,
+            Err(UserStoreError::InvalidCredentials)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_users_pages_by_email() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        for address in ["carol@example.com", "alice@example.com", "bob@example.com"] {
+            let user = User::new(
+                Email::parse(Secret::new(address.to_string())).unwrap(),
+                Password::parse(Secret::new("password".to_string())).unwrap(),
+                false,
+            );
+            store.add_user(user).await.unwrap();
+        }
+
+        // Act
+        let first_page = store.list_users(None, 2, None).await.unwrap();
+        let second_page = store
+            .list_users(Some(&first_page[1].email), 2, None)
+            .await
+            .unwrap();
+
+        // Assert
+        let emails = |users: &[User]| {
+            users
+                .iter()
+                .map(|user| email_str(user).to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            emails(&first_page),
+            vec!["alice@example.com", "bob@example.com"]
+        );
+        assert_eq!(emails(&second_page), vec!["carol@example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_and_count_users_with_search() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        for address in ["alice@example.com", "bob@other.org"] {
+            let user = User::new(
+                Email::parse(Secret::new(address.to_string())).unwrap(),
+                Password::parse(Secret::new("password".to_string())).unwrap(),
+                false,
+            );
+            store.add_user(user).await.unwrap();
+        }
+
+        // Act
+        let users = store.list_users(None, 10, Some("EXAMPLE")).await.unwrap();
+        let count = store.count_users(Some("EXAMPLE")).await.unwrap();
+
+        // Assert
+        assert_eq!(users.len(), 1);
+        assert_eq!(email_str(&users[0]), "alice@example.com");
+        assert_eq!(count, 1);
+        assert_eq!(store.count_users(None).await, Ok(2));
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_removes_roles() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let user = User::new(
+            Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            Password::parse(Secret::new("password".to_string())).unwrap(),
+            false,
+        );
+        store.add_user(user.clone()).await.unwrap();
+        store
+            .set_user_roles(&user.email, vec![Role::admin()])
+            .await
+            .unwrap();
+
+        // Act
+        let result = store.delete_user(&user.email).await;
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            store.get_user(&user.email).await,
+            Err(UserStoreError::UserNotFound)
+        );
+        store.add_user(user.clone()).await.unwrap();
+        assert_eq!(
+            store.get_user_roles(&user.email).await,
+            Ok(UserRoles::default())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_user_roles_collects_permissions_of_all_roles() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let user = User::new(
+            Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            Password::parse(Secret::new("password".to_string())).unwrap(),
+            false,
+        );
+        store.add_user(user.clone()).await.unwrap();
+        let support = Role::parse("support".to_owned()).unwrap();
+        let read = Permission::parse("users:read".to_owned()).unwrap();
+        store
+            .set_role_permissions(&support, vec![read.clone()])
+            .await
+            .unwrap();
+        store
+            .set_role_permissions(&Role::admin(), vec![read.clone()])
+            .await
+            .unwrap();
+        store
+            .set_user_roles(&user.email, vec![support.clone(), Role::admin()])
+            .await
+            .unwrap();
+
+        // Act
+        let result = store.get_user_roles(&user.email).await;
+
+        // Assert
+        assert_eq!(
+            result,
+            Ok(UserRoles::new(vec![Role::admin(), support], vec![read]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_user_roles_unknown_role() {
+        // Arrange
+        let mut store = HashMapUserStore::default();
+        let user = User::new(
+            Email::parse(Secret::new("test@example.com".to_string())).unwrap(),
+            Password::parse(Secret::new("password".to_string())).unwrap(),
+            false,
+        );
+        store.add_user(user.clone()).await.unwrap();
+
+        // Act
+        let result = store
+            .set_user_roles(&user.email, vec![Role::parse("ghost".to_owned()).unwrap()])
+            .await;
+
+        // Assert
+        assert_eq!(result, Err(UserStoreError::RoleNotFound));
+        assert_eq!(
+            store.get_user_roles(&user.email).await,
+            Ok(UserRoles::default())
+        );
+    }
 }
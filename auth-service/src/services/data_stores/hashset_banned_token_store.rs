@@ -1,29 +1,68 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
+
+use chrono::Utc;
+use uuid::Uuid;
 
 use crate::domain::{BannedTokenStore, BannedTokenStoreError};
 
 #[derive(Default)]
 pub struct HashSetBannedTokenStore {
-    banned_tokens: HashSet<String>,
+    // Expiry of each banned jti
+    banned_tokens: HashMap<String, i64>,
+    banned_users: HashMap<Uuid, i64>,
 }
 
 impl HashSetBannedTokenStore {
     pub fn new() -> Self {
         Self {
-            banned_tokens: HashSet::new(),
+            banned_tokens: HashMap::new(),
+            banned_users: HashMap::new(),
         }
     }
 }
 
 #[async_trait::async_trait]
 impl BannedTokenStore for HashSetBannedTokenStore {
-    async fn add_banned_token(&mut self, token: String) -> Result<(), BannedTokenStoreError> {
-        self.banned_tokens.insert(token);
+    async fn add_banned_token(
+        &mut self,
+        jti: &str,
+        expires_at: i64,
+    ) -> Result<(), BannedTokenStoreError> {
+        let now = Utc::now().timestamp();
+        self.banned_tokens
+            .retain(|_, expires_at| *expires_at >= now);
+        if expires_at >= now {
+            self.banned_tokens.insert(jti.to_owned(), expires_at);
+        }
+        Ok(())
+    }
+
+    async fn check_banned_token(&self, jti: &str) -> Result<bool, BannedTokenStoreError> {
+        let now = Utc::now().timestamp();
+        Ok(self
+            .banned_tokens
+            .get(jti)
+            .is_some_and(|expires_at| *expires_at >= now))
+    }
+
+    async fn ban_user_tokens(
+        &mut self,
+        user_id: &Uuid,
+        issued_before: i64,
+    ) -> Result<(), BannedTokenStoreError> {
+        self.banned_users.insert(*user_id, issued_before);
         Ok(())
     }
 
-    async fn check_banned_token(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
-        Ok(self.banned_tokens.contains(token))
+    async fn check_user_tokens_banned(
+        &self,
+        user_id: &Uuid,
+        issued_at: i64,
+    ) -> Result<bool, BannedTokenStoreError> {
+        Ok(self
+            .banned_users
+            .get(user_id)
+            .is_some_and(|issued_before| issued_at < *issued_before))
     }
 }
 
@@ -31,17 +70,19 @@ impl BannedTokenStore for HashSetBannedTokenStore {
 mod tests {
     use super::*;
 
+    fn expires_at() -> i64 {
+        Utc::now().timestamp() + 600
+    }
+
     #[tokio::test]
     async fn test_add_and_check_banned_token() {
         // Arrange
-        let mut store = HashSetBannedTokenStore {
-            banned_tokens: HashSet::new(),
-        };
-        let token = "banned_token".to_string();
+        let mut store = HashSetBannedTokenStore::new();
+        let jti = "banned_jti";
 
         // Act
-        store.add_banned_token(token.clone()).await.unwrap();
-        let is_banned = store.check_banned_token(&token).await.unwrap();
+        store.add_banned_token(jti, expires_at()).await.unwrap();
+        let is_banned = store.check_banned_token(jti).await.unwrap();
 
         // Assert
         assert!(is_banned);
@@ -50,13 +91,11 @@ mod tests {
     #[tokio::test]
     async fn test_check_non_existent_token() {
         // Arrange
-        let store = HashSetBannedTokenStore {
-            banned_tokens: HashSet::new(),
-        };
-        let token = "not_banned_token".to_string();
+        let store = HashSetBannedTokenStore::new();
+        let jti = "not_banned_jti";
 
         // Act
-        let is_banned = store.check_banned_token(&token).await.unwrap();
+        let is_banned = store.check_banned_token(jti).await.unwrap();
 
         // Assert
         assert!(!is_banned);
@@ -65,24 +104,61 @@ mod tests {
     #[tokio::test]
     async fn test_multiple_tokens() {
         // Arrange
-        let mut store = HashSetBannedTokenStore {
-            banned_tokens: HashSet::new(),
-        };
-        let token1 = "banned_token1".to_string();
-        let token2 = "banned_token2".to_string();
-        let token3 = "not_banned_token".to_string();
+        let mut store = HashSetBannedTokenStore::new();
+        let jti1 = "banned_jti1";
+        let jti2 = "banned_jti2";
+        let jti3 = "not_banned_jti";
 
         // Act
-        store.add_banned_token(token1.clone()).await.unwrap();
-        store.add_banned_token(token2.clone()).await.unwrap();
+        store.add_banned_token(jti1, expires_at()).await.unwrap();
+        store.add_banned_token(jti2, expires_at()).await.unwrap();
 
-        let is_token1_banned = store.check_banned_token(&token1).await.unwrap();
-        let is_token2_banned = store.check_banned_token(&token2).await.unwrap();
-        let is_token3_banned = store.check_banned_token(&token3).await.unwrap();
+        let is_token1_banned = store.check_banned_token(jti1).await.unwrap();
+        let is_token2_banned = store.check_banned_token(jti2).await.unwrap();
+        let is_token3_banned = store.check_banned_token(jti3).await.unwrap();
 
         // Assert
         assert!(is_token1_banned);
         assert!(is_token2_banned);
         assert!(!is_token3_banned);
     }
+
+    #[tokio::test]
+    async fn test_expired_token_is_forgotten() {
+        // Arrange
+        let mut store = HashSetBannedTokenStore::new();
+        let expired_at = Utc::now().timestamp() - 1;
+        store
+            .banned_tokens
+            .insert("expired_jti".to_owned(), expired_at);
+
+        // Act
+        store
+            .add_banned_token("banned_jti", expires_at())
+            .await
+            .unwrap();
+
+        // Assert
+        assert!(!store.check_banned_token("expired_jti").await.unwrap());
+        assert!(!store.banned_tokens.contains_key("expired_jti"));
+    }
+
+    #[tokio::test]
+    async fn test_ban_user_tokens() {
+        // Arrange
+        let mut store = HashSetBannedTokenStore::new();
+        let user_id = Uuid::new_v4();
+        let other_user_id = Uuid::new_v4();
+
+        // Act
+        store.ban_user_tokens(&user_id, 100).await.unwrap();
+
+        // Assert
+        assert!(store.check_user_tokens_banned(&user_id, 99).await.unwrap());
+        assert!(!store.check_user_tokens_banned(&user_id, 100).await.unwrap());
+        assert!(!store
+            .check_user_tokens_banned(&other_user_id, 99)
+            .await
+            .unwrap());
+    }
 }
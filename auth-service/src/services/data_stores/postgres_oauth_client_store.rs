@@ -0,0 +1,155 @@
+use sqlx::PgPool;
+
+use uuid::Uuid;
+
+use crate::domain::{OAuthClient, OAuthClientStore, OAuthClientStoreError, OAuthConsent};
+
+pub struct PostgresOAuthClientStore {
+    pool: PgPool,
+}
+
+impl PostgresOAuthClientStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthClientStore for PostgresOAuthClientStore {
+    #[tracing::instrument(name = "Adding OAuth client to PostgreSQL", skip_all)]
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_clients (client_id, name, redirect_uris, client_secret_hash)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            client.client_id,
+            client.name,
+            &client.redirect_uris,
+            client.secret_hash
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving OAuth client from PostgreSQL", skip_all)]
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        sqlx::query!(
+            r#"
+            SELECT client_id, name, redirect_uris, client_secret_hash
+            FROM oauth_clients
+            WHERE client_id = $1
+            "#,
+            client_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?
+        .map(|row| OAuthClient {
+            client_id: row.client_id,
+            name: row.name,
+            redirect_uris: row.redirect_uris,
+            secret_hash: row.client_secret_hash,
+        })
+        .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+
+    #[tracing::instrument(name = "Granting OAuth consent in PostgreSQL", skip_all)]
+    async fn grant_consent(
+        &mut self,
+        user_id: &Uuid,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<(), OAuthClientStoreError> {
+        self.get_client(client_id).await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_consents (user_id, client_id, scope)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, client_id) DO UPDATE
+            SET scope = ARRAY(
+                SELECT DISTINCT unnest(oauth_consents.scope || EXCLUDED.scope) ORDER BY 1
+            )
+            "#,
+            user_id,
+            client_id,
+            scopes
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Checking OAuth consent in PostgreSQL", skip_all)]
+    async fn has_consent(
+        &self,
+        user_id: &Uuid,
+        client_id: &str,
+        scopes: &[String],
+    ) -> Result<bool, OAuthClientStoreError> {
+        let has_consent = sqlx::query!(
+            r#"
+            SELECT scope @> $3 AS "covered!"
+            FROM oauth_consents
+            WHERE user_id = $1 AND client_id = $2
+            "#,
+            user_id,
+            client_id,
+            scopes
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?
+        .is_some_and(|row| row.covered);
+
+        Ok(has_consent)
+    }
+
+    #[tracing::instrument(name = "Listing OAuth consents from PostgreSQL", skip_all)]
+    async fn list_consents(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<Vec<OAuthConsent>, OAuthClientStoreError> {
+        let consents = sqlx::query!(
+            r#"
+            SELECT client_id, scope
+            FROM oauth_consents
+            WHERE user_id = $1
+            ORDER BY client_id
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?
+        .into_iter()
+        .map(|row| OAuthConsent {
+            client_id: row.client_id,
+            scopes: row.scope,
+        })
+        .collect();
+
+        Ok(consents)
+    }
+
+    #[tracing::instrument(name = "Deleting OAuth consents from PostgreSQL", skip_all)]
+    async fn delete_consents(&mut self, user_id: &Uuid) -> Result<(), OAuthClientStoreError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM oauth_consents WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+}
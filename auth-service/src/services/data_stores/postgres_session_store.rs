@@ -0,0 +1,175 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    domain::{Session, SessionStore, SessionStoreError},
+    utils::auth::REFRESH_TOKEN_TTL_SECONDS,
+};
+
+pub struct PostgresSessionStore {
+    pool: PgPool,
+}
+
+impl PostgresSessionStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for PostgresSessionStore {
+    #[tracing::instrument(name = "Adding session to PostgreSQL", skip_all)]
+    async fn add_session(&mut self, session: Session) -> Result<(), SessionStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO sessions (id, user_id, created_at, last_seen_at, user_agent, ip, revoked, amr)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            session.id,
+            session.user_id,
+            session.created_at,
+            session.last_seen_at,
+            session.user_agent,
+            session.ip,
+            session.revoked,
+            &session.amr
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SessionStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving session from PostgreSQL", skip_all)]
+    async fn get_session(&self, id: &str) -> Result<Session, SessionStoreError> {
+        sqlx::query!(
+            r#"
+            SELECT id, user_id, created_at, last_seen_at, user_agent, ip, revoked, amr
+            FROM sessions
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| SessionStoreError::UnexpectedError(e.into()))?
+        .map(|row| Session {
+            id: row.id,
+            user_id: row.user_id,
+            created_at: row.created_at,
+            last_seen_at: row.last_seen_at,
+            user_agent: row.user_agent,
+            ip: row.ip,
+            revoked: row.revoked,
+            amr: row.amr,
+        })
+        .ok_or(SessionStoreError::SessionNotFound)
+    }
+
+    #[tracing::instrument(name = "Listing sessions from PostgreSQL", skip_all)]
+    async fn list_sessions(&self, user_id: &Uuid) -> Result<Vec<Session>, SessionStoreError> {
+        let active_since = Utc::now().timestamp() - REFRESH_TOKEN_TTL_SECONDS;
+        let sessions = sqlx::query!(
+            r#"
+            SELECT id, user_id, created_at, last_seen_at, user_agent, ip, revoked, amr
+            FROM sessions
+            WHERE user_id = $1 AND NOT revoked AND last_seen_at > $2
+            ORDER BY last_seen_at DESC
+            "#,
+            user_id,
+            active_since
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| SessionStoreError::UnexpectedError(e.into()))?
+        .into_iter()
+        .map(|row| Session {
+            id: row.id,
+            user_id: row.user_id,
+            created_at: row.created_at,
+            last_seen_at: row.last_seen_at,
+            user_agent: row.user_agent,
+            ip: row.ip,
+            revoked: row.revoked,
+            amr: row.amr,
+        })
+        .collect();
+
+        Ok(sessions)
+    }
+
+    #[tracing::instrument(name = "Touching session in PostgreSQL", skip_all)]
+    async fn touch_session(
+        &mut self,
+        id: &str,
+        last_seen_at: i64,
+    ) -> Result<(), SessionStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE sessions SET last_seen_at = $2 WHERE id = $1
+            "#,
+            id,
+            last_seen_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SessionStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(SessionStoreError::SessionNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Revoking session in PostgreSQL", skip_all)]
+    async fn revoke_session(&mut self, id: &str) -> Result<(), SessionStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE sessions SET revoked = TRUE WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SessionStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(SessionStoreError::SessionNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Revoking user sessions in PostgreSQL", skip_all)]
+    async fn revoke_user_sessions(&mut self, user_id: &Uuid) -> Result<(), SessionStoreError> {
+        sqlx::query!(
+            r#"
+            UPDATE sessions SET revoked = TRUE WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SessionStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Deleting user sessions from PostgreSQL", skip_all)]
+    async fn delete_user_sessions(&mut self, user_id: &Uuid) -> Result<(), SessionStoreError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM sessions WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| SessionStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+}
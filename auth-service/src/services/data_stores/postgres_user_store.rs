@@ -1,4 +1,10 @@
-use color_eyre::eyre::{eyre, Result};
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use color_eyre::eyre::{eyre, Context, Result};
+use data_encoding::BASE64;
+use sha2::{Digest, Sha256};
 
 use argon2::{
     password_hash::SaltString, Algorithm, Argon2, Params, PasswordHash, PasswordHasher,
@@ -7,9 +13,14 @@ use argon2::{
 
 use secrecy::{ExposeSecret, Secret};
 use sqlx::PgPool;
+use uuid::Uuid;
 
 use crate::{
-    domain::{Password, User, UserStore, UserStoreError},
+    domain::{
+        Password, Permission, RecoveryCode, Role, TotpSecret, TwoFAMethod, User, UserRoles,
+        UserStore, UserStoreError,
+    },
+    utils::constants::TOTP_ENCRYPTION_KEY,
     Email,
 };
 
@@ -48,12 +59,16 @@ impl UserStore for PostgresUserStore {
 
         sqlx::query!(
             r#"
-            INSERT INTO users (email, password_hash, requires_2fa)
-            VALUES ($1, $2, $3)
+            INSERT INTO users (id, email, password_hash, requires_2fa, two_fa_method, verified, disabled)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             "#,
+            user.id,
             user.email.as_ref().expose_secret(),
             password_hash,
-            user.requires_2fa
+            user.requires_2fa,
+            user.two_fa_method.as_str(),
+            user.verified,
+            user.disabled
         )
         .execute(&self.pool)
         .await
@@ -64,9 +79,10 @@ impl UserStore for PostgresUserStore {
 
     #[tracing::instrument(name = "Retrieving user from PostgreSQL", skip_all)]
     async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
-        sqlx::query!(
+        sqlx::query_as!(
+            UserRow,
             r#"
-            SELECT email, password_hash, requires_2fa
+            SELECT id, email, password_hash, requires_2fa, two_fa_method, verified, disabled
             FROM users
             WHERE email = $1
             "#,
@@ -75,16 +91,26 @@ impl UserStore for PostgresUserStore {
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
-        .map(|row| {
-            Ok(User {
-                email: Email::parse(Secret::new(row.email))
-                    .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?,
-                password: Password::parse(Secret::new(row.password_hash))
-                    .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?,
-                requires_2fa: row.requires_2fa,
-            })
-        })
         .ok_or(UserStoreError::UserNotFound)?
+        .try_into()
+    }
+
+    #[tracing::instrument(name = "Retrieving user by id from PostgreSQL", skip_all)]
+    async fn get_user_by_id(&self, id: &Uuid) -> Result<User, UserStoreError> {
+        sqlx::query_as!(
+            UserRow,
+            r#"
+            SELECT id, email, password_hash, requires_2fa, two_fa_method, verified, disabled
+            FROM users
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?
+        .try_into()
     }
 
     #[tracing::instrument(name = "Validating user credentials in PostgreSQL", skip_all)]
@@ -116,6 +142,688 @@ impl UserStore for PostgresUserStore {
             Err(UserStoreError::InvalidCredentials)
         }
     }
+
+    #[tracing::instrument(name = "Updating user password in PostgreSQL", skip_all)]
+    async fn update_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        let password_hash = compute_password_hash_async(password.as_ref().to_owned())
+            .await
+            .map_err(UserStoreError::UnexpectedError)?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET password_hash = $1
+            WHERE email = $2
+            "#,
+            password_hash,
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Marking user as verified in PostgreSQL", skip_all)]
+    async fn mark_verified(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET verified = TRUE
+            WHERE email = $1
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Updating user email in PostgreSQL", skip_all)]
+    async fn update_email(
+        &mut self,
+        email: &Email,
+        new_email: &Email,
+    ) -> Result<(), UserStoreError> {
+        // Sessions, recovery codes, consents and roles reference the id, so they stay with the user
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET email = $1, verified = FALSE
+            WHERE email = $2
+            "#,
+            new_email.as_ref().expose_secret(),
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(e) if e.is_unique_violation() => {
+                UserStoreError::UserAlreadyExists
+            }
+            e => UserStoreError::UnexpectedError(e.into()),
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Storing pending TOTP secret in PostgreSQL", skip_all)]
+    async fn set_pending_totp_secret(
+        &mut self,
+        email: &Email,
+        secret: TotpSecret,
+    ) -> Result<(), UserStoreError> {
+        let encrypted_secret =
+            encrypt_totp_secret(&secret).map_err(UserStoreError::UnexpectedError)?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET totp_pending_secret = $1
+            WHERE email = $2
+            "#,
+            encrypted_secret,
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving pending TOTP secret from PostgreSQL", skip_all)]
+    async fn get_pending_totp_secret(&self, email: &Email) -> Result<TotpSecret, UserStoreError> {
+        let encrypted_secret = sqlx::query!(
+            r#"
+            SELECT totp_pending_secret
+            FROM users
+            WHERE email = $1
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?
+        .totp_pending_secret
+        .ok_or(UserStoreError::TotpSecretNotFound)?;
+
+        decrypt_totp_secret(&encrypted_secret).map_err(UserStoreError::UnexpectedError)
+    }
+
+    #[tracing::instrument(name = "Confirming TOTP secret in PostgreSQL", skip_all)]
+    async fn confirm_totp_secret(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET totp_secret = totp_pending_secret,
+                totp_pending_secret = NULL,
+                requires_2fa = TRUE,
+                two_fa_method = $1
+            WHERE email = $2 AND totp_pending_secret IS NOT NULL
+            "#,
+            TwoFAMethod::Totp.as_str(),
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::TotpSecretNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving TOTP secret from PostgreSQL", skip_all)]
+    async fn get_totp_secret(&self, email: &Email) -> Result<TotpSecret, UserStoreError> {
+        let encrypted_secret = sqlx::query!(
+            r#"
+            SELECT totp_secret
+            FROM users
+            WHERE email = $1
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .ok_or(UserStoreError::UserNotFound)?
+        .totp_secret
+        .ok_or(UserStoreError::TotpSecretNotFound)?;
+
+        decrypt_totp_secret(&encrypted_secret).map_err(UserStoreError::UnexpectedError)
+    }
+
+    #[tracing::instrument(name = "Storing recovery codes in PostgreSQL", skip_all)]
+    async fn set_recovery_codes(
+        &mut self,
+        email: &Email,
+        codes: Vec<RecoveryCode>,
+    ) -> Result<(), UserStoreError> {
+        let existing_user = sqlx::query!(
+            r#"
+            SELECT id FROM users WHERE email = $1
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        let Some(existing_user) = existing_user else {
+            return Err(UserStoreError::UserNotFound);
+        };
+
+        // Recovery codes are hashed like passwords, they grant the same access.
+        // The hashes are computed concurrently as there is a whole batch of them
+        let hash_tasks: Vec<_> = codes
+            .into_iter()
+            .map(|code| tokio::spawn(compute_password_hash_async(code.as_ref().to_owned())))
+            .collect();
+        let mut code_hashes = Vec::with_capacity(hash_tasks.len());
+        for hash_task in hash_tasks {
+            let code_hash = hash_task
+                .await
+                .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+                .map_err(UserStoreError::UnexpectedError)?;
+            code_hashes.push(code_hash);
+        }
+
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM recovery_codes
+            WHERE user_id = $1
+            "#,
+            existing_user.id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        for code_hash in code_hashes {
+            sqlx::query!(
+                r#"
+                INSERT INTO recovery_codes (user_id, code_hash)
+                VALUES ($1, $2)
+                "#,
+                existing_user.id,
+                code_hash
+            )
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        }
+
+        transaction
+            .commit()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Using recovery code in PostgreSQL", skip_all)]
+    async fn use_recovery_code(
+        &mut self,
+        email: &Email,
+        code: &RecoveryCode,
+    ) -> Result<(), UserStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT recovery_codes.id, recovery_codes.code_hash
+            FROM recovery_codes
+            JOIN users ON users.id = recovery_codes.user_id
+            WHERE users.email = $1
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        for row in rows {
+            if verify_password_hash(Secret::new(row.code_hash), code.as_ref().to_owned())
+                .await
+                .is_err()
+            {
+                continue;
+            }
+
+            // Deleting the row is what makes the code single-use
+            let result = sqlx::query!(
+                r#"
+                DELETE FROM recovery_codes
+                WHERE id = $1
+                "#,
+                row.id
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+            // Another request may have used the same code in the meantime
+            if result.rows_affected() == 0 {
+                return Err(UserStoreError::InvalidCredentials);
+            }
+
+            return Ok(());
+        }
+
+        Err(UserStoreError::InvalidCredentials)
+    }
+
+    #[tracing::instrument(name = "Setting role permissions in PostgreSQL", skip_all)]
+    async fn set_role_permissions(
+        &mut self,
+        role: &Role,
+        permissions: Vec<Permission>,
+    ) -> Result<(), UserStoreError> {
+        let permissions: Vec<String> = permissions
+            .iter()
+            .map(|permission| permission.as_ref().to_owned())
+            .collect();
+
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO roles (name)
+            VALUES ($1)
+            ON CONFLICT DO NOTHING
+            "#,
+            role.as_ref()
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM role_permissions
+            WHERE role = $1
+            "#,
+            role.as_ref()
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO role_permissions (role, permission)
+            SELECT $1, permission FROM UNNEST($2::TEXT[]) AS permission
+            ON CONFLICT DO NOTHING
+            "#,
+            role.as_ref(),
+            &permissions
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Setting user roles in PostgreSQL", skip_all)]
+    async fn set_user_roles(
+        &mut self,
+        email: &Email,
+        roles: Vec<Role>,
+    ) -> Result<(), UserStoreError> {
+        let mut roles: Vec<String> = roles.iter().map(|role| role.as_ref().to_owned()).collect();
+        roles.sort();
+        roles.dedup();
+
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        let existing_user = sqlx::query!(
+            r#"
+            SELECT id FROM users WHERE email = $1
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        let Some(existing_user) = existing_user else {
+            return Err(UserStoreError::UserNotFound);
+        };
+
+        let known_roles = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM roles WHERE name = ANY($1)
+            "#,
+            &roles
+        )
+        .fetch_one(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .count;
+        if known_roles != roles.len() as i64 {
+            return Err(UserStoreError::RoleNotFound);
+        }
+
+        sqlx::query!(
+            r#"
+            DELETE FROM user_roles
+            WHERE user_id = $1
+            "#,
+            existing_user.id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_roles (user_id, role)
+            SELECT $1, role FROM UNNEST($2::TEXT[]) AS role
+            "#,
+            existing_user.id,
+            &roles
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving user roles from PostgreSQL", skip_all)]
+    async fn get_user_roles(&self, email: &Email) -> Result<UserRoles, UserStoreError> {
+        let existing_user = sqlx::query!(
+            r#"
+            SELECT id FROM users WHERE email = $1
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+        let Some(existing_user) = existing_user else {
+            return Err(UserStoreError::UserNotFound);
+        };
+
+        let roles = sqlx::query!(
+            r#"
+            SELECT role FROM user_roles WHERE user_id = $1
+            "#,
+            existing_user.id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .into_iter()
+        .map(|row| Role::parse(row.role).map_err(UserStoreError::UnexpectedError))
+        .collect::<Result<Vec<_>, _>>()?;
+
+        let permissions = sqlx::query!(
+            r#"
+            SELECT role_permissions.permission
+            FROM role_permissions
+            JOIN user_roles ON user_roles.role = role_permissions.role
+            WHERE user_roles.user_id = $1
+            "#,
+            existing_user.id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .into_iter()
+        .map(|row| Permission::parse(row.permission).map_err(UserStoreError::UnexpectedError))
+        .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(UserRoles::new(roles, permissions))
+    }
+
+    #[tracing::instrument(name = "Listing users from PostgreSQL", skip_all)]
+    async fn list_users(
+        &self,
+        cursor: Option<&Email>,
+        limit: usize,
+        search: Option<&str>,
+    ) -> Result<Vec<User>, UserStoreError> {
+        // Byte order keeps the cursor stable regardless of the database collation
+        let cursor = cursor.map(|email| email.as_ref().expose_secret().as_str());
+
+        sqlx::query_as!(
+            UserRow,
+            r#"
+            SELECT id, email, password_hash, requires_2fa, two_fa_method, verified, disabled
+            FROM users
+            WHERE ($1::TEXT IS NULL OR email COLLATE "C" > $1)
+              AND ($2::TEXT IS NULL OR email ILIKE $2)
+            ORDER BY email COLLATE "C"
+            LIMIT $3
+            "#,
+            cursor,
+            search.map(search_pattern),
+            limit as i64
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .into_iter()
+        .map(User::try_from)
+        .collect()
+    }
+
+    #[tracing::instrument(name = "Counting users in PostgreSQL", skip_all)]
+    async fn count_users(&self, search: Option<&str>) -> Result<u64, UserStoreError> {
+        let count = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM users
+            WHERE ($1::TEXT IS NULL OR email ILIKE $1)
+            "#,
+            search.map(search_pattern)
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?
+        .count;
+
+        Ok(count as u64)
+    }
+
+    #[tracing::instrument(name = "Setting user disabled flag in PostgreSQL", skip_all)]
+    async fn set_disabled(&mut self, email: &Email, disabled: bool) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET disabled = $1
+            WHERE email = $2
+            "#,
+            disabled,
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Setting user 2FA requirement in PostgreSQL", skip_all)]
+    async fn set_requires_2fa(
+        &mut self,
+        email: &Email,
+        requires_2fa: bool,
+    ) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET requires_2fa = $1
+            WHERE email = $2
+            "#,
+            requires_2fa,
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Deleting user from PostgreSQL", skip_all)]
+    async fn delete_user(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        // Sessions, recovery codes and roles of the user go with it through ON DELETE CASCADE
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM users
+            WHERE email = $1
+            "#,
+            email.as_ref().expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+}
+
+// A row of the `users` table, without the TOTP secrets
+struct UserRow {
+    id: Uuid,
+    email: String,
+    password_hash: String,
+    requires_2fa: bool,
+    two_fa_method: String,
+    verified: bool,
+    disabled: bool,
+}
+
+impl TryFrom<UserRow> for User {
+    type Error = UserStoreError;
+
+    fn try_from(row: UserRow) -> Result<Self, Self::Error> {
+        Ok(User {
+            id: row.id,
+            email: Email::parse(Secret::new(row.email))
+                .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?,
+            password: Password::parse(Secret::new(row.password_hash))
+                .map_err(|e| UserStoreError::UnexpectedError(eyre!(e)))?,
+            requires_2fa: row.requires_2fa,
+            two_fa_method: TwoFAMethod::parse(&row.two_fa_method)
+                .map_err(UserStoreError::UnexpectedError)?,
+            verified: row.verified,
+            disabled: row.disabled,
+        })
+    }
+}
+
+// Helper function to turn a search term into an ILIKE pattern matching it anywhere.
+// The LIKE wildcards in the term are escaped so they match literally
+fn search_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+// Helper function to derive the AES-256 key used for TOTP secrets from the configured key
+fn totp_encryption_key() -> Key<Aes256Gcm> {
+    let digest = Sha256::digest(TOTP_ENCRYPTION_KEY.expose_secret().as_bytes());
+    *Key::<Aes256Gcm>::from_slice(&digest)
+}
+
+// Helper function to encrypt a TOTP secret before persisting it in the database.
+// The random nonce is stored in front of the ciphertext
+fn encrypt_totp_secret(secret: &TotpSecret) -> Result<String> {
+    let cipher = Aes256Gcm::new(&totp_encryption_key());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, secret.as_ref().expose_secret().as_bytes())
+        .map_err(|_| eyre!("Failed to encrypt TOTP secret"))?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(BASE64.encode(&payload))
+}
+
+// Helper function to decrypt a TOTP secret read from the database
+fn decrypt_totp_secret(encrypted_secret: &str) -> Result<TotpSecret> {
+    let payload = BASE64
+        .decode(encrypted_secret.as_bytes())
+        .wrap_err("Failed to decode TOTP secret")?;
+    if payload.len() < 12 {
+        return Err(eyre!("Encrypted TOTP secret is too short"));
+    }
+    let (nonce, ciphertext) = payload.split_at(12);
+
+    let cipher = Aes256Gcm::new(&totp_encryption_key());
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| eyre!("Failed to decrypt TOTP secret"))?;
+
+    TotpSecret::parse(Secret::new(
+        String::from_utf8(plaintext).wrap_err("TOTP secret is not valid UTF-8")?,
+    ))
 }
 
 // Helper function to verify if a given password matches an expected hash
@@ -156,3 +864,49 @@ async fn compute_password_hash_async(password: Secret<String>) -> Result<String>
     })
     .await?
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_pattern_escapes_wildcards() {
+        // Arrange
+        let search = "50%_off\\";
+
+        // Act
+        let pattern = search_pattern(search);
+
+        // Assert
+        assert_eq!(pattern, "%50\\%\\_off\\\\%");
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt_totp_secret() {
+        // Arrange
+        let secret = TotpSecret::default();
+
+        // Act
+        let encrypted_secret = encrypt_totp_secret(&secret).unwrap();
+        let decrypted_secret = decrypt_totp_secret(&encrypted_secret).unwrap();
+
+        // Assert
+        assert!(!encrypted_secret.contains(secret.as_ref().expose_secret()));
+        assert_eq!(decrypted_secret, secret);
+    }
+
+    #[test]
+    fn test_decrypt_tampered_totp_secret() {
+        // Arrange
+        let encrypted_secret = encrypt_totp_secret(&TotpSecret::default()).unwrap();
+        let mut payload = BASE64.decode(encrypted_secret.as_bytes()).unwrap();
+        let last = payload.len() - 1;
+        payload[last] ^= 0x01;
+
+        // Act
+        let result = decrypt_totp_secret(&BASE64.encode(&payload));
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::Context;
+use redis::{Commands, Connection};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        AuthorizationCode, AuthorizationCodeStore, AuthorizationCodeStoreError, AuthorizationGrant,
+    },
+    utils::constants::AUTHORIZATION_CODE_TTL_SECONDS,
+};
+
+pub struct RedisAuthorizationCodeStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisAuthorizationCodeStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for RedisAuthorizationCodeStore {
+    #[tracing::instrument(name = "AuthorizationCodeStore", skip_all)]
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationGrant,
+    ) -> Result<(), AuthorizationCodeStoreError> {
+        let ttl: u64 = AUTHORIZATION_CODE_TTL_SECONDS
+            .try_into()
+            .wrap_err("Failed to cast AUTHORIZATION_CODE_TTL_SECONDS to u64.")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+        let stored = StoredAuthorizationGrant {
+            client_id: grant.client_id,
+            user_id: grant.user_id,
+            redirect_uri: grant.redirect_uri,
+            scope: grant.scope,
+            code_challenge: grant.code_challenge,
+            session_id: grant.session_id,
+            nonce: grant.nonce,
+            auth_time: grant.auth_time,
+            amr: grant.amr,
+        };
+        let serialized_grant = serde_json::to_string(&stored)
+            .wrap_err("Failed to serialize authorization grant.")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        self.conn
+            .write()
+            .await
+            .set_ex::<_, _, ()>(get_key(&code), serialized_grant, ttl)
+            .wrap_err("Failed to set authorization code in Redis.")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "AuthorizationCodeStore", skip_all)]
+    async fn consume_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationGrant, AuthorizationCodeStoreError> {
+        // GETDEL reads and removes the code atomically, so it cannot be exchanged twice
+        let serialized_grant = self
+            .conn
+            .write()
+            .await
+            .get_del::<_, Option<String>>(get_key(code))
+            .wrap_err("Failed to get authorization code from Redis.")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?
+            .ok_or(AuthorizationCodeStoreError::CodeNotFound)?;
+
+        let stored: StoredAuthorizationGrant = serde_json::from_str(&serialized_grant)
+            .wrap_err("Failed to deserialize authorization grant.")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        Ok(AuthorizationGrant {
+            client_id: stored.client_id,
+            user_id: stored.user_id,
+            redirect_uri: stored.redirect_uri,
+            scope: stored.scope,
+            code_challenge: stored.code_challenge,
+            session_id: stored.session_id,
+            nonce: stored.nonce,
+            auth_time: stored.auth_time,
+            amr: stored.amr,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredAuthorizationGrant {
+    client_id: String,
+    user_id: Uuid,
+    redirect_uri: String,
+    scope: String,
+    code_challenge: String,
+    session_id: String,
+    nonce: Option<String>,
+    auth_time: i64,
+    amr: Vec<String>,
+}
+
+const AUTHORIZATION_CODE_KEY_PREFIX: &str = "authorization_code:";
+
+fn get_key(code: &AuthorizationCode) -> String {
+    format!(
+        "{}{}",
+        AUTHORIZATION_CODE_KEY_PREFIX,
+        code.as_ref().expose_secret()
+    )
+}
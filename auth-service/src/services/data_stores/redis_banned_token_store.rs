@@ -1,12 +1,16 @@
 use std::sync::Arc;
 
+use chrono::Utc;
 use color_eyre::eyre::Context;
+use data_encoding::BASE64URL_NOPAD;
 use redis::{Commands, Connection};
+use serde::Deserialize;
 use tokio::sync::RwLock;
+use uuid::Uuid;
 
 use crate::{
     domain::{BannedTokenStore, BannedTokenStoreError},
-    utils::auth::TOKEN_TTL_SECONDS,
+    utils::auth::REFRESH_TOKEN_TTL_SECONDS,
 };
 
 pub struct RedisBannedTokenStore {
@@ -17,23 +21,61 @@ impl RedisBannedTokenStore {
     pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
         Self { conn }
     }
+
+    // Earlier versions stored whole tokens under `banned_token:<JWT>`. Rewrites those entries
+    // as `banned_jti:<jti>` with their remaining TTL and deletes them, so no bearer token is
+    // left in Redis. Tokens without a jti can no longer pass validation, so they are just
+    // deleted. Returns the number of legacy entries removed.
+    #[tracing::instrument(name = "Migrating banned tokens", skip_all)]
+    pub async fn migrate_legacy_keys(&mut self) -> Result<usize, BannedTokenStoreError> {
+        let mut conn = self.conn.write().await;
+
+        let legacy_keys: Vec<String> = conn
+            .scan_match::<_, String>(format!("{}*", LEGACY_BANNED_TOKEN_KEY_PREFIX))
+            .wrap_err("Failed to scan legacy banned tokens in Redis.")
+            .map_err(BannedTokenStoreError::UnexpectedError)?
+            .collect();
+
+        for legacy_key in &legacy_keys {
+            let token = &legacy_key[LEGACY_BANNED_TOKEN_KEY_PREFIX.len()..];
+            let ttl: i64 = conn
+                .ttl(legacy_key)
+                .wrap_err("Failed to get TTL of legacy banned token from Redis.")
+                .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+            if let (Some(jti), true) = (legacy_token_jti(token), ttl > 0) {
+                conn.set_ex::<_, _, ()>(get_key(&jti), true, ttl as u64)
+                    .wrap_err("Failed to set banned token in Redis.")
+                    .map_err(BannedTokenStoreError::UnexpectedError)?;
+            }
+
+            conn.del::<_, ()>(legacy_key)
+                .wrap_err("Failed to delete legacy banned token from Redis.")
+                .map_err(BannedTokenStoreError::UnexpectedError)?;
+        }
+
+        Ok(legacy_keys.len())
+    }
 }
 
 #[async_trait::async_trait]
 impl BannedTokenStore for RedisBannedTokenStore {
     #[tracing::instrument(name = "BannedTokenStore", skip_all)]
-    async fn add_banned_token(&mut self, token: String) -> Result<(), BannedTokenStoreError> {
-        let token_key = get_key(&token);
-        let value = true;
-        let ttl: u64 = TOKEN_TTL_SECONDS
-            .try_into()
-            .wrap_err("Failed to cast TOKEN_TTL_SECONDS to u64.")
-            .map_err(BannedTokenStoreError::UnexpectedError)?;
+    async fn add_banned_token(
+        &mut self,
+        jti: &str,
+        expires_at: i64,
+    ) -> Result<(), BannedTokenStoreError> {
+        // An expired token is rejected anyway, so there is nothing to remember
+        let ttl = expires_at - Utc::now().timestamp();
+        if ttl <= 0 {
+            return Ok(());
+        }
 
         self.conn
             .write()
             .await
-            .set_ex::<_, _, ()>(token_key, value, ttl)
+            .set_ex::<_, _, ()>(get_key(jti), true, ttl as u64)
             .wrap_err("Failed to set banned token in Redis.")
             .map_err(BannedTokenStoreError::UnexpectedError)?;
 
@@ -41,8 +83,8 @@ impl BannedTokenStore for RedisBannedTokenStore {
     }
 
     #[tracing::instrument(name = "BannedTokenStore", skip_all)]
-    async fn check_banned_token(&self, token: &str) -> Result<bool, BannedTokenStoreError> {
-        let token_key = get_key(&token);
+    async fn check_banned_token(&self, jti: &str) -> Result<bool, BannedTokenStoreError> {
+        let token_key = get_key(jti);
 
         let is_banned = self
             .conn
@@ -54,11 +96,95 @@ impl BannedTokenStore for RedisBannedTokenStore {
 
         Ok(is_banned)
     }
+
+    #[tracing::instrument(name = "BannedTokenStore", skip_all)]
+    async fn ban_user_tokens(
+        &mut self,
+        user_id: &Uuid,
+        issued_before: i64,
+    ) -> Result<(), BannedTokenStoreError> {
+        // Keep the entry around as long as any token of the user could still be alive,
+        // which includes sessions that are kept alive by refresh tokens
+        let ttl: u64 = REFRESH_TOKEN_TTL_SECONDS
+            .try_into()
+            .wrap_err("Failed to cast REFRESH_TOKEN_TTL_SECONDS to u64.")
+            .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+        self.conn
+            .write()
+            .await
+            .set_ex::<_, _, ()>(get_user_key(user_id), issued_before, ttl)
+            .wrap_err("Failed to set banned user tokens in Redis.")
+            .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "BannedTokenStore", skip_all)]
+    async fn check_user_tokens_banned(
+        &self,
+        user_id: &Uuid,
+        issued_at: i64,
+    ) -> Result<bool, BannedTokenStoreError> {
+        let issued_before = self
+            .conn
+            .write()
+            .await
+            .get::<_, Option<i64>>(get_user_key(user_id))
+            .wrap_err("Failed to get banned user tokens from Redis.")
+            .map_err(BannedTokenStoreError::UnexpectedError)?;
+
+        Ok(issued_before.is_some_and(|issued_before| issued_at < issued_before))
+    }
 }
 
 // We are using a key prefix to prevent collisions and organize data!
-const BANNED_TOKEN_KEY_PREFIX: &str = "banned_token:";
+const BANNED_TOKEN_KEY_PREFIX: &str = "banned_jti:";
+const LEGACY_BANNED_TOKEN_KEY_PREFIX: &str = "banned_token:";
+const BANNED_USER_KEY_PREFIX: &str = "banned_user_tokens:";
+
+fn get_key(jti: &str) -> String {
+    format!("{}{}", BANNED_TOKEN_KEY_PREFIX, jti)
+}
+
+#[derive(Deserialize)]
+struct LegacyTokenClaims {
+    jti: Option<String>,
+}
+
+// Reads the jti from the payload of a banned token. The signature does not matter here,
+// the token was checked before it was banned.
+fn legacy_token_jti(token: &str) -> Option<String> {
+    let payload = token.split('.').nth(1)?;
+    let payload = BASE64URL_NOPAD.decode(payload.as_bytes()).ok()?;
+    serde_json::from_slice::<LegacyTokenClaims>(&payload)
+        .ok()?
+        .jti
+}
 
-fn get_key(token: &str) -> String {
-    format!("{}{}", BANNED_TOKEN_KEY_PREFIX, token)
+fn get_user_key(user_id: &Uuid) -> String {
+    format!("{}{}", BANNED_USER_KEY_PREFIX, user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_token_jti() {
+        // Arrange
+        let payload = BASE64URL_NOPAD.encode(br#"{"sub":"test@example.com","jti":"abc"}"#);
+        let legacy_payload = BASE64URL_NOPAD.encode(br#"{"sub":"test@example.com"}"#);
+
+        // Act & Assert
+        assert_eq!(
+            legacy_token_jti(&format!("header.{}.signature", payload)),
+            Some("abc".to_owned())
+        );
+        assert_eq!(
+            legacy_token_jti(&format!("header.{}.signature", legacy_payload)),
+            None
+        );
+        assert_eq!(legacy_token_jti("not_a_token"), None);
+    }
 }
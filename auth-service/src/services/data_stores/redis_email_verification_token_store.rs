@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::Context;
+use redis::{Commands, Connection};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        Email, EmailVerificationToken, EmailVerificationTokenStore,
+        EmailVerificationTokenStoreError,
+    },
+    utils::constants::EMAIL_VERIFICATION_TOKEN_TTL_SECONDS,
+};
+
+pub struct RedisEmailVerificationTokenStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisEmailVerificationTokenStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailVerificationTokenStore for RedisEmailVerificationTokenStore {
+    #[tracing::instrument(name = "EmailVerificationTokenStore", skip_all)]
+    async fn add_token(
+        &mut self,
+        token: EmailVerificationToken,
+        email: Email,
+    ) -> Result<(), EmailVerificationTokenStoreError> {
+        let ttl: u64 = EMAIL_VERIFICATION_TOKEN_TTL_SECONDS
+            .try_into()
+            .wrap_err("Failed to cast EMAIL_VERIFICATION_TOKEN_TTL_SECONDS to u64.")
+            .map_err(EmailVerificationTokenStoreError::UnexpectedError)?;
+
+        self.conn
+            .write()
+            .await
+            .set_ex::<_, _, ()>(
+                get_key(&token),
+                email.as_ref().expose_secret().to_owned(),
+                ttl,
+            )
+            .wrap_err("Failed to set email verification token in Redis.")
+            .map_err(EmailVerificationTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "EmailVerificationTokenStore", skip_all)]
+    async fn consume_token(
+        &mut self,
+        token: &EmailVerificationToken,
+    ) -> Result<Email, EmailVerificationTokenStoreError> {
+        // GETDEL reads and removes the token atomically, so it cannot be used twice
+        let email = self
+            .conn
+            .write()
+            .await
+            .get_del::<_, Option<String>>(get_key(token))
+            .wrap_err("Failed to get email verification token from Redis.")
+            .map_err(EmailVerificationTokenStoreError::UnexpectedError)?
+            .ok_or(EmailVerificationTokenStoreError::TokenNotFound)?;
+
+        Email::parse(Secret::new(email)).map_err(EmailVerificationTokenStoreError::UnexpectedError)
+    }
+
+    #[tracing::instrument(name = "EmailVerificationTokenStore", skip_all)]
+    async fn add_email_change_token(
+        &mut self,
+        token: EmailVerificationToken,
+        user_id: Uuid,
+        new_email: Email,
+    ) -> Result<(), EmailVerificationTokenStoreError> {
+        let ttl: u64 = EMAIL_VERIFICATION_TOKEN_TTL_SECONDS
+            .try_into()
+            .wrap_err("Failed to cast EMAIL_VERIFICATION_TOKEN_TTL_SECONDS to u64.")
+            .map_err(EmailVerificationTokenStoreError::UnexpectedError)?;
+        let value = serde_json::to_string(&StoredEmailChange {
+            user_id,
+            new_email: new_email.as_ref().expose_secret().to_owned(),
+        })
+        .wrap_err("Failed to serialize email change.")
+        .map_err(EmailVerificationTokenStoreError::UnexpectedError)?;
+
+        self.conn
+            .write()
+            .await
+            .set_ex::<_, _, ()>(get_email_change_key(&token), value, ttl)
+            .wrap_err("Failed to set email change token in Redis.")
+            .map_err(EmailVerificationTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "EmailVerificationTokenStore", skip_all)]
+    async fn consume_email_change_token(
+        &mut self,
+        token: &EmailVerificationToken,
+    ) -> Result<(Uuid, Email), EmailVerificationTokenStoreError> {
+        let value = self
+            .conn
+            .write()
+            .await
+            .get_del::<_, Option<String>>(get_email_change_key(token))
+            .wrap_err("Failed to get email change token from Redis.")
+            .map_err(EmailVerificationTokenStoreError::UnexpectedError)?
+            .ok_or(EmailVerificationTokenStoreError::TokenNotFound)?;
+
+        let stored: StoredEmailChange = serde_json::from_str(&value)
+            .wrap_err("Failed to deserialize email change.")
+            .map_err(EmailVerificationTokenStoreError::UnexpectedError)?;
+        let new_email = Email::parse(Secret::new(stored.new_email))
+            .map_err(EmailVerificationTokenStoreError::UnexpectedError)?;
+
+        Ok((stored.user_id, new_email))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredEmailChange {
+    user_id: Uuid,
+    new_email: String,
+}
+
+const EMAIL_VERIFICATION_TOKEN_KEY_PREFIX: &str = "email_verification_token:";
+const EMAIL_CHANGE_TOKEN_KEY_PREFIX: &str = "email_change_token:";
+
+fn get_key(token: &EmailVerificationToken) -> String {
+    format!(
+        "{}{}",
+        EMAIL_VERIFICATION_TOKEN_KEY_PREFIX,
+        token.as_ref().expose_secret()
+    )
+}
+
+fn get_email_change_key(token: &EmailVerificationToken) -> String {
+    format!(
+        "{}{}",
+        EMAIL_CHANGE_TOKEN_KEY_PREFIX,
+        token.as_ref().expose_secret()
+    )
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::Context;
+use redis::{Commands, Connection};
+use tokio::sync::RwLock;
+
+use crate::domain::{LoginAttemptStore, LoginAttemptStoreError, LoginThrottleKey};
+
+pub struct RedisLoginAttemptStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisLoginAttemptStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl LoginAttemptStore for RedisLoginAttemptStore {
+    #[tracing::instrument(name = "LoginAttemptStore", skip_all)]
+    async fn record_failure(
+        &mut self,
+        key: &LoginThrottleKey,
+        window_seconds: u64,
+    ) -> Result<u32, LoginAttemptStoreError> {
+        let failures_key = get_failures_key(key);
+        let mut conn = self.conn.write().await;
+
+        let failures: u32 = conn
+            .incr(&failures_key, 1)
+            .wrap_err("Failed to increment login failures in Redis.")
+            .map_err(LoginAttemptStoreError::UnexpectedError)?;
+
+        // Every failure extends the window, so only a quiet period resets the count
+        conn.expire::<_, ()>(&failures_key, window_seconds as i64)
+            .wrap_err("Failed to set expiry on login failures in Redis.")
+            .map_err(LoginAttemptStoreError::UnexpectedError)?;
+
+        Ok(failures)
+    }
+
+    #[tracing::instrument(name = "LoginAttemptStore", skip_all)]
+    async fn lock_out(
+        &mut self,
+        key: &LoginThrottleKey,
+        seconds: u64,
+    ) -> Result<(), LoginAttemptStoreError> {
+        // Redis rejects a zero expiry
+        if seconds == 0 {
+            return Ok(());
+        }
+
+        self.conn
+            .write()
+            .await
+            .set_ex::<_, _, ()>(get_lockout_key(key), true, seconds)
+            .wrap_err("Failed to set login lockout in Redis.")
+            .map_err(LoginAttemptStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "LoginAttemptStore", skip_all)]
+    async fn get_lockout(
+        &self,
+        key: &LoginThrottleKey,
+    ) -> Result<Option<u64>, LoginAttemptStoreError> {
+        // TTL is negative when the key does not exist
+        let ttl: i64 = self
+            .conn
+            .write()
+            .await
+            .ttl(get_lockout_key(key))
+            .wrap_err("Failed to get login lockout from Redis.")
+            .map_err(LoginAttemptStoreError::UnexpectedError)?;
+
+        Ok((ttl > 0).then_some(ttl as u64))
+    }
+
+    #[tracing::instrument(name = "LoginAttemptStore", skip_all)]
+    async fn clear(&mut self, key: &LoginThrottleKey) -> Result<(), LoginAttemptStoreError> {
+        self.conn
+            .write()
+            .await
+            .del::<Vec<String>, ()>(vec![get_failures_key(key), get_lockout_key(key)])
+            .wrap_err("Failed to clear login failures in Redis.")
+            .map_err(LoginAttemptStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+const LOGIN_FAILURES_PREFIX: &str = "login_failures:";
+const LOGIN_LOCKOUT_PREFIX: &str = "login_lockout:";
+
+fn get_failures_key(key: &LoginThrottleKey) -> String {
+    format!("{}{}", LOGIN_FAILURES_PREFIX, key.as_key())
+}
+
+fn get_lockout_key(key: &LoginThrottleKey) -> String {
+    format!("{}{}", LOGIN_LOCKOUT_PREFIX, key.as_key())
+}
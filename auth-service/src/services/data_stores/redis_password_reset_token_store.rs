@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::Context;
+use redis::{Commands, Connection};
+use secrecy::{ExposeSecret, Secret};
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::{Email, PasswordResetToken, PasswordResetTokenStore, PasswordResetTokenStoreError},
+    utils::constants::PASSWORD_RESET_TOKEN_TTL_SECONDS,
+};
+
+pub struct RedisPasswordResetTokenStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisPasswordResetTokenStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl PasswordResetTokenStore for RedisPasswordResetTokenStore {
+    #[tracing::instrument(name = "PasswordResetTokenStore", skip_all)]
+    async fn add_token(
+        &mut self,
+        token: PasswordResetToken,
+        email: Email,
+    ) -> Result<(), PasswordResetTokenStoreError> {
+        let ttl: u64 = PASSWORD_RESET_TOKEN_TTL_SECONDS
+            .try_into()
+            .wrap_err("Failed to cast PASSWORD_RESET_TOKEN_TTL_SECONDS to u64.")
+            .map_err(PasswordResetTokenStoreError::UnexpectedError)?;
+
+        self.conn
+            .write()
+            .await
+            .set_ex::<_, _, ()>(
+                get_key(&token),
+                email.as_ref().expose_secret().to_owned(),
+                ttl,
+            )
+            .wrap_err("Failed to set password reset token in Redis.")
+            .map_err(PasswordResetTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "PasswordResetTokenStore", skip_all)]
+    async fn consume_token(
+        &mut self,
+        token: &PasswordResetToken,
+    ) -> Result<Email, PasswordResetTokenStoreError> {
+        // GETDEL reads and removes the token atomically, so it cannot be used twice
+        let email = self
+            .conn
+            .write()
+            .await
+            .get_del::<_, Option<String>>(get_key(token))
+            .wrap_err("Failed to get password reset token from Redis.")
+            .map_err(PasswordResetTokenStoreError::UnexpectedError)?
+            .ok_or(PasswordResetTokenStoreError::TokenNotFound)?;
+
+        Email::parse(Secret::new(email)).map_err(PasswordResetTokenStoreError::UnexpectedError)
+    }
+}
+
+const PASSWORD_RESET_TOKEN_KEY_PREFIX: &str = "password_reset_token:";
+
+fn get_key(token: &PasswordResetToken) -> String {
+    format!(
+        "{}{}",
+        PASSWORD_RESET_TOKEN_KEY_PREFIX,
+        token.as_ref().expose_secret()
+    )
+}
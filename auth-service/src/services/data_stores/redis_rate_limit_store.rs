@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use color_eyre::eyre::Context;
+use redis::{Commands, Connection};
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    RateLimit, RateLimitDecision, RateLimitStore, RateLimitStoreError, TokenBucket,
+};
+
+pub struct RedisRateLimitStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisRateLimitStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    #[tracing::instrument(name = "RateLimitStore", skip_all)]
+    async fn take_token(
+        &mut self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<RateLimitDecision, RateLimitStoreError> {
+        let bucket_key = get_key(key);
+        let now_ms = Utc::now().timestamp_millis();
+
+        // The connection lock makes the read and the write below atomic within this instance
+        let mut conn = self.conn.write().await;
+
+        let stored: Option<String> = conn
+            .get(&bucket_key)
+            .wrap_err("Failed to get rate limit bucket from Redis.")
+            .map_err(RateLimitStoreError::UnexpectedError)?;
+        let mut bucket = match stored {
+            Some(stored) => serde_json::from_str(&stored)
+                .wrap_err("Failed to deserialize rate limit bucket.")
+                .map_err(RateLimitStoreError::UnexpectedError)?,
+            None => TokenBucket::full(limit, now_ms),
+        };
+
+        let decision = bucket.take(limit, now_ms);
+
+        let serialized_bucket = serde_json::to_string(&bucket)
+            .wrap_err("Failed to serialize rate limit bucket.")
+            .map_err(RateLimitStoreError::UnexpectedError)?;
+        // A bucket that was left alone for a whole period is full again, so it can expire
+        conn.set_ex::<_, _, ()>(&bucket_key, serialized_bucket, limit.period_seconds)
+            .wrap_err("Failed to set rate limit bucket in Redis.")
+            .map_err(RateLimitStoreError::UnexpectedError)?;
+
+        Ok(decision)
+    }
+}
+
+const RATE_LIMIT_PREFIX: &str = "rate_limit:";
+
+fn get_key(key: &str) -> String {
+    format!("{}{}", RATE_LIMIT_PREFIX, key)
+}
@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::Context;
+use redis::{Commands, Connection, SetExpiry, SetOptions};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    domain::{RefreshToken, RefreshTokenRecord, RefreshTokenStore, RefreshTokenStoreError},
+    utils::auth::REFRESH_TOKEN_TTL_SECONDS,
+};
+
+pub struct RedisRefreshTokenStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisRefreshTokenStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl RefreshTokenStore for RedisRefreshTokenStore {
+    #[tracing::instrument(name = "RefreshTokenStore", skip_all)]
+    async fn add_refresh_token(
+        &mut self,
+        token: RefreshToken,
+        record: RefreshTokenRecord,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let token_key = get_token_key(&token);
+        let user_key = get_user_key(&record.user_id);
+        let serialized_record = serialize_record(&record)?;
+        let ttl = get_ttl()?;
+
+        let mut conn = self.conn.write().await;
+        conn.set_ex::<_, _, ()>(&token_key, serialized_record, ttl)
+            .wrap_err("Failed to set refresh token in Redis.")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        // The user's tokens are indexed, so they can be found when the user is deleted.
+        // The index lives as long as the newest token in it.
+        conn.sadd::<_, _, ()>(&user_key, &token_key)
+            .wrap_err("Failed to index refresh token in Redis.")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        conn.expire::<_, ()>(&user_key, REFRESH_TOKEN_TTL_SECONDS)
+            .wrap_err("Failed to set expiry of refresh token index in Redis.")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "RefreshTokenStore", skip_all)]
+    async fn get_refresh_token(
+        &self,
+        token: &RefreshToken,
+    ) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+        let token_key = get_token_key(token);
+
+        let Ok(result) = self.conn.write().await.get::<_, String>(token_key) else {
+            return Err(RefreshTokenStoreError::TokenNotFound);
+        };
+
+        deserialize_record(&result)
+    }
+
+    #[tracing::instrument(name = "RefreshTokenStore", skip_all)]
+    async fn mark_refresh_token_used(
+        &mut self,
+        token: &RefreshToken,
+    ) -> Result<(), RefreshTokenStoreError> {
+        let mut record = self.get_refresh_token(token).await?;
+        record.used = true;
+        let serialized_record = serialize_record(&record)?;
+
+        // Keep the original expiry so rotation does not extend the token's lifetime
+        let options = SetOptions::default().with_expiration(SetExpiry::KEEPTTL);
+        self.conn
+            .write()
+            .await
+            .set_options::<_, _, ()>(get_token_key(token), serialized_record, options)
+            .wrap_err("Failed to mark refresh token as used in Redis.")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "RefreshTokenStore", skip_all)]
+    async fn revoke_family(&mut self, family_id: &str) -> Result<(), RefreshTokenStoreError> {
+        self.conn
+            .write()
+            .await
+            .set_ex::<_, _, ()>(get_family_key(family_id), true, get_ttl()?)
+            .wrap_err("Failed to revoke refresh token family in Redis.")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "RefreshTokenStore", skip_all)]
+    async fn is_family_revoked(&self, family_id: &str) -> Result<bool, RefreshTokenStoreError> {
+        let is_revoked = self
+            .conn
+            .write()
+            .await
+            .exists::<_, bool>(get_family_key(family_id))
+            .wrap_err("Failed to check if refresh token family is revoked in Redis.")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(is_revoked)
+    }
+
+    #[tracing::instrument(name = "RefreshTokenStore", skip_all)]
+    async fn delete_user_tokens(&mut self, user_id: &Uuid) -> Result<(), RefreshTokenStoreError> {
+        let user_key = get_user_key(user_id);
+
+        let mut conn = self.conn.write().await;
+        let mut keys = conn
+            .smembers::<_, Vec<String>>(&user_key)
+            .wrap_err("Failed to get refresh token index from Redis.")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+        keys.push(user_key);
+        conn.del::<_, ()>(keys)
+            .wrap_err("Failed to delete refresh tokens from Redis.")
+            .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredRefreshToken {
+    // Missing in records stored before users had an id
+    #[serde(default)]
+    user_id: Option<Uuid>,
+    family_id: String,
+    family_issued_at: i64,
+    used: bool,
+    // Missing in records stored before OAuth clients were supported
+    #[serde(default)]
+    client_id: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+fn serialize_record(record: &RefreshTokenRecord) -> Result<String, RefreshTokenStoreError> {
+    let stored = StoredRefreshToken {
+        user_id: Some(record.user_id),
+        family_id: record.family_id.clone(),
+        family_issued_at: record.family_issued_at,
+        used: record.used,
+        client_id: record.client_id.clone(),
+        scope: record.scope.clone(),
+    };
+
+    serde_json::to_string(&stored)
+        .wrap_err("Failed to serialize refresh token record.")
+        .map_err(RefreshTokenStoreError::UnexpectedError)
+}
+
+fn deserialize_record(value: &str) -> Result<RefreshTokenRecord, RefreshTokenStoreError> {
+    let stored: StoredRefreshToken = serde_json::from_str(value)
+        .wrap_err("Failed to deserialize refresh token record.")
+        .map_err(RefreshTokenStoreError::UnexpectedError)?;
+
+    // Such records can't be tied to a user anymore, so their holders have to sign in again
+    let Some(user_id) = stored.user_id else {
+        return Err(RefreshTokenStoreError::TokenNotFound);
+    };
+
+    Ok(RefreshTokenRecord {
+        user_id,
+        family_id: stored.family_id,
+        family_issued_at: stored.family_issued_at,
+        used: stored.used,
+        client_id: stored.client_id,
+        scope: stored.scope,
+    })
+}
+
+fn get_ttl() -> Result<u64, RefreshTokenStoreError> {
+    REFRESH_TOKEN_TTL_SECONDS
+        .try_into()
+        .wrap_err("Failed to cast REFRESH_TOKEN_TTL_SECONDS to u64.")
+        .map_err(RefreshTokenStoreError::UnexpectedError)
+}
+
+const REFRESH_TOKEN_KEY_PREFIX: &str = "refresh_token:";
+const REVOKED_FAMILY_KEY_PREFIX: &str = "refresh_token_family_revoked:";
+const USER_TOKENS_KEY_PREFIX: &str = "refresh_tokens_of_user:";
+
+fn get_token_key(token: &RefreshToken) -> String {
+    format!(
+        "{}{}",
+        REFRESH_TOKEN_KEY_PREFIX,
+        token.as_ref().expose_secret()
+    )
+}
+
+fn get_family_key(family_id: &str) -> String {
+    format!("{}{}", REVOKED_FAMILY_KEY_PREFIX, family_id)
+}
+
+fn get_user_key(user_id: &Uuid) -> String {
+    format!("{}{}", USER_TOKENS_KEY_PREFIX, user_id)
+}
@@ -39,13 +39,16 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
             .wrap_err("Failed to serialize 2FA tuple.")
             .map_err(TwoFACodeStoreError::UnexpectedError)?;
 
-        self.conn
-            .write()
-            .await
-            .set_ex::<String, String, ()>(code_key, serialized_tuple, TEN_MINUTES_IN_SECONDS)
+        let mut conn = self.conn.write().await;
+        conn.set_ex::<String, String, ()>(code_key, serialized_tuple, TEN_MINUTES_IN_SECONDS)
             .wrap_err("Failed to set 2FA code in Redis.")
             .map_err(TwoFACodeStoreError::UnexpectedError)?;
 
+        // A new login attempt starts with a clean slate
+        conn.del::<String, ()>(get_attempts_key(&email))
+            .wrap_err("Failed to reset 2FA attempts in Redis.")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
+
         Ok(())
     }
 
@@ -56,7 +59,7 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
         self.conn
             .write()
             .await
-            .del::<String, ()>(code_key)
+            .del::<Vec<String>, ()>(vec![code_key, get_attempts_key(email)])
             .wrap_err("Failed to delete 2FA code from Redis.")
             .map_err(TwoFACodeStoreError::UnexpectedError)?;
 
@@ -86,6 +89,32 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
 
         Ok((login_attempt_id, two_fa_code))
     }
+
+    #[tracing::instrument(name = "TwoFACodeStore", skip_all)]
+    async fn record_failed_attempt(&mut self, email: &Email) -> Result<u32, TwoFACodeStoreError> {
+        let mut conn = self.conn.write().await;
+
+        let code_exists: bool = conn
+            .exists(get_key(email))
+            .wrap_err("Failed to check 2FA code in Redis.")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
+        if !code_exists {
+            return Err(TwoFACodeStoreError::LoginAttemptIdNotFound);
+        }
+
+        let attempts_key = get_attempts_key(email);
+        let attempts: u32 = conn
+            .incr(&attempts_key, 1)
+            .wrap_err("Failed to increment 2FA attempts in Redis.")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
+
+        // The counter lives no longer than the code it protects
+        conn.expire::<_, ()>(&attempts_key, TEN_MINUTES_IN_SECONDS as i64)
+            .wrap_err("Failed to set expiry on 2FA attempts in Redis.")
+            .map_err(TwoFACodeStoreError::UnexpectedError)?;
+
+        Ok(attempts)
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -93,7 +122,16 @@ struct TwoFATuple(pub String, pub String);
 
 const TEN_MINUTES_IN_SECONDS: u64 = 600;
 const TWO_FA_CODE_PREFIX: &str = "two_fa_code:";
+const TWO_FA_ATTEMPTS_PREFIX: &str = "two_fa_attempts:";
 
 fn get_key(email: &Email) -> String {
     format!("{}{}", TWO_FA_CODE_PREFIX, email.as_ref().expose_secret())
 }
+
+fn get_attempts_key(email: &Email) -> String {
+    format!(
+        "{}{}",
+        TWO_FA_ATTEMPTS_PREFIX,
+        email.as_ref().expose_secret()
+    )
+}
@@ -31,7 +31,7 @@ impl EmailClient for SlackMessageClient {
         let client = reqwest::Client::new();
 
         let response = client
-            .post(&self.webhook_url.expose_secret().to_string())
+            .post(self.webhook_url.expose_secret().to_string())
             .header("Content-Type", "application/x-www-form-urlencoded")
             .json(&payload)
             .send()
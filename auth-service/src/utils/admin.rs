@@ -0,0 +1,14 @@
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+
+use crate::AppState;
+
+// Whether the token is the static admin token, if one is configured
+pub fn is_admin_token(state: &AppState, token: &str) -> bool {
+    let Some(admin_token) = &state.admin_token else {
+        return false;
+    };
+
+    // Comparing digests keeps the time taken independent of where the tokens differ
+    Sha256::digest(token.as_bytes()) == Sha256::digest(admin_token.expose_secret().as_bytes())
+}
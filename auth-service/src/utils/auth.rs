@@ -2,64 +2,417 @@ use axum_extra::extract::cookie::{Cookie, SameSite};
 use chrono::Utc;
 use color_eyre::eyre::{eyre, Context, ContextCompat};
 use color_eyre::Result;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Validation};
+use jsonwebtoken::Validation;
 use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::app_state::BannedTokenStoreType;
-use crate::domain::Email;
+use crate::app_state::{
+    BannedTokenStoreType, JwtKeyRingType, RefreshTokenStoreType, SessionStoreType,
+};
+use crate::domain::{AuthorizationGrant, RefreshToken, RefreshTokenRecord, UserRoles};
 
-use super::constants::JWT_COOKIE_NAME;
+use super::{
+    constants::{
+        JWT_AUDIENCES, JWT_COOKIE_NAME, JWT_ISSUER, JWT_LEEWAY_SECONDS, REFRESH_COOKIE_NAME,
+    },
+    decode_token, JwtKeyRing,
+};
 
-// This is definitely NOT a good secret. We will update it soon!
-const JWT_SECRET: &str = "secret654321";
-
-// Create cookie with a new JWT auth token
-pub fn generate_auth_cookie(email: &Email) -> Result<Cookie<'static>> {
-    let token = generate_auth_token(email)?;
+// Create cookie with a new JWT auth token for the default audience, signed with the active key
+pub async fn generate_auth_cookie(
+    user_id: &Uuid,
+    session_id: &str,
+    roles: &UserRoles,
+    jwt_key_ring: JwtKeyRingType,
+) -> Result<Cookie<'static>> {
+    let token = generate_session_auth_token(user_id, session_id, roles, jwt_key_ring).await?;
     Ok(create_auth_cookie(token))
 }
 
+// Create a JWT auth token for the default audience, the one the auth cookie carries
+pub async fn generate_session_auth_token(
+    user_id: &Uuid,
+    session_id: &str,
+    roles: &UserRoles,
+    jwt_key_ring: JwtKeyRingType,
+) -> Result<String> {
+    generate_auth_token(
+        user_id,
+        session_id,
+        roles,
+        default_audience(),
+        &*jwt_key_ring.read().await,
+    )
+}
+
+// Create a JWT auth token for one of the configured relying apps
+pub async fn generate_audience_token(
+    user_id: &Uuid,
+    session_id: &str,
+    roles: &UserRoles,
+    audience: &str,
+    jwt_key_ring: JwtKeyRingType,
+) -> Result<String> {
+    if !JWT_AUDIENCES.iter().any(|known| known == audience) {
+        return Err(eyre!("Unknown audience: {}", audience));
+    }
+    generate_auth_token(
+        user_id,
+        session_id,
+        roles,
+        audience,
+        &*jwt_key_ring.read().await,
+    )
+}
+
+// The audience of the auth cookie
+pub fn default_audience() -> &'static str {
+    &JWT_AUDIENCES[0]
+}
+
+// This value determines how long a refresh token can be used to obtain new auth tokens
+pub const REFRESH_TOKEN_TTL_SECONDS: i64 = 60 * 60 * 24 * 30; // 30 days
+
+// Create cookie with a new refresh token and persist it in the refresh token store.
+// Use `RefreshTokenRecord::new` to start a new session and `RefreshTokenRecord::rotate` to continue one.
+pub async fn generate_refresh_cookie(
+    record: RefreshTokenRecord,
+    refresh_token_store: RefreshTokenStoreType,
+) -> Result<Cookie<'static>> {
+    let token = generate_refresh_token(record, refresh_token_store).await?;
+
+    Ok(create_refresh_cookie(
+        token.as_ref().expose_secret().to_owned(),
+    ))
+}
+
+// Create a new refresh token and persist it in the refresh token store
+pub async fn generate_refresh_token(
+    record: RefreshTokenRecord,
+    refresh_token_store: RefreshTokenStoreType,
+) -> Result<RefreshToken> {
+    let token = RefreshToken::default();
+
+    refresh_token_store
+        .write()
+        .await
+        .add_refresh_token(token.clone(), record)
+        .await
+        .wrap_err("Failed to store refresh token.")?;
+
+    Ok(token)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
+    pub iss: String,
     pub sub: String,
+    pub aud: String,
     pub exp: usize,
+    pub nbf: usize,
+    pub iat: usize,
+    pub jti: String,
+    // The login session the token belongs to
+    pub sid: String,
+    // Granted to the user, so relying apps can authorize requests without asking us
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
+    // Only set in access tokens issued to OAuth clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
 }
 
-// Check if JWT auth token is valid by decoding it using the JWT secret
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    #[serde(flatten)]
+    pub claims: Claims,
+    // When the user logged in, and with which authentication methods (RFC 8176)
+    pub auth_time: i64,
+    pub amr: Vec<String>,
+    // Echoed from the authorization request, so the client can detect replayed id tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+// Check if JWT auth token is valid for the default audience
 pub async fn validate_token(
     token: &str,
+    jwt_key_ring: JwtKeyRingType,
+    banned_token_store: BannedTokenStoreType,
+    session_store: SessionStoreType,
+) -> Result<Claims> {
+    validate_audience_token(
+        token,
+        default_audience(),
+        jwt_key_ring,
+        banned_token_store,
+        session_store,
+    )
+    .await
+}
+
+// Check if JWT auth token is valid by decoding it using the key it was signed with,
+// and that it was issued by us for `audience` within a session that is still active.
+// Only relying apps are accepted as `audience`, as id tokens carry the client id there.
+pub async fn validate_audience_token(
+    token: &str,
+    audience: &str,
+    jwt_key_ring: JwtKeyRingType,
     banned_token_store: BannedTokenStoreType,
+    session_store: SessionStoreType,
 ) -> Result<Claims> {
+    if !JWT_AUDIENCES.iter().any(|known| known == audience) {
+        return Err(eyre!("Token audience is not a relying app."));
+    }
+
+    let claims = decode_token::<Claims>(&jwt_key_ring, token, &token_validation(audience)).await?;
+
+    ensure_token_active(&claims, banned_token_store, session_store).await?;
+
+    Ok(claims)
+}
+
+// Check if a JWT access token was issued to an OAuth client, whichever client that was,
+// within a session that is still active
+pub async fn validate_client_access_token(
+    token: &str,
+    jwt_key_ring: JwtKeyRingType,
+    banned_token_store: BannedTokenStoreType,
+    session_store: SessionStoreType,
+) -> Result<Claims> {
+    let claims = decode_any_audience(token, &jwt_key_ring).await?;
+
+    if claims.client_id.as_deref() != Some(claims.aud.as_str()) {
+        return Err(eyre!("Token was not issued to an OAuth client."));
+    }
+
+    ensure_token_active(&claims, banned_token_store, session_store).await?;
+
+    Ok(claims)
+}
+
+// Check if a JWT access token is valid for whoever it was issued to: one of the relying apps
+// or an OAuth client. Id tokens are not access tokens and are rejected.
+pub async fn validate_access_token(
+    token: &str,
+    jwt_key_ring: JwtKeyRingType,
+    banned_token_store: BannedTokenStoreType,
+    session_store: SessionStoreType,
+) -> Result<Claims> {
+    let claims = decode_any_audience(token, &jwt_key_ring).await?;
+
+    let is_client_token = claims.client_id.as_deref() == Some(claims.aud.as_str());
+    if !is_client_token && !JWT_AUDIENCES.contains(&claims.aud) {
+        return Err(eyre!("Token was not issued as an access token."));
+    }
+
+    ensure_token_active(&claims, banned_token_store, session_store).await?;
+
+    Ok(claims)
+}
+
+async fn decode_any_audience(token: &str, jwt_key_ring: &JwtKeyRingType) -> Result<Claims> {
+    let mut validation = token_validation("");
+    validation.validate_aud = false;
+    decode_token::<Claims>(jwt_key_ring, token, &validation).await
+}
+
+// Reads the client an id token we issued was for. Expired id tokens are accepted,
+// as relying parties send them as a hint long after they were issued.
+pub async fn id_token_hint_client_id(token: &str, jwt_key_ring: JwtKeyRingType) -> Result<String> {
+    let mut validation = token_validation("");
+    validation.validate_aud = false;
+    validation.validate_exp = false;
+    validation.validate_nbf = false;
+    validation.set_required_spec_claims(&["iss", "aud"]);
+    let claims = decode_token::<IdTokenClaims>(&jwt_key_ring, token, &validation).await?;
+
+    Ok(claims.claims.aud)
+}
+
+// Checks of a decoded token that the signature and the standard claims cannot tell
+async fn ensure_token_active(
+    claims: &Claims,
+    banned_token_store: BannedTokenStoreType,
+    session_store: SessionStoreType,
+) -> Result<()> {
     let banned_token_store = banned_token_store.read().await;
-    match banned_token_store.check_banned_token(token).await {
-        Ok(is_banned) => {
-            if is_banned {
-                return Err(eyre!("Token is banned."));
+    if banned_token_store.check_banned_token(&claims.jti).await? {
+        return Err(eyre!("Token is banned."));
+    }
+
+    // The expiry and not-before checks are done while decoding, the issue time is not
+    let now = Utc::now().timestamp() as u64;
+    if claims.iat as u64 > now + *JWT_LEEWAY_SECONDS {
+        return Err(eyre!("Token was issued in the future."));
+    }
+
+    // Revoking a session logs its device out right away, not only once the token expires
+    let session = session_store.read().await.get_session(&claims.sid).await?;
+    if session.revoked {
+        return Err(eyre!("Session was revoked."));
+    }
+
+    // Reject tokens issued before the user's tokens were revoked, e.g. by a password reset
+    let issued_at: i64 = claims
+        .iat
+        .try_into()
+        .wrap_err("Failed to cast iat to i64.")?;
+    if banned_token_store
+        .check_user_tokens_banned(&session.user_id, issued_at)
+        .await?
+    {
+        return Err(eyre!("Token was revoked."));
+    }
+
+    Ok(())
+}
+
+// Invalidates every auth token and refresh token session of the user issued so far
+pub async fn revoke_user_sessions(
+    user_id: &Uuid,
+    banned_token_store: BannedTokenStoreType,
+    session_store: SessionStoreType,
+) -> Result<()> {
+    banned_token_store
+        .write()
+        .await
+        .ban_user_tokens(user_id, Utc::now().timestamp())
+        .await
+        .wrap_err("Failed to revoke user tokens.")?;
+
+    // The sessions are revoked as well, which also covers tokens of the current second
+    session_store
+        .write()
+        .await
+        .revoke_user_sessions(user_id)
+        .await
+        .wrap_err("Failed to revoke user sessions.")
+}
+
+// Logs the user out of every device except the one the current session belongs to
+pub async fn revoke_other_sessions(
+    user_id: &Uuid,
+    current_session_id: &str,
+    session_store: SessionStoreType,
+    refresh_token_store: RefreshTokenStoreType,
+) -> Result<()> {
+    // Revoking the session rejects its auth tokens, revoking the family its refresh token
+    let mut revoked_session_ids = Vec::new();
+    {
+        let mut session_store = session_store.write().await;
+        let sessions = session_store
+            .list_sessions(user_id)
+            .await
+            .wrap_err("Failed to list user sessions.")?;
+
+        for session in sessions {
+            if session.id == current_session_id {
+                continue;
             }
+            session_store
+                .revoke_session(&session.id)
+                .await
+                .wrap_err("Failed to revoke session.")?;
+            revoked_session_ids.push(session.id);
         }
-        Err(err) => return Err(err.into()),
     }
 
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(JWT_SECRET.as_bytes()),
-        &Validation::default(),
-    )
-    .map(|data| data.claims)
-    .wrap_err("Failed to decode token.")
+    // The session store is released first, refreshing takes the two stores the other way round
+    let mut refresh_token_store = refresh_token_store.write().await;
+    for session_id in revoked_session_ids {
+        refresh_token_store
+            .revoke_family(&session_id)
+            .await
+            .wrap_err("Failed to revoke refresh token family.")?;
+    }
+
+    Ok(())
 }
 
 // This value determines how long the JWT auth token is valid for
 pub const TOKEN_TTL_SECONDS: i64 = 600; // 10 minutes
 
-// Create JWT auth token
-fn generate_auth_token(email: &Email) -> Result<String> {
+fn token_validation(audience: &str) -> Validation {
+    let mut validation = Validation::default();
+    validation.set_issuer(&[JWT_ISSUER.as_str()]);
+    validation.set_audience(&[audience]);
+    validation.set_required_spec_claims(&["iss", "sub", "aud", "exp", "nbf"]);
+    validation.validate_nbf = true;
+    validation.leeway = *JWT_LEEWAY_SECONDS;
+    validation
+}
+
+// Create a JWT access token for an OAuth client. The client is the audience,
+// so the token cannot be used as the auth cookie or by another client.
+pub async fn generate_client_access_token(
+    user_id: &Uuid,
+    session_id: &str,
+    client_id: &str,
+    scope: &str,
+    jwt_key_ring: JwtKeyRingType,
+) -> Result<String> {
+    let claims = Claims {
+        scope: Some(scope.to_owned()),
+        client_id: Some(client_id.to_owned()),
+        ..new_claims(user_id, session_id, client_id)?
+    };
+    create_token(&claims, &*jwt_key_ring.read().await)
+}
+
+// Create an OpenID Connect id token telling the client who authorized it and how they logged in.
+// `session_id` is the client's own session rather than the one the user authorized it from.
+pub async fn generate_id_token(
+    grant: &AuthorizationGrant,
+    session_id: &str,
+    jwt_key_ring: JwtKeyRingType,
+) -> Result<String> {
+    let claims = IdTokenClaims {
+        claims: new_claims(&grant.user_id, session_id, &grant.client_id)?,
+        auth_time: grant.auth_time,
+        nonce: grant.nonce.clone(),
+        amr: grant.amr.clone(),
+    };
+    create_token(&claims, &*jwt_key_ring.read().await)
+}
+
+// Create JWT auth token carrying the user's roles and permissions
+fn generate_auth_token(
+    user_id: &Uuid,
+    session_id: &str,
+    roles: &UserRoles,
+    audience: &str,
+    jwt_key_ring: &JwtKeyRing,
+) -> Result<String> {
+    let claims = Claims {
+        roles: roles
+            .roles
+            .iter()
+            .map(|role| role.as_ref().to_owned())
+            .collect(),
+        permissions: roles
+            .permissions
+            .iter()
+            .map(|permission| permission.as_ref().to_owned())
+            .collect(),
+        ..new_claims(user_id, session_id, audience)?
+    };
+    create_token(&claims, jwt_key_ring)
+}
+
+fn new_claims(user_id: &Uuid, session_id: &str, audience: &str) -> Result<Claims> {
     let delta = chrono::Duration::try_seconds(TOKEN_TTL_SECONDS)
         .wrap_err("Failed to create 10 minute time delta.")?;
 
+    let now = Utc::now();
+
     // Create JWT expiration time
-    let exp = Utc::now()
+    let exp = now
         .checked_add_signed(delta)
         .wrap_err("Failed to create 10 minute time delta.")?
         .timestamp();
@@ -70,21 +423,31 @@ fn generate_auth_token(email: &Email) -> Result<String> {
         exp
     ))?;
 
-    let sub = email.as_ref().expose_secret().to_owned();
-
-    let claims = Claims { sub, exp };
+    let iat: usize = now.timestamp().try_into().wrap_err(format!(
+        "failed to cast iat time to usize. iat time: {}",
+        now.timestamp()
+    ))?;
 
-    create_token(&claims)
+    Ok(Claims {
+        iss: JWT_ISSUER.to_owned(),
+        // The user's id rather than their email, which can change and should not leak
+        sub: user_id.to_string(),
+        aud: audience.to_owned(),
+        exp,
+        nbf: iat,
+        iat,
+        jti: Uuid::new_v4().to_string(),
+        sid: session_id.to_owned(),
+        roles: Vec::new(),
+        permissions: Vec::new(),
+        scope: None,
+        client_id: None,
+    })
 }
 
-// Create JWT auth token by encoding claims using the JWT secret
-fn create_token(claims: &Claims) -> Result<String> {
-    encode(
-        &jsonwebtoken::Header::default(),
-        &claims,
-        &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
-    )
-    .wrap_err("Failed to create token")
+// Create JWT auth token by encoding claims using the active signing key
+fn create_token<T: Serialize>(claims: &T, jwt_key_ring: &JwtKeyRing) -> Result<String> {
+    jwt_key_ring.encode(claims)
 }
 
 // Create cookie and set the value to the passed-in token string
@@ -98,6 +461,16 @@ fn create_auth_cookie(token: String) -> Cookie<'static> {
     cookie
 }
 
+// Create cookie and set the value to the passed-in refresh token string
+fn create_refresh_cookie(token: String) -> Cookie<'static> {
+    Cookie::build((REFRESH_COOKIE_NAME, token))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::seconds(REFRESH_TOKEN_TTL_SECONDS))
+        .build()
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -105,17 +478,49 @@ mod tests {
     use secrecy::Secret;
     use tokio::sync::RwLock;
 
-    use crate::HashSetBannedTokenStore;
+    use crate::domain::{
+        BannedTokenStore, Permission, RefreshTokenStore, Role, Session, SessionStore,
+    };
+    use crate::utils::{JwtSigningKey, JWT_SECRET};
+    use crate::{HashMapRefreshTokenStore, HashMapSessionStore, HashSetBannedTokenStore};
 
     use super::*;
 
+    const SESSION_ID: &str = "test-session";
+    const SESSION_USER_ID: Uuid = Uuid::from_u128(1);
+
+    // A session store holding the active `SESSION_ID` session of `SESSION_USER_ID`
+    async fn session_store() -> SessionStoreType {
+        let mut session_store = HashMapSessionStore::default();
+        session_store
+            .add_session(Session::new(
+                SESSION_ID.to_owned(),
+                SESSION_USER_ID,
+                vec![],
+                None,
+                None,
+            ))
+            .await
+            .unwrap();
+        Arc::new(RwLock::new(session_store))
+    }
+
+    fn jwt_key_ring() -> JwtKeyRingType {
+        Arc::new(RwLock::new(JwtKeyRing::new(JwtSigningKey::from_secret(
+            JWT_SECRET.expose_secret().as_bytes(),
+        ))))
+    }
+
     #[tokio::test]
     async fn test_generate_auth_cookie() {
         // Arrange
-        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let user_id = Uuid::new_v4();
 
         // Act
-        let cookie = generate_auth_cookie(&email).unwrap();
+        let cookie =
+            generate_auth_cookie(&user_id, SESSION_ID, &UserRoles::default(), jwt_key_ring())
+                .await
+                .unwrap();
 
         // Assert
         assert_eq!(cookie.name(), JWT_COOKIE_NAME);
@@ -141,27 +546,112 @@ mod tests {
         assert_eq!(cookie.same_site(), Some(SameSite::Lax));
     }
 
+    #[tokio::test]
+    async fn test_generate_refresh_cookie() {
+        // Arrange
+        let record = RefreshTokenRecord::new(Uuid::new_v4());
+        let refresh_token_store = Arc::new(RwLock::new(HashMapRefreshTokenStore::default()));
+
+        // Act
+        let cookie = generate_refresh_cookie(record.clone(), refresh_token_store.clone())
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(cookie.name(), REFRESH_COOKIE_NAME);
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(
+            cookie.max_age(),
+            Some(time::Duration::seconds(REFRESH_TOKEN_TTL_SECONDS))
+        );
+
+        let token = RefreshToken::parse(Secret::new(cookie.value().to_owned())).unwrap();
+        let stored_record = refresh_token_store
+            .read()
+            .await
+            .get_refresh_token(&token)
+            .await
+            .unwrap();
+        assert_eq!(stored_record, record);
+    }
+
     #[tokio::test]
     async fn test_generate_auth_token() {
         // Arrange
-        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let user_id = Uuid::new_v4();
 
         // Act
-        let result = generate_auth_token(&email).unwrap();
+        let result = generate_auth_token(
+            &user_id,
+            SESSION_ID,
+            &UserRoles::default(),
+            default_audience(),
+            &*jwt_key_ring().read().await,
+        )
+        .unwrap();
 
         // Assert
         assert_eq!(result.split('.').count(), 3);
     }
 
+    #[tokio::test]
+    async fn test_generate_auth_token_carries_roles_and_permissions() {
+        // Arrange
+        let user_id = Uuid::new_v4();
+        let jwt_key_ring = jwt_key_ring();
+        let roles = UserRoles::new(
+            vec![Role::admin()],
+            vec![Permission::parse("users:read".to_owned()).unwrap()],
+        );
+        let token = generate_auth_token(
+            &user_id,
+            SESSION_ID,
+            &roles,
+            default_audience(),
+            &*jwt_key_ring.read().await,
+        )
+        .unwrap();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+
+        // Act
+        let claims = validate_token(
+            &token,
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await
+        .unwrap();
+
+        // Assert
+        assert_eq!(claims.roles, vec!["admin"]);
+        assert_eq!(claims.permissions, vec!["users:read"]);
+    }
+
     #[tokio::test]
     async fn test_validate_token_with_valid_token() {
         // Arrange
-        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
-        let token = generate_auth_token(&email).unwrap();
+        let user_id = Uuid::new_v4();
+        let jwt_key_ring = jwt_key_ring();
+        let token = generate_auth_token(
+            &user_id,
+            SESSION_ID,
+            &UserRoles::default(),
+            default_audience(),
+            &*jwt_key_ring.read().await,
+        )
+        .unwrap();
         let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
 
         // Act
-        let result = validate_token(&token, banned_token_store).await.unwrap();
+        let result = validate_token(
+            &token,
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await
+        .unwrap();
 
         let exp = Utc::now()
             .checked_add_signed(chrono::Duration::try_minutes(9).expect("valid duration"))
@@ -169,19 +659,429 @@ mod tests {
             .timestamp();
 
         // Assert
-        assert_eq!(result.sub, "test@example.com");
+        assert_eq!(result.sub, user_id.to_string());
         assert!(result.exp > exp as usize);
     }
 
+    #[tokio::test]
+    async fn test_validate_token_with_revoked_user_tokens() {
+        // Arrange
+        let user_id = SESSION_USER_ID;
+        let jwt_key_ring = jwt_key_ring();
+        let token = generate_auth_token(
+            &user_id,
+            SESSION_ID,
+            &UserRoles::default(),
+            default_audience(),
+            &*jwt_key_ring.read().await,
+        )
+        .unwrap();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+        banned_token_store
+            .write()
+            .await
+            .ban_user_tokens(&user_id, Utc::now().timestamp() + 1)
+            .await
+            .unwrap();
+
+        // Act
+        let result = validate_token(
+            &token,
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_with_banned_jti() {
+        // Arrange
+        let claims = valid_claims();
+        let jwt_key_ring = jwt_key_ring();
+        let token = create_token(&claims, &*jwt_key_ring.read().await).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+        banned_token_store
+            .write()
+            .await
+            .add_banned_token(&claims.jti, claims.exp as i64)
+            .await
+            .unwrap();
+
+        // Act
+        let result = validate_token(
+            &token,
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_with_revoked_session() {
+        // Arrange
+        let claims = valid_claims();
+        let jwt_key_ring = jwt_key_ring();
+        let token = create_token(&claims, &*jwt_key_ring.read().await).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+        let session_store = session_store().await;
+        session_store
+            .write()
+            .await
+            .revoke_session(SESSION_ID)
+            .await
+            .unwrap();
+
+        // Act
+        let result = validate_token(&token, jwt_key_ring, banned_token_store, session_store).await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_with_unknown_session() {
+        // Arrange
+        let claims = Claims {
+            sid: "unknown-session".to_owned(),
+            ..valid_claims()
+        };
+
+        // Act
+        let result = validate_claims(&claims).await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn test_validate_token_with_invalid_token() {
         // Arrange
         let token = "invalid_token".to_owned();
+        let jwt_key_ring = jwt_key_ring();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+        // Act
+        let result = validate_token(
+            &token,
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    fn valid_claims() -> Claims {
+        let now = Utc::now().timestamp() as usize;
+        Claims {
+            iss: JWT_ISSUER.to_owned(),
+            sub: Uuid::new_v4().to_string(),
+            aud: default_audience().to_owned(),
+            exp: now + 600,
+            nbf: now,
+            iat: now,
+            jti: Uuid::new_v4().to_string(),
+            sid: SESSION_ID.to_owned(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+            scope: None,
+            client_id: None,
+        }
+    }
+
+    async fn validate_claims(claims: &Claims) -> Result<Claims> {
+        let jwt_key_ring = jwt_key_ring();
+        let token = create_token(claims, &*jwt_key_ring.read().await).unwrap();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+        validate_token(
+            &token,
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_generate_auth_token_sets_registered_claims() {
+        // Arrange
+        let user_id = Uuid::new_v4();
+        let jwt_key_ring = jwt_key_ring();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+
+        // Act
+        let first = generate_auth_token(
+            &user_id,
+            SESSION_ID,
+            &UserRoles::default(),
+            default_audience(),
+            &*jwt_key_ring.read().await,
+        )
+        .unwrap();
+        let second = generate_auth_token(
+            &user_id,
+            SESSION_ID,
+            &UserRoles::default(),
+            default_audience(),
+            &*jwt_key_ring.read().await,
+        )
+        .unwrap();
+
+        // Assert
+        let first = validate_token(
+            &first,
+            jwt_key_ring.clone(),
+            banned_token_store.clone(),
+            session_store().await,
+        )
+        .await
+        .unwrap();
+        let second = validate_token(
+            &second,
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await
+        .unwrap();
+        assert_eq!(first.iss, *JWT_ISSUER);
+        assert_eq!(first.aud, default_audience());
+        assert_eq!(first.nbf, first.iat);
+        assert_ne!(first.jti, second.jti);
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_rejects_other_audience() {
+        // Arrange
+        let user_id = Uuid::new_v4();
+        let jwt_key_ring = jwt_key_ring();
+        let token = generate_auth_token(
+            &user_id,
+            SESSION_ID,
+            &UserRoles::default(),
+            "other-app",
+            &*jwt_key_ring.read().await,
+        )
+        .unwrap();
         let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+
         // Act
-        let result = validate_token(&token, banned_token_store).await;
+        let default_result = validate_token(
+            &token,
+            jwt_key_ring.clone(),
+            banned_token_store.clone(),
+            session_store().await,
+        )
+        .await;
+        let audience_result = validate_audience_token(
+            &token,
+            "other-app",
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await;
+
+        // Assert
+        assert!(default_result.is_err());
+        // "other-app" is not in JWT_AUDIENCES, so naming it does not help either
+        assert!(audience_result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generate_audience_token_rejects_unknown_audience() {
+        // Arrange
+        let user_id = Uuid::new_v4();
+
+        // Act
+        let result = generate_audience_token(
+            &user_id,
+            SESSION_ID,
+            &UserRoles::default(),
+            "other-app",
+            jwt_key_ring(),
+        )
+        .await;
 
         // Assert
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_validate_token_rejects_invalid_registered_claims() {
+        let now = Utc::now().timestamp() as usize;
+        let leeway = *JWT_LEEWAY_SECONDS as usize;
+        let cases = [
+            (
+                "other issuer",
+                Claims {
+                    iss: "https://elsewhere.example.com".to_owned(),
+                    ..valid_claims()
+                },
+            ),
+            (
+                "expired",
+                Claims {
+                    exp: now - leeway - 10,
+                    ..valid_claims()
+                },
+            ),
+            (
+                "not yet valid",
+                Claims {
+                    nbf: now + leeway + 10,
+                    ..valid_claims()
+                },
+            ),
+            (
+                "issued in the future",
+                Claims {
+                    iat: now + leeway + 10,
+                    ..valid_claims()
+                },
+            ),
+        ];
+
+        for (name, claims) in cases {
+            // Act
+            let result = validate_claims(&claims).await;
+
+            // Assert
+            assert!(result.is_err(), "Failed for case: {}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_allows_clock_leeway() {
+        // Arrange
+        let now = Utc::now().timestamp() as usize;
+        let claims = Claims {
+            exp: now - 10,
+            nbf: now + 10,
+            iat: now + 10,
+            ..valid_claims()
+        };
+
+        // Act
+        let result = validate_claims(&claims).await;
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_validate_token_requires_jti() {
+        // Arrange
+        let jwt_key_ring = jwt_key_ring();
+        let claims = valid_claims();
+        let token = jwt_key_ring
+            .read()
+            .await
+            .encode(&serde_json::json!({
+                "iss": claims.iss,
+                "sub": claims.sub,
+                "aud": claims.aud,
+                "exp": claims.exp,
+                "nbf": claims.nbf,
+                "iat": claims.iat,
+                "sid": claims.sid,
+            }))
+            .unwrap();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+
+        // Act
+        let result = validate_token(
+            &token,
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_id_token_names_client_but_is_no_access_token() {
+        // Arrange
+        let jwt_key_ring = jwt_key_ring();
+        let grant = AuthorizationGrant {
+            client_id: "reports".to_owned(),
+            user_id: Uuid::new_v4(),
+            redirect_uri: "https://reports.example.com/callback".to_owned(),
+            scope: "openid".to_owned(),
+            code_challenge: "challenge".to_owned(),
+            session_id: SESSION_ID.to_owned(),
+            nonce: Some("nonce".to_owned()),
+            auth_time: Utc::now().timestamp(),
+            amr: vec!["pwd".to_owned()],
+        };
+        let id_token = generate_id_token(&grant, SESSION_ID, jwt_key_ring.clone())
+            .await
+            .unwrap();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+
+        // Act
+        let client_id = id_token_hint_client_id(&id_token, jwt_key_ring.clone()).await;
+        let access_result = validate_client_access_token(
+            &id_token,
+            jwt_key_ring,
+            banned_token_store,
+            session_store().await,
+        )
+        .await;
+
+        // Assert
+        assert_eq!(client_id.unwrap(), "reports");
+        assert!(access_result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validate_access_token_accepts_any_access_token_audience() {
+        // Arrange
+        let user_id = Uuid::new_v4();
+        let jwt_key_ring = jwt_key_ring();
+        let auth_token = generate_auth_token(
+            &user_id,
+            SESSION_ID,
+            &UserRoles::default(),
+            default_audience(),
+            &*jwt_key_ring.read().await,
+        )
+        .unwrap();
+        let client_token = generate_client_access_token(
+            &user_id,
+            SESSION_ID,
+            "reports",
+            "profile",
+            jwt_key_ring.clone(),
+        )
+        .await
+        .unwrap();
+        let banned_token_store = Arc::new(RwLock::new(HashSetBannedTokenStore::default()));
+
+        for (token, client_id) in [(auth_token, None), (client_token, Some("reports"))] {
+            // Act
+            let result = validate_access_token(
+                &token,
+                jwt_key_ring.clone(),
+                banned_token_store.clone(),
+                session_store().await,
+            )
+            .await;
+
+            // Assert
+            assert_eq!(result.unwrap().client_id.as_deref(), client_id);
+        }
+    }
 }
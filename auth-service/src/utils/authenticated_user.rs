@@ -0,0 +1,85 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap},
+};
+use axum_extra::extract::CookieJar;
+use uuid::Uuid;
+
+use crate::{
+    domain::{AuthAPIError, Email, User, UserStoreError},
+    AppState,
+};
+
+use super::{
+    auth::{validate_token, Claims},
+    constants::JWT_COOKIE_NAME,
+};
+
+// The user a valid auth token of the request was issued to. Browsers send the `jwt` cookie,
+// clients that cannot keep cookies send the same token as an `Authorization: Bearer` header.
+#[derive(Debug)]
+pub struct AuthenticatedUser {
+    pub user_id: Uuid,
+    pub email: Email,
+    pub claims: Claims,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthenticatedUser {
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = request_auth_token(&parts.headers).ok_or(AuthAPIError::MissingToken)?;
+
+        let claims = validate_token(
+            &token,
+            state.jwt_key_ring.clone(),
+            state.banned_token_store.clone(),
+            state.session_store.clone(),
+        )
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+        let user = token_user(state, &claims).await?;
+
+        Ok(Self {
+            user_id: user.id,
+            email: user.email,
+            claims,
+        })
+    }
+}
+
+// The user a validated token was issued to. Tokens name the user by id,
+// so they keep working when the email changes and stop once the user is deleted.
+pub async fn token_user(state: &AppState, claims: &Claims) -> Result<User, AuthAPIError> {
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AuthAPIError::InvalidToken)?;
+
+    match state.user_store.read().await.get_user_by_id(&user_id).await {
+        Ok(user) => Ok(user),
+        Err(UserStoreError::UserNotFound) => Err(AuthAPIError::InvalidToken),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+// The auth token of the request. An explicit bearer token wins over the cookie
+// the browser happens to send along.
+pub fn request_auth_token(headers: &HeaderMap) -> Option<String> {
+    if let Some(token) = bearer_token(headers) {
+        return Some(token.to_owned());
+    }
+
+    CookieJar::from_headers(headers)
+        .get(JWT_COOKIE_NAME)
+        .map(|cookie| cookie.value().to_owned())
+}
+
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
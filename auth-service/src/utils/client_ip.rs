@@ -0,0 +1,83 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{request::Parts, HeaderMap},
+};
+
+const X_FORWARDED_FOR: &str = "X-Forwarded-For";
+
+// Address of the client that sent the request, if it can be determined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer_ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+
+        Ok(ClientIp(forwarded_ip(&parts.headers).or(peer_ip)))
+    }
+}
+
+// nginx appends the address it saw to X-Forwarded-For, so only the last entry
+// can be trusted, anything before it is whatever the client sent
+fn forwarded_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .next_back()?
+        .to_str()
+        .ok()?
+        .rsplit(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_forwarded_ip_uses_last_entry() {
+        // Arrange
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            X_FORWARDED_FOR,
+            HeaderValue::from_static("1.1.1.1, 203.0.113.7"),
+        );
+
+        // Act
+        let result = forwarded_ip(&headers);
+
+        // Assert
+        assert_eq!(result, Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_forwarded_ip_missing_or_invalid() {
+        // Arrange
+        let mut invalid = HeaderMap::new();
+        invalid.insert(X_FORWARDED_FOR, HeaderValue::from_static("not-an-ip"));
+
+        // Act & Assert
+        assert_eq!(forwarded_ip(&HeaderMap::new()), None);
+        assert_eq!(forwarded_ip(&invalid), None);
+    }
+}
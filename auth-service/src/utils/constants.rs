@@ -1,23 +1,120 @@
 use dotenvy::dotenv;
+use jsonwebtoken::Algorithm;
 use lazy_static::lazy_static;
-use secrecy::Secret;
-use std::env as std_env;
+use secrecy::{ExposeSecret, Secret};
+use std::{env as std_env, path::Path, str::FromStr, time::Duration};
+
+use crate::domain::{
+    LoginThrottleConfig, LoginThrottlePolicy, RateLimitConfig, UnverifiedLoginPolicy,
+};
+
+use super::{JwtKeyRing, JwtSigningKey};
 
 lazy_static! {
-    pub static ref JWT_SECRET: String = set_token();
+    pub static ref JWT_SECRET: Secret<String> = set_token();
+    pub static ref JWT_KEY_ROTATION_INTERVAL: Option<Duration> = set_jwt_key_rotation_interval();
+    pub static ref ADMIN_TOKEN: Option<Secret<String>> = set_admin_token();
+    pub static ref JWT_ISSUER: String = set_jwt_issuer();
+    pub static ref JWT_AUDIENCES: Vec<String> = set_jwt_audiences();
+    pub static ref JWT_LEEWAY_SECONDS: u64 =
+        env_or(env::JWT_LEEWAY_SECONDS_ENV_VAR, DEFAULT_JWT_LEEWAY_SECONDS);
     pub static ref DATABASE_URL: Secret<String> = set_database_url();
     pub static ref REDIS_HOST_NAME: String = set_redis_host();
     pub static ref SLACK_WEBHOOK: Secret<String> = set_slack_webhook();
+    pub static ref AUTH_BASE_URL: String = set_auth_base_url();
+    pub static ref TOTP_ENCRYPTION_KEY: Secret<String> = set_totp_encryption_key();
+    pub static ref UNVERIFIED_LOGIN_POLICY: UnverifiedLoginPolicy = set_unverified_login_policy();
+    pub static ref LOGIN_THROTTLE: LoginThrottleConfig = set_login_throttle();
+    pub static ref RATE_LIMITS: RateLimitConfig = set_rate_limits();
 }
 
-fn set_token() -> String {
+fn set_token() -> Secret<String> {
     dotenv().ok();
     let secret =
         std_env::var(env::JWT_SECRET_ENV_VAR).expect("JWT_SECRET environment variable not set");
     if secret.is_empty() {
         panic!("JWT_SECRET environment variable is empty");
     }
-    secret
+    Secret::new(secret)
+}
+
+// HS256 with `JWT_SECRET` unless an asymmetric algorithm and its private key are configured.
+// With a key directory, the keys are loaded from there and can be rotated.
+pub fn configure_jwt_key_ring() -> JwtKeyRing {
+    dotenv().ok();
+    let algorithm = match std_env::var(env::JWT_SIGNING_ALGORITHM_ENV_VAR) {
+        Ok(algorithm) => Algorithm::from_str(&algorithm)
+            .expect("JWT_SIGNING_ALGORITHM must be one of: HS256, RS256, EdDSA"),
+        Err(_) => Algorithm::HS256,
+    };
+
+    if algorithm == Algorithm::HS256 {
+        return JwtKeyRing::new(JwtSigningKey::from_secret(
+            JWT_SECRET.expose_secret().as_bytes(),
+        ));
+    }
+
+    let key_file = std_env::var(env::JWT_PRIVATE_KEY_FILE_ENV_VAR)
+        .ok()
+        .filter(|key_file| !key_file.is_empty());
+    match std_env::var(env::JWT_KEY_DIR_ENV_VAR) {
+        Ok(key_dir) if !key_dir.is_empty() => JwtKeyRing::open(
+            algorithm,
+            Path::new(&key_dir),
+            key_file.as_deref().map(Path::new),
+        )
+        .expect("Failed to load JWT signing keys"),
+        _ => {
+            let key_file = key_file.expect("JWT_PRIVATE_KEY_FILE environment variable not set");
+            JwtKeyRing::new(
+                JwtSigningKey::from_pem_file(algorithm, Path::new(&key_file))
+                    .expect("Failed to load JWT private key"),
+            )
+        }
+    }
+}
+
+// Unset, empty or zero turns scheduled rotation off
+fn set_jwt_key_rotation_interval() -> Option<Duration> {
+    dotenv().ok();
+    std_env::var(env::JWT_KEY_ROTATION_INTERVAL_SECONDS_ENV_VAR)
+        .ok()
+        .filter(|seconds| !seconds.is_empty())
+        .map(|seconds| {
+            seconds
+                .parse()
+                .expect("JWT_KEY_ROTATION_INTERVAL_SECONDS must be a positive number")
+        })
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+}
+
+fn set_admin_token() -> Option<Secret<String>> {
+    dotenv().ok();
+    std_env::var(env::ADMIN_TOKEN_ENV_VAR)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .map(Secret::new)
+}
+
+fn set_jwt_issuer() -> String {
+    dotenv().ok();
+    std_env::var(env::JWT_ISSUER_ENV_VAR).unwrap_or_else(|_| AUTH_BASE_URL.to_owned())
+}
+
+// Relying apps tokens can be issued for, the first one gets the auth cookie
+fn set_jwt_audiences() -> Vec<String> {
+    dotenv().ok();
+    let audiences: Vec<String> = std_env::var(env::JWT_AUDIENCES_ENV_VAR)
+        .unwrap_or(DEFAULT_JWT_AUDIENCE.to_owned())
+        .split(',')
+        .map(|audience| audience.trim().to_owned())
+        .filter(|audience| !audience.is_empty())
+        .collect();
+    if audiences.is_empty() {
+        panic!("JWT_AUDIENCES environment variable is empty");
+    }
+    audiences
 }
 
 fn set_database_url() -> Secret<String> {
@@ -45,14 +142,124 @@ fn set_slack_webhook() -> Secret<String> {
     Secret::new(slack_webhook)
 }
 
+fn set_auth_base_url() -> String {
+    dotenv().ok();
+    std_env::var(env::AUTH_BASE_URL_ENV_VAR).unwrap_or(DEFAULT_AUTH_BASE_URL.to_owned())
+}
+
+fn set_totp_encryption_key() -> Secret<String> {
+    dotenv().ok();
+    let key = std_env::var(env::TOTP_ENCRYPTION_KEY_ENV_VAR)
+        .expect("TOTP_ENCRYPTION_KEY environment variable not set");
+    if key.is_empty() {
+        panic!("TOTP_ENCRYPTION_KEY environment variable is empty");
+    }
+    Secret::new(key)
+}
+
+fn set_unverified_login_policy() -> UnverifiedLoginPolicy {
+    dotenv().ok();
+    match std_env::var(env::UNVERIFIED_LOGIN_POLICY_ENV_VAR) {
+        Ok(policy) => UnverifiedLoginPolicy::parse(&policy)
+            .expect("UNVERIFIED_LOGIN_POLICY must be one of: allow, restrict, refuse"),
+        Err(_) => UnverifiedLoginPolicy::default(),
+    }
+}
+
+fn set_login_throttle() -> LoginThrottleConfig {
+    dotenv().ok();
+    let defaults = LoginThrottleConfig::default();
+    let base_backoff_seconds = env_or(
+        env::LOGIN_BACKOFF_BASE_SECONDS_ENV_VAR,
+        defaults.email.base_backoff_seconds,
+    );
+    let lockout_seconds = env_or(
+        env::LOGIN_LOCKOUT_SECONDS_ENV_VAR,
+        defaults.email.lockout_seconds,
+    );
+
+    LoginThrottleConfig {
+        email: LoginThrottlePolicy {
+            backoff_after: env_or(
+                env::LOGIN_EMAIL_BACKOFF_AFTER_ENV_VAR,
+                defaults.email.backoff_after,
+            ),
+            lockout_after: env_or(
+                env::LOGIN_EMAIL_LOCKOUT_AFTER_ENV_VAR,
+                defaults.email.lockout_after,
+            ),
+            base_backoff_seconds,
+            lockout_seconds,
+        },
+        ip: LoginThrottlePolicy {
+            backoff_after: env_or(
+                env::LOGIN_IP_BACKOFF_AFTER_ENV_VAR,
+                defaults.ip.backoff_after,
+            ),
+            lockout_after: env_or(
+                env::LOGIN_IP_LOCKOUT_AFTER_ENV_VAR,
+                defaults.ip.lockout_after,
+            ),
+            base_backoff_seconds,
+            lockout_seconds,
+        },
+    }
+}
+
+fn set_rate_limits() -> RateLimitConfig {
+    dotenv().ok();
+    match std_env::var(env::RATE_LIMITS_ENV_VAR) {
+        Ok(rate_limits) => RateLimitConfig::parse(&rate_limits)
+            .expect("RATE_LIMITS must look like: /login=20/60,/signup=10/60@ip"),
+        Err(_) => RateLimitConfig::default(),
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match std_env::var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a non-negative number", name)),
+        Err(_) => default,
+    }
+}
+
 pub const JWT_COOKIE_NAME: &str = "jwt";
+pub const REFRESH_COOKIE_NAME: &str = "refresh_token";
 pub const DEFAULT_REDIS_HOSTNAME: &str = "127.0.0.1";
+pub const DEFAULT_AUTH_BASE_URL: &str = "http://localhost:3000";
+pub const DEFAULT_JWT_AUDIENCE: &str = "app-service";
+pub const DEFAULT_JWT_LEEWAY_SECONDS: u64 = 60;
+pub const PASSWORD_RESET_TOKEN_TTL_SECONDS: i64 = 900; // 15 minutes
+pub const EMAIL_VERIFICATION_TOKEN_TTL_SECONDS: i64 = 86400; // 24 hours
+pub const AUTHORIZATION_CODE_TTL_SECONDS: i64 = 60; // 1 minute
+pub const TOTP_ISSUER: &str = "Live Bootcamp";
+pub const RECOVERY_CODE_COUNT: usize = 10;
+pub const MAX_TWO_FA_ATTEMPTS: u32 = 5;
 
 pub mod env {
     pub const JWT_SECRET_ENV_VAR: &str = "JWT_SECRET";
+    pub const JWT_SIGNING_ALGORITHM_ENV_VAR: &str = "JWT_SIGNING_ALGORITHM";
+    pub const JWT_PRIVATE_KEY_FILE_ENV_VAR: &str = "JWT_PRIVATE_KEY_FILE";
+    pub const JWT_KEY_DIR_ENV_VAR: &str = "JWT_KEY_DIR";
+    pub const JWT_KEY_ROTATION_INTERVAL_SECONDS_ENV_VAR: &str = "JWT_KEY_ROTATION_INTERVAL_SECONDS";
+    pub const ADMIN_TOKEN_ENV_VAR: &str = "ADMIN_TOKEN";
+    pub const JWT_ISSUER_ENV_VAR: &str = "JWT_ISSUER";
+    pub const JWT_AUDIENCES_ENV_VAR: &str = "JWT_AUDIENCES";
+    pub const JWT_LEEWAY_SECONDS_ENV_VAR: &str = "JWT_LEEWAY_SECONDS";
     pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
     pub const REDIS_HOST_NAME_ENV_VAR: &str = "REDIS_HOST_NAME";
     pub const SLACK_WEBHOOK_ENV_VAR: &str = "SLACK_WEBHOOK";
+    pub const AUTH_BASE_URL_ENV_VAR: &str = "AUTH_BASE_URL";
+    pub const TOTP_ENCRYPTION_KEY_ENV_VAR: &str = "TOTP_ENCRYPTION_KEY";
+    pub const UNVERIFIED_LOGIN_POLICY_ENV_VAR: &str = "UNVERIFIED_LOGIN_POLICY";
+    pub const LOGIN_EMAIL_BACKOFF_AFTER_ENV_VAR: &str = "LOGIN_EMAIL_BACKOFF_AFTER";
+    pub const LOGIN_EMAIL_LOCKOUT_AFTER_ENV_VAR: &str = "LOGIN_EMAIL_LOCKOUT_AFTER";
+    pub const LOGIN_IP_BACKOFF_AFTER_ENV_VAR: &str = "LOGIN_IP_BACKOFF_AFTER";
+    pub const LOGIN_IP_LOCKOUT_AFTER_ENV_VAR: &str = "LOGIN_IP_LOCKOUT_AFTER";
+    pub const LOGIN_BACKOFF_BASE_SECONDS_ENV_VAR: &str = "LOGIN_BACKOFF_BASE_SECONDS";
+    pub const LOGIN_LOCKOUT_SECONDS_ENV_VAR: &str = "LOGIN_LOCKOUT_SECONDS";
+    pub const RATE_LIMITS_ENV_VAR: &str = "RATE_LIMITS";
 }
 
 pub mod prod {
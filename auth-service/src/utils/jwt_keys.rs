@@ -0,0 +1,772 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::{
+    rand::SystemRandom,
+    rsa::PublicKeyComponents,
+    signature::{Ed25519KeyPair, KeyPair, RsaKeyPair},
+};
+use rsa::{
+    pkcs8::{EncodePrivateKey, LineEnding},
+    RsaPrivateKey,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{auth::TOKEN_TTL_SECONDS, constants::JWT_LEEWAY_SECONDS};
+use crate::app_state::JwtKeyRingType;
+
+const RSA_KEY_BITS: usize = 2048;
+
+// How often instances sharing a key directory look for keys another instance rotated in
+pub const KEY_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+// Key the auth tokens are signed with. Asymmetric keys are published in the JWKS
+// so other services can verify tokens without calling us.
+pub struct JwtSigningKey {
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    // Public half of asymmetric keys, a shared secret is never published
+    jwk: Option<Jwk>,
+}
+
+impl JwtSigningKey {
+    pub fn from_secret(secret: &[u8]) -> Self {
+        Self {
+            algorithm: Algorithm::HS256,
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+            jwk: None,
+        }
+    }
+
+    // Loads a private key in PKCS#8 PEM format, RS256 also accepts PKCS#1
+    pub fn from_pem(algorithm: Algorithm, pem: &[u8]) -> Result<Self> {
+        let parsed = pem::parse(pem).wrap_err("Failed to parse PEM")?;
+
+        let (encoding_key, decoding_key, parameters) = match algorithm {
+            Algorithm::RS256 => {
+                let key_pair = match parsed.tag() {
+                    "RSA PRIVATE KEY" => RsaKeyPair::from_der(parsed.contents()),
+                    _ => RsaKeyPair::from_pkcs8(parsed.contents()),
+                }
+                .map_err(|e| eyre!("Invalid RSA private key: {}", e))?;
+                let components = PublicKeyComponents::<Vec<u8>>::from(key_pair.public());
+                let n = without_leading_zeros(&components.n);
+                let e = without_leading_zeros(&components.e);
+
+                (
+                    EncodingKey::from_rsa_pem(pem).wrap_err("Invalid RSA private key")?,
+                    DecodingKey::from_rsa_raw_components(n, e),
+                    AlgorithmParameters::RSA(RSAKeyParameters {
+                        key_type: RSAKeyType::RSA,
+                        n: BASE64URL_NOPAD.encode(n),
+                        e: BASE64URL_NOPAD.encode(e),
+                    }),
+                )
+            }
+            Algorithm::EdDSA => {
+                let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(parsed.contents())
+                    .map_err(|e| eyre!("Invalid Ed25519 private key: {}", e))?;
+                let x = BASE64URL_NOPAD.encode(key_pair.public_key().as_ref());
+
+                (
+                    EncodingKey::from_ed_pem(pem).wrap_err("Invalid Ed25519 private key")?,
+                    DecodingKey::from_ed_components(&x).wrap_err("Invalid Ed25519 public key")?,
+                    AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x,
+                    }),
+                )
+            }
+            _ => return Err(eyre!("Unsupported signing algorithm: {:?}", algorithm)),
+        };
+
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(key_algorithm(algorithm)),
+                key_id: Some(thumbprint(&parameters)),
+                ..Default::default()
+            },
+            algorithm: parameters,
+        };
+
+        Ok(Self {
+            algorithm,
+            encoding_key,
+            decoding_key,
+            jwk: Some(jwk),
+        })
+    }
+
+    pub fn from_pem_file(algorithm: Algorithm, path: &Path) -> Result<Self> {
+        let pem = std::fs::read(path)
+            .wrap_err_with(|| format!("Failed to read key file {}", path.display()))?;
+        Self::from_pem(algorithm, &pem)
+    }
+
+    // Creates a new private key, along with its PKCS#8 PEM so it can be saved
+    pub fn generate(algorithm: Algorithm) -> Result<(Self, Vec<u8>)> {
+        let pem = match algorithm {
+            Algorithm::RS256 => RsaPrivateKey::new(&mut rand::thread_rng(), RSA_KEY_BITS)
+                .wrap_err("Failed to generate RSA key")?
+                .to_pkcs8_pem(LineEnding::LF)
+                .wrap_err("Failed to encode RSA key")?
+                .as_bytes()
+                .to_vec(),
+            Algorithm::EdDSA => {
+                let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                    .map_err(|e| eyre!("Failed to generate Ed25519 key: {}", e))?;
+                pem::encode(&pem::Pem::new("PRIVATE KEY", document.as_ref())).into_bytes()
+            }
+            _ => return Err(eyre!("Cannot generate {:?} keys", algorithm)),
+        };
+
+        Ok((Self::from_pem(algorithm, &pem)?, pem))
+    }
+
+    pub fn kid(&self) -> Option<&str> {
+        self.jwk.as_ref()?.common.key_id.as_deref()
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String> {
+        let mut header = Header::new(self.algorithm);
+        header.kid = self.kid().map(str::to_owned);
+
+        encode(&header, claims, &self.encoding_key).wrap_err("Failed to create token")
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, token: &str, validation: &Validation) -> Result<T> {
+        let header = decode_header(token).wrap_err("Failed to decode token header.")?;
+        if header.kid.as_deref() != self.kid() {
+            return Err(eyre!("Token was signed with an unknown key."));
+        }
+
+        // Only the algorithm of the key is accepted, so a token cannot pick a weaker one
+        let mut validation = validation.clone();
+        validation.algorithms = vec![self.algorithm];
+
+        decode::<T>(token, &self.decoding_key, &validation)
+            .map(|data| data.claims)
+            .wrap_err("Failed to decode token.")
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.jwk.iter().cloned().collect(),
+        }
+    }
+}
+
+// A key that no longer signs tokens, accepted until the last token it signed has expired
+struct RetiredKey {
+    key: JwtSigningKey,
+    accepted_until: i64,
+    path: Option<PathBuf>,
+}
+
+// One active key signing new tokens, plus retired keys still accepted for verification.
+// Tokens name the key they were signed with in the kid header.
+pub struct JwtKeyRing {
+    active: JwtSigningKey,
+    // Where the active key is saved, rotation is only possible with a key directory
+    active_path: Option<PathBuf>,
+    retired: Vec<RetiredKey>,
+}
+
+impl JwtKeyRing {
+    pub fn new(active: JwtSigningKey) -> Self {
+        Self {
+            active,
+            active_path: None,
+            retired: Vec::new(),
+        }
+    }
+
+    // Loads the keys saved in `key_dir`, each named after its creation time in milliseconds,
+    // so keys rotated before a restart keep verifying their tokens.
+    // An empty directory is seeded with `initial_key`, or a generated key.
+    pub fn open(algorithm: Algorithm, key_dir: &Path, initial_key: Option<&Path>) -> Result<Self> {
+        if algorithm == Algorithm::HS256 {
+            return Err(eyre!(
+                "A key directory needs an RS256 or EdDSA signing algorithm"
+            ));
+        }
+        fs::create_dir_all(key_dir)
+            .wrap_err_with(|| format!("Failed to create key directory {}", key_dir.display()))?;
+
+        let mut saved = saved_keys(key_dir)?;
+        if saved.is_empty() {
+            let pem = match initial_key {
+                Some(path) => fs::read(path)
+                    .wrap_err_with(|| format!("Failed to read key file {}", path.display()))?,
+                None => JwtSigningKey::generate(algorithm)?.1,
+            };
+            let created_at_ms = Utc::now().timestamp_millis();
+            saved.push((created_at_ms, save_key(key_dir, created_at_ms, &pem)?));
+        }
+
+        // A key was retired when the one after it was created
+        let mut retired = Vec::new();
+        for pair in saved.windows(2) {
+            let (_, path) = &pair[0];
+            let (successor_created_at_ms, _) = pair[1];
+            retired.push(RetiredKey {
+                key: JwtSigningKey::from_pem_file(algorithm, path)?,
+                accepted_until: successor_created_at_ms / 1000 + retired_key_grace_seconds(),
+                path: Some(path.clone()),
+            });
+        }
+        let (_, active_path) = saved.pop().wrap_err("No saved signing key")?;
+
+        let mut ring = Self {
+            active: JwtSigningKey::from_pem_file(algorithm, &active_path)?,
+            active_path: Some(active_path),
+            retired,
+        };
+        ring.prune(Utc::now().timestamp());
+        Ok(ring)
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.active.algorithm
+    }
+
+    pub fn can_rotate(&self) -> bool {
+        self.active_path.is_some()
+    }
+
+    // Saves `key` and starts signing with it. The previous key keeps verifying
+    // the tokens it signed until they have expired.
+    pub fn rotate(&mut self, key: JwtSigningKey, pem: &[u8]) -> Result<()> {
+        let key_dir = self
+            .active_path
+            .as_deref()
+            .and_then(Path::parent)
+            .wrap_err("Key rotation needs a key directory")?;
+        if key.algorithm != self.active.algorithm {
+            return Err(eyre!("The new key must use {:?}", self.active.algorithm));
+        }
+
+        let now_ms = Utc::now().timestamp_millis();
+        // Keys are ordered by their file names, so the new one has to come after the active one
+        // even when both were created within the same millisecond
+        let created_at_ms = self
+            .active_path
+            .as_deref()
+            .and_then(key_created_at_ms)
+            .map_or(now_ms, |active_ms| now_ms.max(active_ms + 1));
+        let path = save_key(key_dir, created_at_ms, pem)?;
+
+        let previous = std::mem::replace(&mut self.active, key);
+        let previous_path = self.active_path.replace(path);
+        self.retired.push(RetiredKey {
+            key: previous,
+            accepted_until: now_ms / 1000 + retired_key_grace_seconds(),
+            path: previous_path,
+        });
+        self.prune(now_ms / 1000);
+        Ok(())
+    }
+
+    // Picks up keys another instance sharing the key directory rotated in.
+    // Returns whether the active key changed.
+    pub fn reload(&mut self) -> Result<bool> {
+        let Some(key_dir) = self
+            .active_path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+        else {
+            return Ok(false);
+        };
+
+        let newest = saved_keys(&key_dir)?.pop().map(|(_, path)| path);
+        if newest.is_none() || newest == self.active_path {
+            return Ok(false);
+        }
+
+        *self = Self::open(self.algorithm(), &key_dir, None)?;
+        Ok(true)
+    }
+
+    pub fn active_kid(&self) -> Option<&str> {
+        self.active.kid()
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String> {
+        self.active.encode(claims)
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, token: &str, validation: &Validation) -> Result<T> {
+        let header = decode_header(token).wrap_err("Failed to decode token header.")?;
+        self.keys(Utc::now().timestamp())
+            .find(|key| key.kid() == header.kid.as_deref())
+            .wrap_err("Token was signed with an unknown key.")?
+            .decode(token, validation)
+    }
+
+    // Retired keys stay published, so verifiers can still check tokens they signed
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .keys(Utc::now().timestamp())
+                .flat_map(|key| key.jwks().keys)
+                .collect(),
+        }
+    }
+
+    fn keys(&self, now: i64) -> impl Iterator<Item = &JwtSigningKey> {
+        std::iter::once(&self.active).chain(
+            self.retired
+                .iter()
+                .filter(move |retired| retired.accepted_until >= now)
+                .map(|retired| &retired.key),
+        )
+    }
+
+    // Forgets retired keys whose tokens have all expired
+    fn prune(&mut self, now: i64) {
+        self.retired.retain(|retired| {
+            if retired.accepted_until >= now {
+                return true;
+            }
+            if let Some(path) = &retired.path {
+                if let Err(e) = fs::remove_file(path) {
+                    tracing::warn!("Failed to remove retired key {}: {}", path.display(), e);
+                }
+            }
+            false
+        });
+    }
+}
+
+// Decodes with the keys this instance knows. A key another instance just rotated in is
+// picked up by `reload_signing_keys_every`, not on demand, so forged key ids cannot make
+// every request take the write lock and rescan the key directory.
+pub async fn decode_token<T: DeserializeOwned>(
+    jwt_key_ring: &JwtKeyRingType,
+    token: &str,
+    validation: &Validation,
+) -> Result<T> {
+    jwt_key_ring.read().await.decode(token, validation)
+}
+
+// Generates the key outside of the ring lock, as RSA key generation takes a while
+pub async fn rotate_signing_key(jwt_key_ring: &JwtKeyRingType) -> Result<String> {
+    let algorithm = jwt_key_ring.read().await.algorithm();
+    let (key, pem) = tokio::task::spawn_blocking(move || JwtSigningKey::generate(algorithm))
+        .await
+        .wrap_err("Key generation task failed")??;
+
+    let mut jwt_key_ring = jwt_key_ring.write().await;
+    jwt_key_ring.rotate(key, &pem)?;
+    jwt_key_ring
+        .active_kid()
+        .map(str::to_owned)
+        .wrap_err("Rotated key has no kid")
+}
+
+// Scheduled rotation. With several instances, only one of them should rotate,
+// the others pick up the new keys through `reload_signing_keys_every`.
+pub async fn rotate_signing_key_every(jwt_key_ring: JwtKeyRingType, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    // The first tick completes immediately
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        match rotate_signing_key(&jwt_key_ring).await {
+            Ok(kid) => tracing::info!("Rotated JWT signing key, new kid: {}", kid),
+            Err(e) => tracing::error!("Failed to rotate JWT signing key: {:?}", e),
+        }
+    }
+}
+
+// Keeps every instance sharing the key directory signing with the newest key
+pub async fn reload_signing_keys_every(jwt_key_ring: JwtKeyRingType, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    // The first tick completes immediately
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        match jwt_key_ring.write().await.reload() {
+            Ok(true) => tracing::info!("Reloaded JWT signing keys"),
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to reload JWT signing keys: {:?}", e),
+        }
+    }
+}
+
+// Tokens signed by a retired key live at most TOKEN_TTL_SECONDS, plus the leeway of the expiry check.
+// Other instances may go on signing with it until their next reload.
+fn retired_key_grace_seconds() -> i64 {
+    TOKEN_TTL_SECONDS + *JWT_LEEWAY_SECONDS as i64 + KEY_RELOAD_INTERVAL.as_secs() as i64
+}
+
+// Saved keys ordered from oldest to newest
+fn saved_keys(key_dir: &Path) -> Result<Vec<(i64, PathBuf)>> {
+    let entries = fs::read_dir(key_dir)
+        .wrap_err_with(|| format!("Failed to read key directory {}", key_dir.display()))?;
+
+    let mut keys = Vec::new();
+    for entry in entries {
+        let path = entry.wrap_err("Failed to read key directory entry")?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("pem") {
+            continue;
+        }
+        if let Some(created_at_ms) = key_created_at_ms(&path) {
+            keys.push((created_at_ms, path));
+        }
+    }
+    keys.sort();
+    Ok(keys)
+}
+
+fn key_created_at_ms(path: &Path) -> Option<i64> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.parse::<i64>().ok())
+}
+
+fn save_key(key_dir: &Path, created_at_ms: i64, pem: &[u8]) -> Result<PathBuf> {
+    let path = key_dir.join(format!("{}.pem", created_at_ms));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(pem))
+        .wrap_err_with(|| format!("Failed to save key file {}", path.display()))?;
+    Ok(path)
+}
+
+fn key_algorithm(algorithm: Algorithm) -> KeyAlgorithm {
+    match algorithm {
+        Algorithm::EdDSA => KeyAlgorithm::EdDSA,
+        _ => KeyAlgorithm::RS256,
+    }
+}
+
+fn without_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+// RFC 7638 thumbprint, so the same key always ends up with the same kid
+fn thumbprint(parameters: &AlgorithmParameters) -> String {
+    let canonical = match parameters {
+        AlgorithmParameters::RSA(rsa) => {
+            format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, rsa.e, rsa.n)
+        }
+        AlgorithmParameters::OctetKeyPair(okp) => {
+            format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, okp.x)
+        }
+        _ => unreachable!("Only RSA and Ed25519 keys are supported"),
+    };
+    BASE64URL_NOPAD.encode(&Sha256::digest(canonical.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    const RS256_PEM: &[u8] = include_bytes!("../../tests/fixtures/jwt_rs256_private.pem");
+    const EDDSA_PEM: &[u8] = include_bytes!("../../tests/fixtures/jwt_eddsa_private.pem");
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TestClaims {
+        sub: String,
+        exp: usize,
+    }
+
+    fn claims() -> TestClaims {
+        TestClaims {
+            sub: "test@example.com".to_owned(),
+            exp: (chrono::Utc::now().timestamp() + 600) as usize,
+        }
+    }
+
+    #[test]
+    fn test_asymmetric_keys_roundtrip() {
+        for (algorithm, pem) in [(Algorithm::RS256, RS256_PEM), (Algorithm::EdDSA, EDDSA_PEM)] {
+            // Arrange
+            let key = JwtSigningKey::from_pem(algorithm, pem).unwrap();
+            let claims = claims();
+
+            // Act
+            let token = key.encode(&claims).unwrap();
+            let result: TestClaims = key.decode(&token, &Validation::default()).unwrap();
+
+            // Assert
+            assert_eq!(result, claims, "Failed for algorithm: {:?}", algorithm);
+            let header = decode_header(&token).unwrap();
+            assert_eq!(header.alg, algorithm);
+            assert_eq!(header.kid.as_deref(), key.kid());
+        }
+    }
+
+    #[test]
+    fn test_token_verifies_with_published_jwk() {
+        for (algorithm, pem) in [(Algorithm::RS256, RS256_PEM), (Algorithm::EdDSA, EDDSA_PEM)] {
+            // Arrange
+            let key = JwtSigningKey::from_pem(algorithm, pem).unwrap();
+            let token = key.encode(&claims()).unwrap();
+            let jwks = key.jwks();
+
+            // Act
+            let jwk = jwks.find(key.kid().unwrap()).unwrap();
+            let result = decode::<TestClaims>(
+                &token,
+                &DecodingKey::from_jwk(jwk).unwrap(),
+                &Validation::new(algorithm),
+            );
+
+            // Assert
+            assert!(result.is_ok(), "Failed for algorithm: {:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_token_from_other_key() {
+        // Arrange
+        let rsa_key = JwtSigningKey::from_pem(Algorithm::RS256, RS256_PEM).unwrap();
+        let ed_key = JwtSigningKey::from_pem(Algorithm::EdDSA, EDDSA_PEM).unwrap();
+        let hmac_key = JwtSigningKey::from_secret(b"secret");
+
+        // Act & Assert
+        let token = ed_key.encode(&claims()).unwrap();
+        assert!(rsa_key
+            .decode::<TestClaims>(&token, &Validation::default())
+            .is_err());
+        let token = hmac_key.encode(&claims()).unwrap();
+        assert!(rsa_key
+            .decode::<TestClaims>(&token, &Validation::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_thumbprint_matches_rfc_example() {
+        // Arrange
+        let parameters = AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw".to_owned(),
+            e: "AQAB".to_owned(),
+        });
+
+        // Act
+        let result = thumbprint(&parameters);
+
+        // Assert
+        assert_eq!(result, "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs");
+    }
+
+    #[test]
+    fn test_secret_key_is_not_published() {
+        // Arrange
+        let key = JwtSigningKey::from_secret(b"secret");
+
+        // Act
+        let jwks = key.jwks();
+
+        // Assert
+        assert!(jwks.keys.is_empty());
+        assert_eq!(key.kid(), None);
+    }
+
+    #[test]
+    fn test_from_pem_rejects_mismatched_key() {
+        // Arrange & Act
+        let result = JwtSigningKey::from_pem(Algorithm::EdDSA, RS256_PEM);
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    fn temp_key_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("jwt-keys-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_generated_key_roundtrip() {
+        // Arrange
+        let (key, pem) = JwtSigningKey::generate(Algorithm::EdDSA).unwrap();
+        let claims = claims();
+
+        // Act
+        let token = key.encode(&claims).unwrap();
+        let reloaded = JwtSigningKey::from_pem(Algorithm::EdDSA, &pem).unwrap();
+
+        // Assert
+        assert_eq!(
+            reloaded
+                .decode::<TestClaims>(&token, &Validation::default())
+                .unwrap(),
+            claims
+        );
+        assert!(JwtSigningKey::generate(Algorithm::HS256).is_err());
+    }
+
+    #[test]
+    fn test_rotation_keeps_tokens_of_retired_key_valid() {
+        // Arrange
+        let key_dir = temp_key_dir();
+        let mut ring = JwtKeyRing::open(
+            Algorithm::EdDSA,
+            &key_dir,
+            Some(Path::new("tests/fixtures/jwt_eddsa_private.pem")),
+        )
+        .unwrap();
+        let old_kid = ring.active_kid().unwrap().to_owned();
+        let old_token = ring.encode(&claims()).unwrap();
+        let (key, pem) = JwtSigningKey::generate(Algorithm::EdDSA).unwrap();
+
+        // Act
+        ring.rotate(key, &pem).unwrap();
+
+        // Assert
+        let new_token = ring.encode(&claims()).unwrap();
+        assert_ne!(ring.active_kid().unwrap(), old_kid);
+        assert_eq!(
+            decode_header(&new_token).unwrap().kid.as_deref(),
+            ring.active_kid()
+        );
+        assert!(ring
+            .decode::<TestClaims>(&old_token, &Validation::default())
+            .is_ok());
+        assert!(ring
+            .decode::<TestClaims>(&new_token, &Validation::default())
+            .is_ok());
+        assert_eq!(ring.jwks().keys.len(), 2);
+
+        fs::remove_dir_all(key_dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_restores_rotated_keys() {
+        // Arrange
+        let key_dir = temp_key_dir();
+        let mut ring = JwtKeyRing::open(Algorithm::EdDSA, &key_dir, None).unwrap();
+        let old_token = ring.encode(&claims()).unwrap();
+        let (key, pem) = JwtSigningKey::generate(Algorithm::EdDSA).unwrap();
+        ring.rotate(key, &pem).unwrap();
+
+        // Act
+        let reopened = JwtKeyRing::open(Algorithm::EdDSA, &key_dir, None).unwrap();
+
+        // Assert
+        assert_eq!(reopened.active_kid(), ring.active_kid());
+        assert!(reopened
+            .decode::<TestClaims>(&old_token, &Validation::default())
+            .is_ok());
+
+        fs::remove_dir_all(key_dir).unwrap();
+    }
+
+    #[test]
+    fn test_reload_picks_up_key_rotated_by_other_instance() {
+        // Arrange
+        let key_dir = temp_key_dir();
+        let mut rotating = JwtKeyRing::open(Algorithm::EdDSA, &key_dir, None).unwrap();
+        let mut other = JwtKeyRing::open(Algorithm::EdDSA, &key_dir, None).unwrap();
+        let old_token = other.encode(&claims()).unwrap();
+        let (key, pem) = JwtSigningKey::generate(Algorithm::EdDSA).unwrap();
+        rotating.rotate(key, &pem).unwrap();
+
+        // Act
+        let reloaded = other.reload().unwrap();
+
+        // Assert
+        assert!(reloaded);
+        assert!(!other.reload().unwrap());
+        assert_eq!(other.active_kid(), rotating.active_kid());
+        assert!(other
+            .decode::<TestClaims>(&old_token, &Validation::default())
+            .is_ok());
+
+        fs::remove_dir_all(key_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_decode_token_does_not_reload_on_unknown_kid() {
+        // Arrange
+        let key_dir = temp_key_dir();
+        let mut rotating = JwtKeyRing::open(Algorithm::EdDSA, &key_dir, None).unwrap();
+        let other: JwtKeyRingType = std::sync::Arc::new(tokio::sync::RwLock::new(
+            JwtKeyRing::open(Algorithm::EdDSA, &key_dir, None).unwrap(),
+        ));
+        let old_kid = other.read().await.active_kid().map(str::to_owned);
+        let (key, pem) = JwtSigningKey::generate(Algorithm::EdDSA).unwrap();
+        rotating.rotate(key, &pem).unwrap();
+        let new_token = rotating.encode(&claims()).unwrap();
+
+        // Act
+        let result = decode_token::<TestClaims>(&other, &new_token, &Validation::default()).await;
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(other.read().await.active_kid().map(str::to_owned), old_kid);
+
+        fs::remove_dir_all(key_dir).unwrap();
+    }
+
+    #[test]
+    fn test_decode_rejects_key_retired_for_too_long() {
+        // Arrange
+        let retired_key = JwtSigningKey::from_pem(Algorithm::EdDSA, EDDSA_PEM).unwrap();
+        let token = retired_key.encode(&claims()).unwrap();
+        let mut ring =
+            JwtKeyRing::new(JwtSigningKey::from_pem(Algorithm::RS256, RS256_PEM).unwrap());
+        ring.retired.push(RetiredKey {
+            key: retired_key,
+            accepted_until: Utc::now().timestamp() - 1,
+            path: None,
+        });
+
+        // Act
+        let result = ring.decode::<TestClaims>(&token, &Validation::default());
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(ring.jwks().keys.len(), 1);
+    }
+
+    #[test]
+    fn test_rotate_needs_key_dir() {
+        // Arrange
+        let mut ring = JwtKeyRing::new(JwtSigningKey::from_secret(b"secret"));
+        let (key, pem) = JwtSigningKey::generate(Algorithm::EdDSA).unwrap();
+
+        // Act
+        let result = ring.rotate(key, &pem);
+
+        // Assert
+        assert!(!ring.can_rotate());
+        assert!(result.is_err());
+        assert!(JwtKeyRing::open(Algorithm::HS256, &temp_key_dir(), None).is_err());
+    }
+}
@@ -1,7 +1,21 @@
+mod admin;
 pub mod auth;
+mod authenticated_user;
+mod client_ip;
 pub mod constants;
+mod jwt_keys;
+mod rate_limit;
+mod require_role;
 mod tracing;
+mod user_agent;
 
 // re-export items from sub-modules
+pub use admin::*;
+pub use authenticated_user::*;
+pub use client_ip::*;
 pub use constants::*;
+pub use jwt_keys::*;
+pub use rate_limit::*;
+pub use require_role::*;
 pub use tracing::*;
+pub use user_agent::*;
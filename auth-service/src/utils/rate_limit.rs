@@ -0,0 +1,89 @@
+use crate::{
+    domain::{AuthAPIError, RateLimitDecision, RateLimitScope},
+    AppState,
+};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use super::{auth::validate_token, request_auth_token, ClientIp};
+
+// Applies the limit configured for the matched route and reports it in RateLimit-* headers
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    matched_path: Option<MatchedPath>,
+    ClientIp(client_ip): ClientIp,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(path) = matched_path else {
+        return next.run(request).await;
+    };
+    let Some(route_limit) = state.rate_limits.get(path.as_str()).copied() else {
+        return next.run(request).await;
+    };
+
+    let account = match route_limit.scope {
+        RateLimitScope::Account => authenticated_account(&state, request.headers()).await,
+        RateLimitScope::Ip => None,
+    };
+    let subject = match (account, client_ip) {
+        (Some(account), _) => format!("account:{}", account),
+        (None, Some(ip)) => format!("ip:{}", ip),
+        // Requests that cannot be told apart are not limited, rather than sharing one bucket
+        (None, None) => return next.run(request).await,
+    };
+    let key = format!("{}:{}", path.as_str(), subject);
+
+    let decision = match state
+        .rate_limit_store
+        .write()
+        .await
+        .take_token(&key, &route_limit.limit)
+        .await
+    {
+        Ok(decision) => decision,
+        Err(e) => {
+            // An unavailable store should not take the whole service down with it
+            tracing::error!(error = ?e, "Rate limit check failed");
+            return next.run(request).await;
+        }
+    };
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        AuthAPIError::RateLimited {
+            retry_after_seconds: decision.retry_after_seconds,
+        }
+        .into_response()
+    };
+    insert_rate_limit_headers(response.headers_mut(), &decision);
+    response
+}
+
+async fn authenticated_account(state: &AppState, headers: &HeaderMap) -> Option<String> {
+    let token = request_auth_token(headers)?;
+    let claims = validate_token(
+        &token,
+        state.jwt_key_ring.clone(),
+        state.banned_token_store.clone(),
+        state.session_store.clone(),
+    )
+    .await
+    .ok()?;
+    Some(claims.sub)
+}
+
+fn insert_rate_limit_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    for (name, value) in [
+        ("RateLimit-Limit", decision.limit as u64),
+        ("RateLimit-Remaining", decision.remaining as u64),
+        ("RateLimit-Reset", decision.reset_seconds),
+    ] {
+        headers.insert(name, HeaderValue::from(value));
+    }
+}
@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{domain::AuthAPIError, AppState};
+
+use super::{auth::validate_token, bearer_token, is_admin_token, request_auth_token};
+
+// Lets only users holding the role through to the routes it guards, e.g.
+// `.route_layer(middleware::from_fn_with_state(RequireRole::new("admin", state), require_role))`.
+// The static admin token stands for the operator and passes every role check.
+#[derive(Clone)]
+pub struct RequireRole {
+    role: &'static str,
+    state: AppState,
+}
+
+impl RequireRole {
+    pub fn new(role: &'static str, state: AppState) -> Self {
+        Self { role, state }
+    }
+}
+
+pub async fn require_role(
+    State(guard): State<RequireRole>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match guard.check(request.headers()).await {
+        Ok(()) => next.run(request).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+impl RequireRole {
+    async fn check(&self, headers: &HeaderMap) -> Result<(), AuthAPIError> {
+        if bearer_token(headers).is_some_and(|token| is_admin_token(&self.state, token)) {
+            return Ok(());
+        }
+
+        let token = request_auth_token(headers).ok_or(AuthAPIError::MissingToken)?;
+        let claims = validate_token(
+            &token,
+            self.state.jwt_key_ring.clone(),
+            self.state.banned_token_store.clone(),
+            self.state.session_store.clone(),
+        )
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+        if !claims.roles.iter().any(|role| role == self.role) {
+            return Err(AuthAPIError::MissingRole);
+        }
+        Ok(())
+    }
+}
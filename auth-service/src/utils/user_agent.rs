@@ -0,0 +1,29 @@
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header::USER_AGENT, request::Parts},
+};
+
+// User agent the client sent, if any, shown to the user when listing their sessions
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserAgent(pub Option<String>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for UserAgent
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+
+        Ok(UserAgent(user_agent))
+    }
+}
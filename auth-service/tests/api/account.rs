@@ -0,0 +1,475 @@
+use auth_service::{
+    AccountExportResponse, AdminUserDetailsResponse, LoginThrottleConfig, LoginThrottlePolicy,
+    RefreshToken, SessionTokensResponse, TwoFAMethod, ADMIN_ROLE,
+};
+use db_test_macro::db_test;
+use secrecy::Secret;
+
+use crate::helpers_arrange::{
+    authorize_params, get_user_id, setup_authorization_code, setup_email_change_token,
+    setup_email_verification_token, setup_logged_in_user, setup_oauth_client,
+    setup_registered_user, setup_user_roles, token_claims, TestUser,
+};
+use crate::helpers_assert::{assert_error_message, assert_status, extract_token};
+use crate::helpers_harness::{get_random_email, TestApp, ADMIN_TOKEN};
+
+#[db_test]
+async fn should_change_password_and_log_out_other_devices() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    // The first session stands for another device, the app's cookies belong to the second one
+    let (user, other_device_token) = setup_logged_in_user(&app).await;
+    let second_login = app.post_login(&user.login_payload()).await;
+    assert_status(&second_login, 200, Some("Second login failed"));
+
+    // Act
+    let response = app
+        .post_account_password(&serde_json::json!({
+            "currentPassword": user.password,
+            "newPassword": "new-password123",
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 204, None);
+    let sessions_response = app.get_sessions().await;
+    assert_status(
+        &sessions_response,
+        200,
+        Some("Current session was logged out"),
+    );
+    let other_device_response = app.get_sessions_with_bearer(&other_device_token).await;
+    assert_status(
+        &other_device_response,
+        401,
+        Some("Other device still logged in"),
+    );
+    let old_login = app.post_login(&user.login_payload()).await;
+    assert_status(&old_login, 401, Some("Old password still accepted"));
+    let new_login = app
+        .post_login(&serde_json::json!({
+            "email": user.email,
+            "password": "new-password123",
+        }))
+        .await;
+    assert_status(&new_login, 200, Some("New password rejected"));
+}
+
+#[db_test]
+async fn should_return_401_if_current_password_is_wrong() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    let new_email = get_random_email();
+
+    // Act
+    let responses = [
+        app.post_account_password(&serde_json::json!({
+            "currentPassword": "wrong-password",
+            "newPassword": "new-password123",
+        }))
+        .await,
+        app.post_account_email(&serde_json::json!({
+            "currentPassword": "wrong-password",
+            "newEmail": new_email,
+        }))
+        .await,
+    ];
+
+    // Assert
+    for response in responses {
+        assert_status(&response, 401, None);
+        assert_error_message(response, "Incorrect credentials").await;
+    }
+    let login_response = app.post_login(&user.login_payload()).await;
+    assert_status(&login_response, 200, Some("Credentials changed anyway"));
+}
+
+#[db_test]
+async fn should_return_400_if_invalid_input() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let password_response = app
+        .post_account_password(&serde_json::json!({
+            "currentPassword": user.password,
+            "newPassword": "short",
+        }))
+        .await;
+    let email_response = app
+        .post_account_email(&serde_json::json!({
+            "currentPassword": user.password,
+            "newEmail": "not-an-email",
+        }))
+        .await;
+
+    // Assert
+    assert_status(&password_response, 400, None);
+    assert_error_message(password_response, "Invalid credentials").await;
+    assert_status(&email_response, 400, None);
+    assert_error_message(email_response, "Invalid credentials").await;
+}
+
+#[db_test]
+async fn should_return_400_if_not_logged_in() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .post_account_password(&serde_json::json!({
+            "currentPassword": "password123",
+            "newPassword": "new-password123",
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "Missing token").await;
+}
+
+#[db_test]
+async fn should_keep_old_email_until_change_is_confirmed() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, token) = setup_logged_in_user(&app).await;
+    let new_email = get_random_email();
+
+    // Act
+    let response = app
+        .post_account_email(&serde_json::json!({
+            "currentPassword": user.password,
+            "newEmail": new_email,
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 202, None);
+    let sessions_response = app.get_sessions_with_bearer(&token).await;
+    assert_status(
+        &sessions_response,
+        200,
+        Some("Session ended before confirmation"),
+    );
+    let old_login = app.post_login(&user.login_payload()).await;
+    assert_status(&old_login, 200, Some("Old email stopped signing in"));
+    let new_login = app
+        .post_login(&serde_json::json!({
+            "email": new_email,
+            "password": user.password,
+        }))
+        .await;
+    assert_status(&new_login, 401, Some("Unconfirmed email signs in"));
+}
+
+#[db_test]
+async fn should_change_email_once_confirmed() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, token) = setup_logged_in_user(&app).await;
+    setup_user_roles(&app, &user.email, &[ADMIN_ROLE]).await;
+    let user_id = get_user_id(&app, &user.email).await;
+    let new_email = get_random_email();
+    let change_token = setup_email_change_token(&app, &user_id, &new_email).await;
+
+    // Act
+    let response = app
+        .post_account_email_confirm(&serde_json::json!({ "token": change_token }))
+        .await;
+
+    // Assert
+    assert_status(&response, 204, None);
+    let old_token_response = app.get_sessions_with_bearer(&token).await;
+    assert_status(&old_token_response, 401, Some("Old token still accepted"));
+    let old_login = app.post_login(&user.login_payload()).await;
+    assert_status(&old_login, 401, Some("Old email still signs in"));
+    let new_login = app
+        .post_login(&serde_json::json!({
+            "email": new_email,
+            "password": user.password,
+        }))
+        .await;
+    assert_status(&new_login, 200, Some("New email does not sign in"));
+    let claims = token_claims(&extract_token(&new_login));
+    // The user keeps their id and roles
+    assert_eq!(claims["sub"], user_id);
+    assert_eq!(claims["roles"], serde_json::json!([ADMIN_ROLE]));
+
+    // Using the link proved the new address
+    let details = app
+        .get_admin_user(ADMIN_TOKEN, &user_id)
+        .await
+        .json::<AdminUserDetailsResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUserDetailsResponse");
+    assert!(details.user.verified);
+}
+
+#[db_test]
+async fn should_return_401_if_email_change_token_is_used_twice() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+    let user_id = get_user_id(&app, &user.email).await;
+    let change_token = setup_email_change_token(&app, &user_id, &get_random_email()).await;
+    let request_body = serde_json::json!({ "token": change_token });
+
+    // Act
+    let first_response = app.post_account_email_confirm(&request_body).await;
+    let second_response = app.post_account_email_confirm(&request_body).await;
+
+    // Assert
+    assert_status(&first_response, 204, None);
+    assert_status(&second_response, 401, None);
+    assert_error_message(second_response, "Invalid token").await;
+}
+
+#[db_test]
+async fn should_not_confirm_email_change_with_verification_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+    let token = setup_email_verification_token(&app, &user.email).await;
+
+    // Act
+    let response = app
+        .post_account_email_confirm(&serde_json::json!({ "token": token }))
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+}
+
+#[db_test]
+async fn should_keep_sessions_if_confirmed_email_was_taken_meanwhile() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, token) = setup_logged_in_user(&app).await;
+    let user_id = get_user_id(&app, &user.email).await;
+    let new_email = get_random_email();
+    let change_token = setup_email_change_token(&app, &user_id, &new_email).await;
+    setup_registered_user(&app, &TestUser::with_email(&new_email)).await;
+
+    // Act
+    let response = app
+        .post_account_email_confirm(&serde_json::json!({ "token": change_token }))
+        .await;
+
+    // Assert
+    assert_status(&response, 409, None);
+    let sessions_response = app.get_sessions_with_bearer(&token).await;
+    assert_status(
+        &sessions_response,
+        200,
+        Some("Session ended by failed change"),
+    );
+    let old_login = app.post_login(&user.login_payload()).await;
+    assert_status(&old_login, 200, Some("Old email stopped signing in"));
+}
+
+#[db_test]
+async fn should_return_409_if_new_email_is_taken() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let other_user = setup_registered_user(&app, &TestUser::new()).await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .post_account_email(&serde_json::json!({
+            "currentPassword": user.password,
+            "newEmail": other_user.email,
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 409, None);
+    let sessions_response = app.get_sessions().await;
+    assert_status(
+        &sessions_response,
+        200,
+        Some("Session ended by failed change"),
+    );
+}
+
+#[db_test]
+async fn should_export_account_data() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    setup_user_roles(&app, &user.email, &[ADMIN_ROLE]).await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_authorization_code(&app, &client).await;
+
+    // Act
+    let response = app.get_account_export().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let content_disposition = response
+        .headers()
+        .get(reqwest::header::CONTENT_DISPOSITION)
+        .expect("No Content-Disposition header found")
+        .to_str()
+        .unwrap();
+    assert!(content_disposition.starts_with("attachment"));
+    let export = response
+        .json::<AccountExportResponse>()
+        .await
+        .expect("Could not deserialize response body to AccountExportResponse");
+    assert_eq!(export.user.email, user.email);
+    assert!(!export.two_fa.requires_2fa);
+    assert_eq!(export.two_fa.method, TwoFAMethod::Email);
+    assert_eq!(export.roles, vec![ADMIN_ROLE]);
+    assert_eq!(export.sessions.len(), 1);
+    assert!(export.sessions[0].current);
+    assert_eq!(export.oauth_consents.len(), 1);
+    assert_eq!(export.oauth_consents[0].client_id, client.client_id);
+    assert_eq!(export.oauth_consents[0].scopes, vec!["profile"]);
+}
+
+#[db_test]
+async fn should_delete_account_and_everything_stored_about_it() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, other_device_token) = setup_logged_in_user(&app).await;
+    setup_user_roles(&app, &user.email, &[ADMIN_ROLE]).await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_authorization_code(&app, &client).await;
+    let user_id = get_user_id(&app, &user.email).await;
+    let mut token_login = user.login_payload();
+    token_login["responseMode"] = serde_json::json!("token");
+    let client_tokens = app
+        .post_login(&token_login)
+        .await
+        .json::<SessionTokensResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionTokensResponse");
+
+    // Act
+    let response = app
+        .post_account_delete(&serde_json::json!({ "currentPassword": user.password }))
+        .await;
+
+    // Assert
+    assert_status(&response, 204, None);
+    let jwt_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == "jwt")
+        .expect("No jwt cookie found");
+    assert!(jwt_cookie.value().is_empty());
+    let other_device_response = app.get_sessions_with_bearer(&other_device_token).await;
+    assert_status(
+        &other_device_response,
+        401,
+        Some("Token outlived the account"),
+    );
+    let details_response = app.get_admin_user(ADMIN_TOKEN, &user_id).await;
+    assert_status(&details_response, 404, Some("User still exists"));
+    let refresh_response = app
+        .post_refresh_token_with_body(
+            &serde_json::json!({ "refreshToken": client_tokens.refresh_token }),
+        )
+        .await;
+    assert_status(
+        &refresh_response,
+        401,
+        Some("Refresh token outlived the account"),
+    );
+    let refresh_token = RefreshToken::parse(Secret::new(client_tokens.refresh_token)).unwrap();
+    let refresh_record = app
+        .refresh_token_store
+        .read()
+        .await
+        .get_refresh_token(&refresh_token)
+        .await;
+    assert!(refresh_record.is_err(), "Refresh token record still stored");
+
+    // Nothing of the deleted account carries over when the email signs up again
+    setup_registered_user(&app, &user).await;
+    let login_response = app.post_login(&user.login_payload()).await;
+    assert_status(
+        &login_response,
+        200,
+        Some("Login failed after signing up again"),
+    );
+    let new_user_id = get_user_id(&app, &user.email).await;
+    assert_ne!(new_user_id, user_id);
+    let details = app
+        .get_admin_user(ADMIN_TOKEN, &new_user_id)
+        .await
+        .json::<AdminUserDetailsResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUserDetailsResponse");
+    assert!(details.roles.is_empty());
+    assert_eq!(details.active_sessions, 1);
+    let authorize_response = app
+        .get_authorize(&authorize_params(&client, "profile"))
+        .await;
+    assert_status(&authorize_response, 200, Some("Consent carried over"));
+}
+
+#[db_test]
+async fn should_forget_failed_logins_of_deleted_account() {
+    // Arrange
+    let policy = LoginThrottlePolicy {
+        backoff_after: 3,
+        lockout_after: 3,
+        base_backoff_seconds: 1,
+        lockout_seconds: 60,
+    };
+    let mut app = TestApp::new_with_login_throttle(LoginThrottleConfig {
+        email: policy,
+        ip: LoginThrottlePolicy {
+            backoff_after: 100,
+            lockout_after: 100,
+            ..policy
+        },
+    })
+    .await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    for _ in 0..3 {
+        let response = app
+            .post_login(&serde_json::json!({
+                "email": user.email,
+                "password": "wrong-password",
+            }))
+            .await;
+        assert_status(&response, 401, Some("Wrong password accepted"));
+    }
+
+    // Act
+    let response = app
+        .post_account_delete(&serde_json::json!({ "currentPassword": user.password }))
+        .await;
+
+    // Assert
+    assert_status(&response, 204, None);
+    setup_registered_user(&app, &user).await;
+    let login_response = app.post_login(&user.login_payload()).await;
+    assert_status(
+        &login_response,
+        200,
+        Some("Lockout carried over to the new account"),
+    );
+}
+
+#[db_test]
+async fn should_keep_account_if_deletion_password_is_wrong() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_user, _token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .post_account_delete(&serde_json::json!({ "currentPassword": "wrong-password" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+    assert_error_message(response, "Incorrect credentials").await;
+    let sessions_response = app.get_sessions().await;
+    assert_status(&sessions_response, 200, Some("Session ended anyway"));
+}
@@ -0,0 +1,271 @@
+use auth_service::{AdminUserDetailsResponse, AdminUsersResponse, ADMIN_ROLE};
+use db_test_macro::db_test;
+use uuid::Uuid;
+
+use crate::helpers_arrange::{
+    get_user_id, setup_logged_in_user, setup_registered_user, setup_user_roles, TestUser,
+};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::{TestApp, ADMIN_TOKEN};
+
+async fn admin_users(response: reqwest::Response) -> AdminUsersResponse {
+    response
+        .json::<AdminUsersResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUsersResponse")
+}
+
+fn emails(users: &AdminUsersResponse) -> Vec<&str> {
+    users.users.iter().map(|user| user.email.as_str()).collect()
+}
+
+#[db_test]
+async fn should_list_users_page_by_page() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    for email in ["carol@example.com", "alice@example.com", "bob@example.com"] {
+        setup_registered_user(&app, &TestUser::with_email(email)).await;
+    }
+
+    // Act
+    let first_response = app.get_admin_users(ADMIN_TOKEN, &[("limit", "2")]).await;
+    assert_status(&first_response, 200, None);
+    let first_page = admin_users(first_response).await;
+    let cursor = first_page.next_cursor.clone().expect("No next cursor");
+    let second_response = app
+        .get_admin_users(ADMIN_TOKEN, &[("limit", "2"), ("cursor", &cursor)])
+        .await;
+
+    // Assert
+    assert_status(&second_response, 200, None);
+    let second_page = admin_users(second_response).await;
+    assert_eq!(
+        emails(&first_page),
+        vec!["alice@example.com", "bob@example.com"]
+    );
+    assert_eq!(emails(&second_page), vec!["carol@example.com"]);
+    assert_eq!(second_page.next_cursor, None);
+    assert_eq!(first_page.total, 3);
+}
+
+#[db_test]
+async fn should_search_users_by_email() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    for email in ["alice@example.com", "bob@other.org", "alice_b@other.org"] {
+        setup_registered_user(&app, &TestUser::with_email(email)).await;
+    }
+
+    // Act
+    let response = app
+        .get_admin_users(ADMIN_TOKEN, &[("search", "ALICE")])
+        .await;
+    let wildcard_response = app.get_admin_users(ADMIN_TOKEN, &[("search", "e_b")]).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let users = admin_users(response).await;
+    assert_eq!(
+        emails(&users),
+        vec!["alice@example.com", "alice_b@other.org"]
+    );
+    assert_eq!(users.total, 2);
+    // `_` is matched literally, not as a wildcard
+    let wildcard_users = admin_users(wildcard_response).await;
+    assert_eq!(emails(&wildcard_users), vec!["alice_b@other.org"]);
+}
+
+#[db_test]
+async fn should_show_user_details() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    setup_user_roles(&app, &user.email, &[ADMIN_ROLE]).await;
+    let user_id = get_user_id(&app, &user.email).await;
+
+    // Act
+    let response = app.get_admin_user(ADMIN_TOKEN, &user_id).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let details = response
+        .json::<AdminUserDetailsResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUserDetailsResponse");
+    assert_eq!(details.user.id.to_string(), user_id);
+    assert_eq!(details.user.email, user.email);
+    assert!(!details.user.disabled);
+    assert_eq!(details.roles, vec![ADMIN_ROLE]);
+    assert_eq!(details.active_sessions, 1);
+}
+
+#[db_test]
+async fn should_refuse_login_of_disabled_user_until_enabled() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    let user_id = get_user_id(&app, &user.email).await;
+
+    // Act
+    let disable_response = app
+        .post_admin_user_action(ADMIN_TOKEN, &user_id, "disable")
+        .await;
+
+    // Assert
+    assert_status(&disable_response, 204, None);
+    let sessions_response = app.get_sessions().await;
+    assert_status(&sessions_response, 401, Some("Session survived disabling"));
+    let login_response = app.post_login(&user.login_payload()).await;
+    assert_status(&login_response, 403, None);
+    assert_error_message(login_response, "Account disabled").await;
+
+    let enable_response = app
+        .post_admin_user_action(ADMIN_TOKEN, &user_id, "enable")
+        .await;
+    assert_status(&enable_response, 204, None);
+    let login_response = app.post_login(&user.login_payload()).await;
+    assert_status(&login_response, 200, Some("Login failed after enabling"));
+}
+
+#[db_test]
+async fn should_not_reveal_disabled_account_for_wrong_password() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+    let user_id = get_user_id(&app, &user.email).await;
+    let disable_response = app
+        .post_admin_user_action(ADMIN_TOKEN, &user_id, "disable")
+        .await;
+    assert_status(&disable_response, 204, Some("Failed to disable user"));
+
+    // Act
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": user.email,
+            "password": "wrong-password",
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+}
+
+#[db_test]
+async fn should_replace_password_on_forced_reset() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    let user_id = get_user_id(&app, &user.email).await;
+
+    // Act
+    let response = app
+        .post_admin_user_action(ADMIN_TOKEN, &user_id, "password-reset")
+        .await;
+
+    // Assert
+    assert_status(&response, 204, None);
+    let sessions_response = app.get_sessions().await;
+    assert_status(&sessions_response, 401, Some("Session survived reset"));
+    let login_response = app.post_login(&user.login_payload()).await;
+    assert_status(&login_response, 401, Some("Old password still accepted"));
+}
+
+#[db_test]
+async fn should_toggle_requires_2fa() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+    let user_id = get_user_id(&app, &user.email).await;
+
+    // Act
+    let response = app
+        .put_admin_user_2fa(
+            ADMIN_TOKEN,
+            &user_id,
+            &serde_json::json!({ "requires2FA": true }),
+        )
+        .await;
+
+    // Assert
+    assert_status(&response, 204, None);
+    let login_response = app.post_login(&user.login_payload()).await;
+    assert_status(&login_response, 206, Some("Login did not ask for 2FA"));
+}
+
+#[db_test]
+async fn should_delete_user() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    setup_user_roles(&app, &user.email, &[ADMIN_ROLE]).await;
+    let user_id = get_user_id(&app, &user.email).await;
+
+    // Act
+    let response = app.delete_admin_user(ADMIN_TOKEN, &user_id).await;
+
+    // Assert
+    assert_status(&response, 204, None);
+    let get_response = app.get_admin_user(ADMIN_TOKEN, &user_id).await;
+    assert_status(&get_response, 404, Some("User still exists"));
+    let sessions_response = app.get_sessions().await;
+    assert_status(&sessions_response, 401, Some("Session survived deletion"));
+    let signup_response = app.post_signup(&user.signup_payload()).await;
+    assert_status(&signup_response, 201, Some("Email not free again"));
+}
+
+#[db_test]
+async fn should_return_404_for_unknown_user() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user_id = Uuid::new_v4().to_string();
+
+    // Act
+    let responses = [
+        app.get_admin_user(ADMIN_TOKEN, &user_id).await,
+        app.post_admin_user_action(ADMIN_TOKEN, &user_id, "disable")
+            .await,
+        app.post_admin_user_action(ADMIN_TOKEN, &user_id, "enable")
+            .await,
+        app.post_admin_user_action(ADMIN_TOKEN, &user_id, "password-reset")
+            .await,
+        app.put_admin_user_2fa(
+            ADMIN_TOKEN,
+            &user_id,
+            &serde_json::json!({ "requires2FA": true }),
+        )
+        .await,
+        app.delete_admin_user(ADMIN_TOKEN, &user_id).await,
+    ];
+
+    // Assert
+    for response in responses {
+        assert_status(&response, 404, Some(response.url().path()));
+    }
+}
+
+#[db_test]
+async fn should_return_400_for_user_named_by_email() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+
+    // Act
+    let response = app.get_admin_user(ADMIN_TOKEN, &user.email).await;
+
+    // Assert
+    assert_status(&response, 400, None);
+}
+
+#[db_test]
+async fn should_return_403_for_user_management_without_admin_role() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_user, token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app.get_admin_users(&token, &[]).await;
+
+    // Assert
+    assert_status(&response, 403, None);
+    assert_error_message(response, "Missing role").await;
+}
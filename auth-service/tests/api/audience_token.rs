@@ -0,0 +1,62 @@
+use auth_service::AudienceTokenResponse;
+use db_test_macro::db_test;
+use serde_json::json;
+
+use crate::helpers_arrange::setup_logged_in_user;
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+
+#[db_test]
+async fn should_return_200_with_token_for_audience() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .post_audience_token(&json!({ "audience": "app-service" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let token = response
+        .json::<AudienceTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to AudienceTokenResponse")
+        .token;
+    let response = app
+        .post_verify_token(&json!({ "token": token, "audience": "app-service" }))
+        .await;
+    assert_status(&response, 200, Some("Token for the requested audience"));
+}
+
+#[db_test]
+async fn should_return_400_for_unknown_audience() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .post_audience_token(&json!({ "audience": "unknown-app" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "Unknown audience").await;
+}
+
+#[db_test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .post_audience_token(&json!({ "audience": "app-service" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "Missing token").await;
+}
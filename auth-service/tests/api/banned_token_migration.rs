@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use auth_service::{configure_redis, BannedTokenStoreType, RedisBannedTokenStore};
+use data_encoding::BASE64URL_NOPAD;
+use redis::Commands;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+fn legacy_token(payload: &serde_json::Value) -> String {
+    format!(
+        "header.{}.signature",
+        BASE64URL_NOPAD.encode(payload.to_string().as_bytes())
+    )
+}
+
+#[tokio::test]
+async fn should_migrate_legacy_banned_tokens_to_jti_keys() {
+    // Arrange
+    let redis_conn = Arc::new(RwLock::new(configure_redis()));
+    let jti = Uuid::new_v4().to_string();
+    let token = legacy_token(&serde_json::json!({ "sub": "test@example.com", "jti": jti }));
+    let token_without_jti = legacy_token(&serde_json::json!({ "sub": Uuid::new_v4() }));
+    for token in [&token, &token_without_jti] {
+        redis_conn
+            .write()
+            .await
+            .set_ex::<_, _, ()>(format!("banned_token:{}", token), true, 600)
+            .unwrap();
+    }
+    let mut store = RedisBannedTokenStore::new(redis_conn.clone());
+
+    // Act
+    let migrated = store.migrate_legacy_keys().await.unwrap();
+
+    // Assert
+    assert!(migrated >= 2);
+    let mut conn = redis_conn.write().await;
+    for token in [&token, &token_without_jti] {
+        let exists: bool = conn.exists(format!("banned_token:{}", token)).unwrap();
+        assert!(!exists, "Legacy entry was not removed");
+    }
+    let ttl: i64 = conn.ttl(format!("banned_jti:{}", jti)).unwrap();
+    assert!(ttl > 0 && ttl <= 600);
+    drop(conn);
+
+    let store: BannedTokenStoreType = Arc::new(RwLock::new(store));
+    assert!(store.read().await.check_banned_token(&jti).await.unwrap());
+}
@@ -0,0 +1,138 @@
+use auth_service::{SessionTokensResponse, SessionsResponse};
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::{
+    create_2fa_payload, setup_2fa_login_started, setup_registered_user, TestUser,
+};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+
+fn token_login_payload(user: &TestUser) -> serde_json::Value {
+    let mut payload = user.login_payload();
+    payload["responseMode"] = serde_json::json!("token");
+    payload
+}
+
+async fn session_tokens(response: reqwest::Response) -> SessionTokensResponse {
+    response
+        .json::<SessionTokensResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionTokensResponse")
+}
+
+// Log in a registered user asking for the tokens in the response body
+async fn setup_token_mode_login(app: &TestApp) -> SessionTokensResponse {
+    let user = setup_registered_user(app, &TestUser::new()).await;
+    let response = app.post_login(&token_login_payload(&user)).await;
+    assert_status(&response, 200, Some("Login failed"));
+
+    session_tokens(response).await
+}
+
+#[db_test]
+async fn should_return_tokens_in_body_without_cookies_in_token_mode() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+
+    // Act
+    let response = app.post_login(&token_login_payload(&user)).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    assert_eq!(response.cookies().count(), 0);
+    let tokens = session_tokens(response).await;
+    assert!(!tokens.access_token.is_empty());
+    assert!(!tokens.refresh_token.is_empty());
+    assert!(tokens.expires_in > 0);
+}
+
+#[db_test]
+async fn should_return_tokens_in_body_after_2fa_in_token_mode() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, two_fa_data) = setup_2fa_login_started(&app).await;
+    let mut payload = create_2fa_payload(&user.email, &two_fa_data);
+    payload["responseMode"] = serde_json::json!("token");
+
+    // Act
+    let response = app.post_verify_2fa(&payload).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    assert_eq!(response.cookies().count(), 0);
+    let tokens = session_tokens(response).await;
+    assert!(!tokens.access_token.is_empty());
+}
+
+#[db_test]
+async fn should_list_sessions_with_bearer_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let tokens = setup_token_mode_login(&app).await;
+
+    // Act
+    let response = app.get_sessions_with_bearer(&tokens.access_token).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let sessions = response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions;
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0].current);
+}
+
+#[db_test]
+async fn should_return_401_if_invalid_bearer_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app.get_sessions_with_bearer("invalid").await;
+
+    // Assert
+    assert_status(&response, 401, None);
+    assert_error_message(response, "Invalid token").await;
+}
+
+#[db_test]
+async fn should_rotate_refresh_token_from_body_in_token_mode() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let tokens = setup_token_mode_login(&app).await;
+
+    // Act
+    let response = app
+        .post_refresh_token_with_body(&serde_json::json!({ "refreshToken": tokens.refresh_token }))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    assert_eq!(response.cookies().count(), 0);
+    let new_tokens = session_tokens(response).await;
+    assert_ne!(new_tokens.refresh_token, tokens.refresh_token);
+    let sessions_response = app.get_sessions_with_bearer(&new_tokens.access_token).await;
+    assert_status(&sessions_response, 200, Some("New access token rejected"));
+}
+
+#[db_test]
+async fn should_end_session_on_logout_with_bearer_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let tokens = setup_token_mode_login(&app).await;
+
+    // Act
+    let response = app.post_logout_with_bearer(&tokens.access_token).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let sessions_response = app.get_sessions_with_bearer(&tokens.access_token).await;
+    assert_status(&sessions_response, 401, Some("Access token still accepted"));
+    let refresh_response = app
+        .post_refresh_token_with_body(&serde_json::json!({ "refreshToken": tokens.refresh_token }))
+        .await;
+    assert_status(&refresh_response, 401, Some("Refresh token still accepted"));
+}
@@ -0,0 +1,74 @@
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::{add_token_to_cookie_jar, get_user_id, setup_logged_in_user};
+use crate::helpers_assert::assert_status;
+use crate::helpers_harness::TestApp;
+
+fn header<'a>(response: &'a reqwest::Response, name: &str) -> Option<&'a str> {
+    response
+        .headers()
+        .get(name)
+        .map(|value| value.to_str().expect("Header is not valid UTF-8"))
+}
+
+#[db_test]
+async fn should_return_user_headers_for_auth_cookie() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app.get_forward_auth(None).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let user_id = get_user_id(&app, &user.email).await;
+    assert_eq!(header(&response, "X-Auth-User"), Some(user_id.as_str()));
+    assert_eq!(header(&response, "X-Auth-Email"), Some(user.email.as_str()));
+    assert_eq!(header(&response, "X-Auth-Roles"), Some(""));
+}
+
+#[db_test]
+async fn should_prefer_bearer_token_over_cookie() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, token) = setup_logged_in_user(&app).await;
+    add_token_to_cookie_jar(&app, "invalid");
+
+    // Act
+    let response = app.get_forward_auth(Some(&token)).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    assert_eq!(header(&response, "X-Auth-Email"), Some(user.email.as_str()));
+}
+
+#[db_test]
+async fn should_return_401_without_valid_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let missing_response = app.get_forward_auth(None).await;
+    let invalid_response = app.get_forward_auth(Some("invalid")).await;
+
+    // Assert
+    assert_status(&missing_response, 401, Some("Missing token"));
+    assert_status(&invalid_response, 401, Some("Invalid token"));
+    assert!(header(&invalid_response, "X-Auth-User").is_none());
+}
+
+#[db_test]
+async fn should_return_401_after_logout() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_user, token) = setup_logged_in_user(&app).await;
+    let logout_response = app.post_logout().await;
+    assert_status(&logout_response, 200, Some("Logout failed"));
+
+    // Act
+    let response = app.get_forward_auth(Some(&token)).await;
+
+    // Assert
+    assert_status(&response, 401, None);
+}
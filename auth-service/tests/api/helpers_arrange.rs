@@ -1,8 +1,14 @@
-use auth_service::{Email, TwoFactorAuthResponse, JWT_COOKIE_NAME};
+use auth_service::{
+    AdminUsersResponse, Email, EmailVerificationToken, EnrollTotpResponse, PasswordResetToken,
+    RegisterOAuthClientResponse, TotpSecret, TwoFactorAuthResponse, JWT_COOKIE_NAME,
+    REFRESH_COOKIE_NAME,
+};
+use chrono::Utc;
 use reqwest::Url;
 use secrecy::{ExposeSecret, Secret};
+use uuid::Uuid;
 
-use crate::helpers_harness::{get_random_email, TestApp};
+use crate::helpers_harness::{get_random_email, TestApp, ADMIN_TOKEN};
 
 /// Represents a test user's credentials
 #[derive(Clone)]
@@ -73,6 +79,12 @@ impl TestUser {
     }
 }
 
+impl Default for TestUser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Struct to hold 2FA verification data
 #[derive(Clone)]
 pub struct TwoFAData {
@@ -155,6 +167,46 @@ pub async fn setup_2fa_login_started(app: &TestApp) -> (TestUser, TwoFAData) {
     (registered_user, two_fa_data)
 }
 
+/// Setup a logged in user who enrolled and confirmed an authenticator app
+/// (Use this in the arrange phase only, not act)
+pub async fn setup_totp_enabled_user(app: &TestApp) -> (TestUser, TotpSecret) {
+    let (user, _) = setup_logged_in_user(app).await;
+
+    let enroll_response = app.post_totp_enroll().await;
+    assert_eq!(
+        enroll_response.status().as_u16(),
+        200,
+        "TOTP enrollment failed"
+    );
+
+    let enrollment = enroll_response
+        .json::<EnrollTotpResponse>()
+        .await
+        .expect("Could not deserialize response body to EnrollTotpResponse");
+    let secret = TotpSecret::parse(Secret::new(enrollment.secret)).expect("Invalid TOTP secret");
+
+    let confirm_response = app
+        .post_totp_confirm(&serde_json::json!({ "code": current_totp_code(&secret) }))
+        .await;
+    assert_eq!(
+        confirm_response.status().as_u16(),
+        200,
+        "TOTP confirmation failed"
+    );
+
+    (user, secret)
+}
+
+/// Generate the code an authenticator app would show right now
+pub fn current_totp_code(secret: &TotpSecret) -> String {
+    secret
+        .generate_code(Utc::now().timestamp())
+        .expect("Failed to generate TOTP code")
+        .as_ref()
+        .expose_secret()
+        .to_owned()
+}
+
 /// Get the 2FA code tuple for a user
 /// (Use this in the arrange phase only, not act)
 pub async fn get_2fa_code_tuple(app: &TestApp, email: Secret<String>) -> (String, String) {
@@ -172,6 +224,61 @@ pub async fn get_2fa_code_tuple(app: &TestApp, email: Secret<String>) -> (String
     )
 }
 
+/// Issue a password reset token for a user, as if the reset link had been emailed
+/// (Use this in the arrange phase only, not act)
+pub async fn setup_password_reset_token(app: &TestApp, email: &str) -> String {
+    let token = PasswordResetToken::default();
+
+    app.password_reset_token_store
+        .write()
+        .await
+        .add_token(
+            token.clone(),
+            Email::parse(Secret::new(email.to_owned())).unwrap(),
+        )
+        .await
+        .expect("Failed to add password reset token");
+
+    token.as_ref().expose_secret().to_owned()
+}
+
+/// Issue an email verification token for a user, as if the verification link had been emailed
+/// (Use this in the arrange phase only, not act)
+pub async fn setup_email_verification_token(app: &TestApp, email: &str) -> String {
+    let token = EmailVerificationToken::default();
+
+    app.email_verification_token_store
+        .write()
+        .await
+        .add_token(
+            token.clone(),
+            Email::parse(Secret::new(email.to_owned())).unwrap(),
+        )
+        .await
+        .expect("Failed to add email verification token");
+
+    token.as_ref().expose_secret().to_owned()
+}
+
+/// Issue an email change token for a user, as if the confirmation link had been emailed
+/// to the new address (Use this in the arrange phase only, not act)
+pub async fn setup_email_change_token(app: &TestApp, user_id: &str, new_email: &str) -> String {
+    let token = EmailVerificationToken::default();
+
+    app.email_verification_token_store
+        .write()
+        .await
+        .add_email_change_token(
+            token.clone(),
+            Uuid::parse_str(user_id).unwrap(),
+            Email::parse(Secret::new(new_email.to_owned())).unwrap(),
+        )
+        .await
+        .expect("Failed to add email change token");
+
+    token.as_ref().expose_secret().to_owned()
+}
+
 /// Create 2FA verification JSON payload
 pub fn create_2fa_payload(email: &str, data: &TwoFAData) -> serde_json::Value {
     serde_json::json!({
@@ -191,3 +298,170 @@ pub fn add_token_to_cookie_jar(app: &TestApp, token: &str) {
         &Url::parse("http://127.0.0.1").expect("Failed to parse URL"),
     );
 }
+
+/// Add an arbitrary refresh token to the cookie jar
+pub fn add_refresh_token_to_cookie_jar(app: &TestApp, token: &str) {
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}={}; HttpOnly; SameSite=Lax; Secure; Path=/",
+            REFRESH_COOKIE_NAME, token
+        ),
+        &Url::parse("http://127.0.0.1").expect("Failed to parse URL"),
+    );
+}
+
+/// Give a registered user roles through the admin API. They show up in the next auth token.
+/// (Use this in the arrange phase only, not act)
+pub async fn setup_user_roles(app: &TestApp, email: &str, roles: &[&str]) {
+    let response = app
+        .put_admin_user_roles(
+            ADMIN_TOKEN,
+            &serde_json::json!({ "email": email, "roles": roles }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 204, "Failed to set user roles");
+}
+
+/// Look up the id tokens and admin routes name a registered user by, through the admin API
+pub async fn get_user_id(app: &TestApp, email: &str) -> String {
+    let response = app.get_admin_users(ADMIN_TOKEN, &[("search", email)]).await;
+    assert_eq!(response.status().as_u16(), 200, "Failed to look up user");
+
+    response
+        .json::<AdminUsersResponse>()
+        .await
+        .expect("Could not deserialize response body to AdminUsersResponse")
+        .users
+        .into_iter()
+        .find(|user| user.email == email)
+        .expect("User not found")
+        .id
+        .to_string()
+}
+
+/// An OAuth client registered through the admin API
+pub struct TestOAuthClient {
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_uri: String,
+}
+
+/// Register an OAuth client, with a secret if it is confidential
+/// (Use this in the arrange phase only, not act)
+pub async fn setup_oauth_client(app: &TestApp, confidential: bool) -> TestOAuthClient {
+    let redirect_uri = "https://client.example.com/callback".to_owned();
+    let response = app
+        .post_admin_oauth_client(
+            ADMIN_TOKEN,
+            &serde_json::json!({
+                "name": "Example client",
+                "redirectUris": [redirect_uri],
+                "confidential": confidential
+            }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 201, "Failed to register client");
+
+    let body = response
+        .json::<RegisterOAuthClientResponse>()
+        .await
+        .expect("Could not deserialize response body to RegisterOAuthClientResponse");
+
+    TestOAuthClient {
+        client_id: body.client_id,
+        client_secret: body.client_secret,
+        redirect_uri,
+    }
+}
+
+/// Example PKCE verifier and S256 challenge from RFC 7636 appendix B
+pub const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+pub const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+/// Parameters of an authorization request of the client
+pub fn authorize_params(client: &TestOAuthClient, scope: &str) -> Vec<(&'static str, String)> {
+    vec![
+        ("response_type", "code".to_owned()),
+        ("client_id", client.client_id.clone()),
+        ("redirect_uri", client.redirect_uri.clone()),
+        ("scope", scope.to_owned()),
+        ("state", "af0ifjsldkj".to_owned()),
+        ("code_challenge", CODE_CHALLENGE.to_owned()),
+        ("code_challenge_method", "S256".to_owned()),
+    ]
+}
+
+/// Read a query parameter of the redirect the response asks for
+pub fn redirect_param(response: &reqwest::Response, name: &str) -> Option<String> {
+    let location = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .expect("No Location header found")
+        .to_str()
+        .expect("Location header is not valid UTF-8");
+
+    Url::parse(location)
+        .expect("Location header is not a URL")
+        .query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Authorize the client as the logged in user and return the authorization code
+/// (Use this in the arrange phase only, not act)
+pub async fn setup_authorization_code(app: &TestApp, client: &TestOAuthClient) -> String {
+    let mut form = authorize_params(client, "profile");
+    form.push(("decision", "allow".to_owned()));
+
+    let response = app.post_authorize(&form).await;
+    assert_eq!(response.status().as_u16(), 303, "Authorization failed");
+
+    redirect_param(&response, "code").expect("No code in redirect")
+}
+
+/// Form of a token request exchanging the authorization code
+pub fn authorization_code_form(
+    client: &TestOAuthClient,
+    code: &str,
+) -> Vec<(&'static str, String)> {
+    vec![
+        ("grant_type", "authorization_code".to_owned()),
+        ("code", code.to_owned()),
+        ("redirect_uri", client.redirect_uri.clone()),
+        ("code_verifier", CODE_VERIFIER.to_owned()),
+        ("client_id", client.client_id.clone()),
+    ]
+}
+
+/// Read the jti claim of a token issued by the app, without verifying it
+pub fn token_jti(token: &str) -> String {
+    token_claim(token, "jti")
+}
+
+/// Read the session id claim of a token issued by the app, without verifying it
+pub fn token_sid(token: &str) -> String {
+    token_claim(token, "sid")
+}
+
+/// Read a claim of a token issued by the app, without verifying it
+pub fn token_claim(token: &str, name: &str) -> String {
+    token_claims(token)[name]
+        .as_str()
+        .unwrap_or_else(|| panic!("Token has no {}", name))
+        .to_owned()
+}
+
+/// Read all claims of a token issued by the app, without verifying them
+pub fn token_claims(token: &str) -> serde_json::Value {
+    let mut validation = jsonwebtoken::Validation::default();
+    validation.insecure_disable_signature_validation();
+    validation.validate_aud = false;
+
+    jsonwebtoken::decode::<serde_json::Value>(
+        token,
+        &jsonwebtoken::DecodingKey::from_secret(&[]),
+        &validation,
+    )
+    .expect("Failed to decode token")
+    .claims
+}
@@ -1,4 +1,4 @@
-use auth_service::{ErrorResponse, JWT_COOKIE_NAME};
+use auth_service::{ErrorResponse, JWT_COOKIE_NAME, REFRESH_COOKIE_NAME};
 
 /// Extract token from auth cookie in response
 pub fn extract_token(response: &reqwest::Response) -> String {
@@ -6,22 +6,29 @@ pub fn extract_token(response: &reqwest::Response) -> String {
         .cookies()
         .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
         .expect("No auth cookie found");
-    
+
     auth_cookie.value().to_string()
 }
 
+/// Extract refresh token from refresh cookie in response
+pub fn extract_refresh_token(response: &reqwest::Response) -> String {
+    let refresh_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == REFRESH_COOKIE_NAME)
+        .expect("No refresh cookie found");
+
+    refresh_cookie.value().to_string()
+}
+
 /// Assert HTTP status code with optional context
 pub fn assert_status(response: &reqwest::Response, expected: u16, context: Option<&str>) {
     let status = response.status().as_u16();
     let context_msg = context.unwrap_or("");
-    
+
     assert_eq!(
-        status,
-        expected,
+        status, expected,
         "Expected status {}, got {} {}",
-        expected,
-        status,
-        context_msg
+        expected, status, context_msg
     );
 }
 
@@ -30,9 +37,12 @@ pub fn assert_has_auth_cookie(response: &reqwest::Response) {
     let auth_cookie = response
         .cookies()
         .find(|cookie| cookie.name() == JWT_COOKIE_NAME);
-    
+
     assert!(auth_cookie.is_some(), "No auth cookie found");
-    assert!(!auth_cookie.unwrap().value().is_empty(), "Auth cookie value is empty");
+    assert!(
+        !auth_cookie.unwrap().value().is_empty(),
+        "Auth cookie value is empty"
+    );
 }
 
 /// Assert response contains the expected error message
@@ -41,7 +51,7 @@ pub async fn assert_error_message(response: reqwest::Response, expected_message:
         .json::<ErrorResponse>()
         .await
         .expect("Could not deserialize response body to ErrorResponse");
-    
+
     assert_eq!(
         body.error,
         expected_message.to_owned(),
@@ -49,4 +59,4 @@ pub async fn assert_error_message(response: reqwest::Response, expected_message:
         expected_message,
         body.error
     );
-}
\ No newline at end of file
+}
@@ -1,7 +1,12 @@
 use auth_service::{
-    configure_redis, get_postgres_pool, test, AppState, Application, BannedTokenStoreType,
-    EmailClientType, MockEmailClient, PostgresUserStore, RedisBannedTokenStore,
-    RedisTwoFACodeStore, TwoFACodeStoreType, DATABASE_URL,
+    configure_jwt_key_ring, configure_redis, get_postgres_pool, test, AppState, Application,
+    BannedTokenStoreType, EmailClientType, EmailVerificationTokenStoreType,
+    HashMapLoginAttemptStore, HashMapRateLimitStore, JwtKeyRing, LoginThrottleConfig,
+    MockEmailClient, PasswordResetTokenStoreType, PostgresOAuthClientStore, PostgresSessionStore,
+    PostgresUserStore, RateLimitConfig, RedisAuthorizationCodeStore, RedisBannedTokenStore,
+    RedisEmailVerificationTokenStore, RedisPasswordResetTokenStore, RedisRefreshTokenStore,
+    RedisTwoFACodeStore, RefreshTokenStoreType, SessionStoreType, TwoFACodeStoreType,
+    UnverifiedLoginPolicy, UserStoreType, DATABASE_URL,
 };
 use reqwest::cookie::Jar;
 use secrecy::{ExposeSecret, Secret};
@@ -19,11 +24,37 @@ pub struct TestApp {
     pub http_client: reqwest::Client,
     pub db_name: String,
     pub clean_up_called: bool,
+    pub user_store: UserStoreType,
     pub banned_token_store: BannedTokenStoreType,
+    pub refresh_token_store: RefreshTokenStoreType,
+    pub session_store: SessionStoreType,
     pub two_fa_code_store: TwoFACodeStoreType,
+    pub password_reset_token_store: PasswordResetTokenStoreType,
+    pub email_verification_token_store: EmailVerificationTokenStoreType,
     pub email_client: EmailClientType,
 }
 
+pub const ADMIN_TOKEN: &str = "test-admin-token";
+
+// Settings that differ between tests
+pub struct TestAppConfig {
+    pub unverified_login_policy: UnverifiedLoginPolicy,
+    pub login_throttle: LoginThrottleConfig,
+    pub rate_limits: RateLimitConfig,
+    pub jwt_key_ring: JwtKeyRing,
+}
+
+impl Default for TestAppConfig {
+    fn default() -> Self {
+        Self {
+            unverified_login_policy: UnverifiedLoginPolicy::Allow,
+            login_throttle: LoginThrottleConfig::default(),
+            rate_limits: RateLimitConfig::default(),
+            jwt_key_ring: configure_jwt_key_ring(),
+        }
+    }
+}
+
 pub fn get_random_email() -> String {
     format!("{}@example.com", Uuid::new_v4())
 }
@@ -39,19 +70,87 @@ impl Drop for TestApp {
 
 impl TestApp {
     pub async fn new() -> Self {
+        Self::build(TestAppConfig::default()).await
+    }
+
+    pub async fn new_with_unverified_login_policy(
+        unverified_login_policy: UnverifiedLoginPolicy,
+    ) -> Self {
+        Self::build(TestAppConfig {
+            unverified_login_policy,
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn new_with_login_throttle(login_throttle: LoginThrottleConfig) -> Self {
+        Self::build(TestAppConfig {
+            login_throttle,
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn new_with_rate_limits(rate_limits: RateLimitConfig) -> Self {
+        Self::build(TestAppConfig {
+            rate_limits,
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn new_with_jwt_key_ring(jwt_key_ring: JwtKeyRing) -> Self {
+        Self::build(TestAppConfig {
+            jwt_key_ring,
+            ..Default::default()
+        })
+        .await
+    }
+
+    async fn build(config: TestAppConfig) -> Self {
         let (pg_pool, db_name) = configure_postgresql().await;
         let redis_conn = Arc::new(RwLock::new(configure_redis()));
 
-        let user_store = Arc::from(RwLock::from(PostgresUserStore::new(pg_pool)));
+        let user_store: UserStoreType =
+            Arc::from(RwLock::from(PostgresUserStore::new(pg_pool.clone())));
         let banned_token_store =
             Arc::from(RwLock::from(RedisBannedTokenStore::new(redis_conn.clone())));
+        let refresh_token_store =
+            Arc::new(RwLock::new(RedisRefreshTokenStore::new(redis_conn.clone())));
+        let session_store = Arc::new(RwLock::new(PostgresSessionStore::new(pg_pool.clone())));
+        let oauth_client_store = Arc::new(RwLock::new(PostgresOAuthClientStore::new(pg_pool)));
         let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(redis_conn.clone())));
+        let password_reset_token_store = Arc::new(RwLock::new(RedisPasswordResetTokenStore::new(
+            redis_conn.clone(),
+        )));
+        let email_verification_token_store = Arc::new(RwLock::new(
+            RedisEmailVerificationTokenStore::new(redis_conn.clone()),
+        ));
+        let authorization_code_store = Arc::new(RwLock::new(RedisAuthorizationCodeStore::new(
+            redis_conn.clone(),
+        )));
+        // Kept in memory, as every test app talks from the same address
+        let login_attempt_store = Arc::new(RwLock::new(HashMapLoginAttemptStore::new()));
+        let rate_limit_store = Arc::new(RwLock::new(HashMapRateLimitStore::new()));
         let email_client = Arc::new(RwLock::new(MockEmailClient {}));
         let app_state = AppState {
             user_store: user_store.clone(),
             banned_token_store: banned_token_store.clone(),
+            refresh_token_store: refresh_token_store.clone(),
+            session_store: session_store.clone(),
             two_fa_code_store: two_fa_code_store.clone(),
+            password_reset_token_store: password_reset_token_store.clone(),
+            email_verification_token_store: email_verification_token_store.clone(),
+            oauth_client_store,
+            authorization_code_store,
+            login_attempt_store,
+            rate_limit_store,
             email_client: email_client.clone(),
+            jwt_key_ring: Arc::new(RwLock::new(config.jwt_key_ring)),
+            unverified_login_policy: config.unverified_login_policy,
+            login_throttle: config.login_throttle,
+            rate_limits: Arc::new(config.rate_limits),
+            admin_token: Some(Secret::new(ADMIN_TOKEN.to_owned())),
         };
         let app = Application::build(app_state, test::APP_ADDRESS)
             .await
@@ -67,6 +166,8 @@ impl TestApp {
         let cookie_jar = Arc::new(Jar::default());
         let http_client = reqwest::Client::builder()
             .cookie_provider(cookie_jar.clone())
+            // OAuth redirects point at clients that do not exist, tests inspect them instead
+            .redirect(reqwest::redirect::Policy::none())
             .build()
             .expect("Failed to build HTTP client");
 
@@ -77,8 +178,13 @@ impl TestApp {
             http_client,
             db_name,
             clean_up_called: false,
+            user_store,
             banned_token_store,
+            refresh_token_store,
+            session_store,
             two_fa_code_store,
+            password_reset_token_store,
+            email_verification_token_store,
             email_client,
         }
     }
@@ -92,7 +198,24 @@ impl TestApp {
 
     pub async fn get_root(&self) -> reqwest::Response {
         self.http_client
-            .get(&format!("{}/", &self.address))
+            .get(format!("{}/", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_jwks(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/.well-known/jwks.json", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_rotate_signing_keys(&self, admin_token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/admin/keys/rotate", &self.address))
+            .bearer_auth(admin_token)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -103,7 +226,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/signup", &self.address))
+            .post(format!("{}/signup", &self.address))
             .json(body)
             .send()
             .await
@@ -115,16 +238,392 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_login_from<Body>(&self, body: &Body, client_ip: &str) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/login", &self.address))
+            .header("X-Forwarded-For", client_ip)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_login_with_user_agent<Body>(
+        &self,
+        body: &Body,
+        user_agent: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/login", &self.address))
+            .header("User-Agent", user_agent)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_sessions(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/sessions", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_sessions_with_bearer(&self, access_token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/sessions", &self.address))
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_account_password<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/account/password", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_account_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/account/email", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_account_email_confirm<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/account/email/confirm", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_account_export(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/account/export", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_account_delete<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/account/delete", &self.address))
             .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
+    pub async fn delete_session(&self, session_id: &str) -> reqwest::Response {
+        self.http_client
+            .delete(format!("{}/sessions/{}", &self.address, session_id))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_logout(&self) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/logout", &self.address))
+            .post(format!("{}/logout", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_logout_with_bearer(&self, access_token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/logout", &self.address))
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_logout_all(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/logout-all", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_role<Body>(
+        &self,
+        admin_token: &str,
+        role: &str,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .put(format!("{}/admin/roles/{}", &self.address, role))
+            .bearer_auth(admin_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_user_roles<Body>(
+        &self,
+        admin_token: &str,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .put(format!("{}/admin/users/roles", &self.address))
+            .bearer_auth(admin_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_users(
+        &self,
+        admin_token: &str,
+        query: &[(&str, &str)],
+    ) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/admin/users", &self.address))
+            .bearer_auth(admin_token)
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_user(&self, admin_token: &str, user_id: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/admin/users/{}", &self.address, user_id))
+            .bearer_auth(admin_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Post one of the per-user admin actions, e.g. `disable` or `password-reset`
+    pub async fn post_admin_user_action(
+        &self,
+        admin_token: &str,
+        user_id: &str,
+        action: &str,
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!(
+                "{}/admin/users/{}/{}",
+                &self.address, user_id, action
+            ))
+            .bearer_auth(admin_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn put_admin_user_2fa<Body>(
+        &self,
+        admin_token: &str,
+        user_id: &str,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .put(format!("{}/admin/users/{}/2fa", &self.address, user_id))
+            .bearer_auth(admin_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_admin_user(&self, admin_token: &str, user_id: &str) -> reqwest::Response {
+        self.http_client
+            .delete(format!("{}/admin/users/{}", &self.address, user_id))
+            .bearer_auth(admin_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_oauth_client<Body>(
+        &self,
+        admin_token: &str,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/admin/oauth/clients", &self.address))
+            .bearer_auth(admin_token)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_authorize<Query>(&self, query: &Query) -> reqwest::Response
+    where
+        Query: serde::Serialize + ?Sized,
+    {
+        self.http_client
+            .get(format!("{}/authorize", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_authorize<Form>(&self, form: &Form) -> reqwest::Response
+    where
+        Form: serde::Serialize + ?Sized,
+    {
+        self.http_client
+            .post(format!("{}/authorize", &self.address))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_token<Form>(&self, form: &Form) -> reqwest::Response
+    where
+        Form: serde::Serialize + ?Sized,
+    {
+        self.http_client
+            .post(format!("{}/token", &self.address))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_token_with_basic_auth<Form>(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        form: &Form,
+    ) -> reqwest::Response
+    where
+        Form: serde::Serialize + ?Sized,
+    {
+        self.http_client
+            .post(format!("{}/token", &self.address))
+            .basic_auth(client_id, Some(client_secret))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_introspect<Form>(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        form: &Form,
+    ) -> reqwest::Response
+    where
+        Form: serde::Serialize + ?Sized,
+    {
+        self.http_client
+            .post(format!("{}/introspect", &self.address))
+            .basic_auth(client_id, Some(client_secret))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_revoke<Form>(&self, form: &Form) -> reqwest::Response
+    where
+        Form: serde::Serialize + ?Sized,
+    {
+        self.http_client
+            .post(format!("{}/revoke", &self.address))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_forward_auth(&self, bearer_token: Option<&str>) -> reqwest::Response {
+        let request = self
+            .http_client
+            .get(format!("{}/forward-auth", &self.address));
+        let request = match bearer_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_openid_configuration(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!(
+                "{}/.well-known/openid-configuration",
+                &self.address
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_userinfo(&self, access_token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/userinfo", &self.address))
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_end_session<Query>(&self, query: &Query) -> reqwest::Response
+    where
+        Query: serde::Serialize + ?Sized,
+    {
+        self.http_client
+            .get(format!("{}/end-session", &self.address))
+            .query(query)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -142,6 +641,106 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn post_refresh_token(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/token/refresh", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_refresh_token_with_body<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/token/refresh", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_password_reset_request<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-reset/request", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_password_reset_confirm<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/password-reset/confirm", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_totp_enroll(&self) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/2fa/totp/enroll", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_totp_confirm<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/2fa/totp/confirm", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_regenerate_recovery_codes<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/2fa/recovery-codes", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_verify_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/verify-email", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_audience_token<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/token/audience", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_verify_token<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
@@ -207,7 +806,7 @@ async fn configure_database(db_conn_string: &Secret<String>, db_name: &str) {
 async fn delete_database(db_name: &str) {
     let postgresql_conn_url = DATABASE_URL.to_owned();
 
-    let connection_options = PgConnectOptions::from_str(&postgresql_conn_url.expose_secret())
+    let connection_options = PgConnectOptions::from_str(postgresql_conn_url.expose_secret())
         .expect("Failed to parse PostgreSQL connection string");
 
     let mut connection = PgConnection::connect_with(&connection_options)
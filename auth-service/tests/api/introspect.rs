@@ -0,0 +1,235 @@
+use auth_service::{IntrospectionResponse, TokenResponse};
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::{
+    authorization_code_form, get_user_id, setup_authorization_code, setup_logged_in_user,
+    setup_oauth_client, TestOAuthClient,
+};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+
+// Issue tokens to the client on behalf of the logged in user
+async fn issue_tokens(app: &TestApp, client: &TestOAuthClient) -> TokenResponse {
+    let code = setup_authorization_code(app, client).await;
+    let response = app
+        .post_token(&authorization_code_form(client, &code))
+        .await;
+    assert_status(&response, 200, Some("Code exchange failed"));
+
+    response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+}
+
+// Introspect the token as the confidential resource server client
+async fn introspect(
+    app: &TestApp,
+    resource_server: &TestOAuthClient,
+    token: &str,
+) -> IntrospectionResponse {
+    let response = app
+        .post_introspect(
+            &resource_server.client_id,
+            resource_server
+                .client_secret
+                .as_deref()
+                .expect("Resource server has no secret"),
+            &[("token", token)],
+        )
+        .await;
+    assert_status(&response, 200, Some("Introspection failed"));
+
+    response
+        .json::<IntrospectionResponse>()
+        .await
+        .expect("Could not deserialize response body to IntrospectionResponse")
+}
+
+fn revoke_form<'a>(client: &'a TestOAuthClient, token: &'a str) -> [(&'static str, &'a str); 2] {
+    [("token", token), ("client_id", client.client_id.as_str())]
+}
+
+#[db_test]
+async fn should_introspect_client_access_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let resource_server = setup_oauth_client(&app, true).await;
+    let client = setup_oauth_client(&app, false).await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    let tokens = issue_tokens(&app, &client).await;
+
+    // Act
+    let introspection = introspect(&app, &resource_server, &tokens.access_token).await;
+
+    // Assert
+    assert!(introspection.active);
+    let user_id = get_user_id(&app, &user.email).await;
+    assert_eq!(introspection.sub.as_deref(), Some(user_id.as_str()));
+    assert_eq!(
+        introspection.client_id.as_deref(),
+        Some(client.client_id.as_str())
+    );
+    assert_eq!(introspection.scope.as_deref(), Some("profile"));
+    let (exp, iat) = (introspection.exp.unwrap(), introspection.iat.unwrap());
+    assert!(exp > iat);
+}
+
+#[db_test]
+async fn should_introspect_auth_cookie_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let resource_server = setup_oauth_client(&app, true).await;
+    let (user, token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let introspection = introspect(&app, &resource_server, &token).await;
+
+    // Assert
+    assert!(introspection.active);
+    let user_id = get_user_id(&app, &user.email).await;
+    assert_eq!(introspection.sub.as_deref(), Some(user_id.as_str()));
+    assert!(introspection.client_id.is_none());
+}
+
+#[db_test]
+async fn should_report_inactive_for_tokens_that_are_no_access_tokens() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let resource_server = setup_oauth_client(&app, true).await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let tokens = issue_tokens(&app, &client).await;
+
+    for token in ["not-a-token", tokens.refresh_token.as_str()] {
+        // Act
+        let introspection = introspect(&app, &resource_server, token).await;
+
+        // Assert
+        assert!(!introspection.active, "Active for input: {}", token);
+        assert!(introspection.sub.is_none());
+    }
+}
+
+#[db_test]
+async fn should_return_401_for_introspection_without_client_secret() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let resource_server = setup_oauth_client(&app, true).await;
+    let public_client = setup_oauth_client(&app, false).await;
+    let (_user, token) = setup_logged_in_user(&app).await;
+
+    let cases = [
+        (resource_server.client_id.as_str(), "wrong-secret"),
+        (public_client.client_id.as_str(), ""),
+        ("unknown-client", "secret"),
+    ];
+
+    for (client_id, client_secret) in cases {
+        // Act
+        let response = app
+            .post_introspect(client_id, client_secret, &[("token", token.as_str())])
+            .await;
+
+        // Assert
+        assert_status(&response, 401, Some(client_id));
+        assert_error_message(response, "invalid_client").await;
+    }
+}
+
+#[db_test]
+async fn should_revoke_access_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let resource_server = setup_oauth_client(&app, true).await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let tokens = issue_tokens(&app, &client).await;
+
+    // Act
+    let response = app
+        .post_revoke(&revoke_form(&client, &tokens.access_token))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let introspection = introspect(&app, &resource_server, &tokens.access_token).await;
+    assert!(!introspection.active);
+}
+
+#[db_test]
+async fn should_revoke_refresh_token_and_its_session() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let resource_server = setup_oauth_client(&app, true).await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let tokens = issue_tokens(&app, &client).await;
+
+    // Act
+    let response = app
+        .post_revoke(&revoke_form(&client, &tokens.refresh_token))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let refresh_response = app
+        .post_token(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", tokens.refresh_token.as_str()),
+            ("client_id", client.client_id.as_str()),
+        ])
+        .await;
+    assert_status(&refresh_response, 400, Some("Refresh token was revoked"));
+    let introspection = introspect(&app, &resource_server, &tokens.access_token).await;
+    assert!(!introspection.active);
+}
+
+#[db_test]
+async fn should_ignore_revocation_of_other_clients_tokens() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let resource_server = setup_oauth_client(&app, true).await;
+    let client = setup_oauth_client(&app, false).await;
+    let other_client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let tokens = issue_tokens(&app, &client).await;
+
+    for token in [&tokens.access_token, &tokens.refresh_token] {
+        // Act
+        let response = app.post_revoke(&revoke_form(&other_client, token)).await;
+
+        // Assert
+        assert_status(&response, 200, None);
+    }
+    let introspection = introspect(&app, &resource_server, &tokens.access_token).await;
+    assert!(introspection.active);
+}
+
+#[db_test]
+async fn should_return_200_for_revoking_unknown_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+
+    // Act
+    let response = app.post_revoke(&revoke_form(&client, "not-a-token")).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+}
+
+#[db_test]
+async fn should_return_401_for_revocation_by_unknown_client() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .post_revoke(&[("token", "not-a-token"), ("client_id", "unknown-client")])
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+    assert_error_message(response, "invalid_client").await;
+}
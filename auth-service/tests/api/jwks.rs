@@ -0,0 +1,28 @@
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::setup_logged_in_user;
+use crate::helpers_assert::assert_status;
+use crate::helpers_harness::TestApp;
+
+#[db_test]
+async fn should_publish_the_key_tokens_are_signed_with() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_, token) = setup_logged_in_user(&app).await;
+    let kid = jsonwebtoken::decode_header(&token).unwrap().kid;
+
+    // Act
+    let response = app.get_jwks().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let jwks = response
+        .json::<jsonwebtoken::jwk::JwkSet>()
+        .await
+        .expect("Could not deserialize response body to JwkSet");
+    // Tokens signed with a shared secret have no kid and nothing is published
+    match kid {
+        Some(kid) => assert!(jwks.find(&kid).is_some()),
+        None => assert!(jwks.keys.is_empty()),
+    }
+}
@@ -1,7 +1,7 @@
 use crate::helpers_arrange::{get_2fa_code_tuple, setup_registered_user, TestUser};
-use crate::helpers_assert::{assert_has_auth_cookie, assert_status};
-use crate::helpers_harness::TestApp;
-use auth_service::TwoFactorAuthResponse;
+use crate::helpers_assert::{assert_error_message, assert_has_auth_cookie, assert_status};
+use crate::helpers_harness::{get_random_email, TestApp};
+use auth_service::{LoginThrottleConfig, LoginThrottlePolicy, TwoFactorAuthResponse};
 use db_test_macro::db_test;
 use rstest::rstest;
 use secrecy::Secret;
@@ -130,3 +130,100 @@ async fn should_return_422_if_malformed_input(#[case] test_case: serde_json::Val
         Some(&format!("Failed for input: {:?}", test_case)),
     );
 }
+
+// Locks a key out for a minute after `lockout_after` failures, without backoff before that
+fn lockout_after(email: u32, ip: u32) -> LoginThrottleConfig {
+    let policy = |lockout_after| LoginThrottlePolicy {
+        backoff_after: lockout_after,
+        lockout_after,
+        base_backoff_seconds: 1,
+        lockout_seconds: 60,
+    };
+    LoginThrottleConfig {
+        email: policy(email),
+        ip: policy(ip),
+    }
+}
+
+fn wrong_password_payload(email: &str) -> serde_json::Value {
+    serde_json::json!({
+        "email": email,
+        "password": "wrongpassword",
+    })
+}
+
+#[db_test]
+async fn should_return_429_with_retry_after_if_email_locked_out() {
+    // Arrange
+    let mut app = TestApp::new_with_login_throttle(lockout_after(3, 100)).await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+    for _ in 0..3 {
+        let response = app.post_login(&wrong_password_payload(&user.email)).await;
+        assert_status(&response, 401, None);
+    }
+
+    // Act
+    let response = app.post_login(&user.login_payload()).await;
+
+    // Assert
+    assert_status(&response, 429, None);
+    let retry_after: u64 = response
+        .headers()
+        .get("Retry-After")
+        .expect("Missing Retry-After header")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 60);
+    assert_error_message(response, "Too many login attempts, please try again later").await;
+}
+
+#[db_test]
+async fn should_return_429_if_ip_locked_out() {
+    // Arrange
+    let mut app = TestApp::new_with_login_throttle(lockout_after(100, 2)).await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+    // Guessing across different accounts still counts against the address
+    for _ in 0..2 {
+        let response = app
+            .post_login_from(&wrong_password_payload(&get_random_email()), "203.0.113.7")
+            .await;
+        assert_status(&response, 401, None);
+    }
+
+    // Act
+    let locked_out_response = app
+        .post_login_from(&user.login_payload(), "203.0.113.7")
+        .await;
+    let other_ip_response = app
+        .post_login_from(&user.login_payload(), "198.51.100.1")
+        .await;
+
+    // Assert
+    assert_status(&locked_out_response, 429, None);
+    assert!(locked_out_response.headers().contains_key("Retry-After"));
+    assert_status(&other_ip_response, 200, None);
+}
+
+#[db_test]
+async fn should_reset_email_failures_after_successful_login() {
+    // Arrange
+    let mut app = TestApp::new_with_login_throttle(lockout_after(3, 100)).await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+    for _ in 0..2 {
+        app.post_login(&wrong_password_payload(&user.email)).await;
+    }
+    let response = app.post_login(&user.login_payload()).await;
+    assert_status(&response, 200, None);
+
+    // Act
+    for _ in 0..2 {
+        let response = app.post_login(&wrong_password_payload(&user.email)).await;
+        assert_status(&response, 401, None);
+    }
+    let response = app.post_login(&user.login_payload()).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+}
@@ -1,8 +1,11 @@
 use db_test_macro::db_test;
+use uuid::Uuid;
 
-use crate::helpers_arrange::{add_token_to_cookie_jar, setup_logged_in_user};
-use crate::helpers_assert::assert_status;
-use crate::helpers_harness::TestApp;
+use crate::helpers_arrange::{
+    add_token_to_cookie_jar, get_user_id, setup_logged_in_user, token_jti,
+};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::{TestApp, ADMIN_TOKEN};
 
 #[db_test]
 async fn should_return_200_if_valid_jwt_cookie() {
@@ -20,7 +23,7 @@ async fn should_return_200_if_valid_jwt_cookie() {
         .banned_token_store
         .read()
         .await
-        .check_banned_token(&token)
+        .check_banned_token(&token_jti(&token))
         .await
         .unwrap());
 }
@@ -64,3 +67,122 @@ async fn should_return_401_if_invalid_token() {
     // Assert
     assert_status(&response, 401, None);
 }
+
+#[db_test]
+async fn should_invalidate_every_session_on_logout_all() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, first_token) = setup_logged_in_user(&app).await;
+    let second_login = app.post_login(&user.login_payload()).await;
+    assert_status(&second_login, 200, Some("Second login failed"));
+    let second_token = second_login
+        .cookies()
+        .find(|cookie| cookie.name() == "jwt")
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+    let third_login = app.post_login(&user.login_payload()).await;
+    assert_status(&third_login, 200, Some("Third login failed"));
+
+    // Act
+    let response = app.post_logout_all().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    for token in [first_token, second_token] {
+        let verify_response = app
+            .post_verify_token(&serde_json::json!({ "token": token }))
+            .await;
+        assert_status(
+            &verify_response,
+            402,
+            Some("Session started before logout-all"),
+        );
+    }
+    let refresh_response = app.post_refresh_token().await;
+    assert_status(&refresh_response, 400, Some("Refresh cookie was removed"));
+}
+
+#[db_test]
+async fn should_return_400_if_logout_all_without_jwt_cookie() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app.post_logout_all().await;
+
+    // Assert
+    assert_status(&response, 400, None);
+}
+
+#[db_test]
+async fn should_invalidate_user_sessions_on_admin_logout_all() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, token) = setup_logged_in_user(&app).await;
+    let user_id = get_user_id(&app, &user.email).await;
+
+    // Act
+    let response = app
+        .post_admin_user_action(ADMIN_TOKEN, &user_id, "logout-all")
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let verify_response = app
+        .post_verify_token(&serde_json::json!({ "token": token }))
+        .await;
+    assert_status(&verify_response, 402, None);
+    let refresh_response = app.post_refresh_token().await;
+    assert_status(&refresh_response, 401, None);
+}
+
+#[db_test]
+async fn should_return_404_if_admin_logout_all_for_unknown_user() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .post_admin_user_action(ADMIN_TOKEN, &Uuid::new_v4().to_string(), "logout-all")
+        .await;
+
+    // Assert
+    assert_status(&response, 404, None);
+    assert_error_message(response, "User not found").await;
+}
+
+#[db_test]
+async fn should_return_401_if_admin_logout_all_with_invalid_admin_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, token) = setup_logged_in_user(&app).await;
+    let user_id = get_user_id(&app, &user.email).await;
+
+    // Act
+    let response = app
+        .post_admin_user_action("invalid", &user_id, "logout-all")
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+    let verify_response = app
+        .post_verify_token(&serde_json::json!({ "token": token }))
+        .await;
+    assert_status(&verify_response, 200, None);
+}
+
+#[db_test]
+async fn should_return_400_if_admin_logout_all_names_user_by_email() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .post_admin_user_action(ADMIN_TOKEN, &user.email, "logout-all")
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+}
@@ -1,11 +1,31 @@
+pub mod account;
+pub mod admin_users;
+pub mod audience_token;
+pub mod banned_token_migration;
+pub mod bearer_auth;
+pub mod forward_auth;
 pub mod helpers_arrange;
 pub mod helpers_assert;
 pub mod helpers_harness;
+pub mod introspect;
+pub mod jwks;
 pub mod login;
 pub mod logout;
+pub mod oauth;
+pub mod oidc;
+pub mod password_reset;
+pub mod rate_limit;
+pub mod recovery_codes;
+pub mod refresh_token;
+pub mod roles;
 pub mod root;
+pub mod sessions;
+pub mod signing_keys;
 pub mod signup;
+pub mod totp;
+pub mod user_store;
 pub mod verify_2fa;
+pub mod verify_email;
 pub mod verify_token;
 
 pub use db_test_macro::db_test;
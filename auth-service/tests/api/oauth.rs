@@ -0,0 +1,474 @@
+use auth_service::{RegisterOAuthClientResponse, SessionsResponse, TokenResponse};
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::{
+    add_refresh_token_to_cookie_jar, authorization_code_form, authorize_params, redirect_param,
+    setup_authorization_code, setup_logged_in_user, setup_oauth_client, token_claim, token_sid,
+    TestOAuthClient,
+};
+use crate::helpers_assert::{assert_error_message, assert_status, extract_refresh_token};
+use crate::helpers_harness::{TestApp, ADMIN_TOKEN};
+
+async fn exchange_code(app: &TestApp, client: &TestOAuthClient, code: &str) -> TokenResponse {
+    let response = app.post_token(&authorization_code_form(client, code)).await;
+    assert_status(&response, 200, Some("Code exchange failed"));
+
+    response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+}
+
+#[db_test]
+async fn should_register_confidential_and_public_clients() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    for (confidential, has_secret) in [(true, true), (false, false)] {
+        // Act
+        let response = app
+            .post_admin_oauth_client(
+                ADMIN_TOKEN,
+                &serde_json::json!({
+                    "name": "Reports",
+                    "redirectUris": ["https://reports.example.com/callback"],
+                    "confidential": confidential
+                }),
+            )
+            .await;
+
+        // Assert
+        assert_status(&response, 201, None);
+        let body = response
+            .json::<RegisterOAuthClientResponse>()
+            .await
+            .expect("Could not deserialize response body to RegisterOAuthClientResponse");
+        assert_eq!(body.client_secret.is_some(), has_secret);
+    }
+}
+
+#[db_test]
+async fn should_return_400_for_invalid_client_registration() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    let cases = [
+        serde_json::json!({
+            "name": "Reports",
+            "redirectUris": ["http://reports.example.com/callback"],
+            "confidential": true
+        }),
+        serde_json::json!({
+            "name": "Reports",
+            "redirectUris": [],
+            "confidential": true
+        }),
+        serde_json::json!({
+            "name": " ",
+            "redirectUris": ["https://reports.example.com/callback"],
+            "confidential": true
+        }),
+    ];
+
+    for case in cases {
+        // Act
+        let response = app.post_admin_oauth_client(ADMIN_TOKEN, &case).await;
+
+        // Assert
+        assert_status(&response, 400, Some(&format!("Failed for input: {}", case)));
+        assert_error_message(response, "Invalid OAuth client").await;
+    }
+}
+
+#[db_test]
+async fn should_return_401_for_client_registration_without_admin_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .post_admin_oauth_client(
+            "wrong-token",
+            &serde_json::json!({
+                "name": "Reports",
+                "redirectUris": ["https://reports.example.com/callback"],
+                "confidential": true
+            }),
+        )
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+}
+
+#[db_test]
+async fn should_show_login_form_when_not_logged_in() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+
+    // Act
+    let response = app
+        .get_authorize(&authorize_params(&client, "profile"))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let html = response.text().await.expect("Failed to read body");
+    assert!(html.contains("id=\"login-form\""));
+    assert!(!html.contains("id=\"consent-form\""));
+}
+
+#[db_test]
+async fn should_ask_logged_in_user_for_consent() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .get_authorize(&authorize_params(&client, "profile"))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let html = response.text().await.expect("Failed to read body");
+    assert!(html.contains("id=\"consent-form\""));
+    assert!(html.contains("Example client"));
+    assert!(html.contains(&client.client_id));
+}
+
+#[db_test]
+async fn should_not_redirect_to_unregistered_redirect_uri() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    let mut params = authorize_params(&client, "profile");
+    params[2].1 = "https://attacker.example.com/callback".to_owned();
+
+    // Act
+    let response = app.get_authorize(&params).await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert!(response.headers().get(reqwest::header::LOCATION).is_none());
+    assert_error_message(response, "invalid_request").await;
+}
+
+#[db_test]
+async fn should_return_401_for_unknown_client() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = TestOAuthClient {
+        client_id: "unknown".to_owned(),
+        client_secret: None,
+        redirect_uri: "https://client.example.com/callback".to_owned(),
+    };
+
+    // Act
+    let response = app
+        .get_authorize(&authorize_params(&client, "profile"))
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+    assert_error_message(response, "invalid_client").await;
+}
+
+#[db_test]
+async fn should_redirect_with_error_for_invalid_requests() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+
+    let cases = [
+        ("response_type", "token", "unsupported_response_type"),
+        ("code_challenge_method", "plain", "invalid_request"),
+        ("code_challenge", "too-short", "invalid_request"),
+        ("scope", "bad\"scope", "invalid_scope"),
+    ];
+
+    for (name, value, expected_error) in cases {
+        let mut params = authorize_params(&client, "profile");
+        for param in params.iter_mut().filter(|(key, _)| *key == name) {
+            param.1 = value.to_owned();
+        }
+
+        // Act
+        let response = app.get_authorize(&params).await;
+
+        // Assert
+        assert_status(&response, 303, Some(&format!("Failed for case: {}", name)));
+        assert_eq!(
+            redirect_param(&response, "error").as_deref(),
+            Some(expected_error)
+        );
+        assert_eq!(
+            redirect_param(&response, "state").as_deref(),
+            Some("af0ifjsldkj")
+        );
+    }
+}
+
+#[db_test]
+async fn should_redirect_with_access_denied_when_user_denies() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let mut form = authorize_params(&client, "profile");
+    form.push(("decision", "deny".to_owned()));
+
+    // Act
+    let response = app.post_authorize(&form).await;
+
+    // Assert
+    assert_status(&response, 303, None);
+    assert_eq!(
+        redirect_param(&response, "error").as_deref(),
+        Some("access_denied")
+    );
+    assert_eq!(redirect_param(&response, "code"), None);
+}
+
+#[db_test]
+async fn should_skip_consent_once_granted() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    setup_authorization_code(&app, &client).await;
+
+    // Act
+    let response = app
+        .get_authorize(&authorize_params(&client, "profile"))
+        .await;
+
+    // Assert
+    assert_status(&response, 303, None);
+    assert!(redirect_param(&response, "code").is_some());
+    assert_eq!(
+        redirect_param(&response, "state").as_deref(),
+        Some("af0ifjsldkj")
+    );
+
+    // Asking for more than was granted needs consent again
+    let response = app
+        .get_authorize(&authorize_params(&client, "profile email"))
+        .await;
+    assert_status(&response, 200, None);
+}
+
+#[db_test]
+async fn should_exchange_code_for_tokens() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let code = setup_authorization_code(&app, &client).await;
+
+    // Act
+    let response = app
+        .post_token(&authorization_code_form(&client, &code))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    assert_eq!(
+        response
+            .headers()
+            .get(reqwest::header::CACHE_CONTROL)
+            .map(|value| value.to_str().unwrap()),
+        Some("no-store")
+    );
+    let body = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+    assert_eq!(body.token_type, "Bearer");
+    assert_eq!(body.scope, "profile");
+    assert_eq!(token_claim(&body.access_token, "aud"), client.client_id);
+    assert_eq!(
+        token_claim(&body.access_token, "client_id"),
+        client.client_id
+    );
+    assert_eq!(token_claim(&body.access_token, "scope"), "profile");
+
+    // The client shows up among the user's sessions
+    let sessions = app
+        .get_sessions()
+        .await
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions;
+    assert!(sessions
+        .iter()
+        .any(|session| session.id == token_sid(&body.access_token)));
+}
+
+#[db_test]
+async fn should_reject_wrong_code_verifier() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let code = setup_authorization_code(&app, &client).await;
+    let mut form = authorization_code_form(&client, &code);
+    form[3].1 = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXx".to_owned();
+
+    // Act
+    let response = app.post_token(&form).await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "invalid_grant").await;
+}
+
+#[db_test]
+async fn should_reject_reused_code() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let code = setup_authorization_code(&app, &client).await;
+    exchange_code(&app, &client, &code).await;
+
+    // Act
+    let response = app
+        .post_token(&authorization_code_form(&client, &code))
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "invalid_grant").await;
+}
+
+#[db_test]
+async fn should_reject_code_after_user_logged_out() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let code = setup_authorization_code(&app, &client).await;
+    assert_status(&app.post_logout().await, 200, Some("Logout failed"));
+
+    // Act
+    let response = app
+        .post_token(&authorization_code_form(&client, &code))
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "invalid_grant").await;
+}
+
+#[db_test]
+async fn should_authenticate_confidential_client() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, true).await;
+    let client_secret = client.client_secret.clone().expect("No client secret");
+    setup_logged_in_user(&app).await;
+    let code = setup_authorization_code(&app, &client).await;
+    let form = authorization_code_form(&client, &code);
+
+    // Act
+    let without_secret = app.post_token(&form).await;
+    let wrong_secret = app
+        .post_token_with_basic_auth(&client.client_id, "wrong", &form)
+        .await;
+    let response = app
+        .post_token_with_basic_auth(&client.client_id, &client_secret, &form)
+        .await;
+
+    // Assert
+    assert_status(&without_secret, 401, None);
+    assert_error_message(without_secret, "invalid_client").await;
+    assert_status(&wrong_secret, 401, None);
+    assert_status(&response, 200, None);
+}
+
+#[db_test]
+async fn should_refresh_client_tokens() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let code = setup_authorization_code(&app, &client).await;
+    let tokens = exchange_code(&app, &client, &code).await;
+    let form = [
+        ("grant_type", "refresh_token"),
+        ("refresh_token", &tokens.refresh_token),
+        ("client_id", &client.client_id),
+    ];
+
+    // Act
+    let response = app.post_token(&form).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let refreshed = response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse");
+    assert_ne!(refreshed.refresh_token, tokens.refresh_token);
+    assert_eq!(refreshed.scope, "profile");
+    assert_eq!(
+        token_sid(&refreshed.access_token),
+        token_sid(&tokens.access_token)
+    );
+
+    // A refresh token can only be used once
+    let replayed = app.post_token(&form).await;
+    assert_status(&replayed, 400, None);
+    assert_error_message(replayed, "invalid_grant").await;
+}
+
+#[db_test]
+async fn should_keep_client_and_cookie_refresh_tokens_apart() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    let login = app.post_login(&user.login_payload()).await;
+    let cookie_refresh_token = extract_refresh_token(&login);
+    let code = setup_authorization_code(&app, &client).await;
+    let tokens = exchange_code(&app, &client, &code).await;
+
+    // Act
+    let response = app
+        .post_token(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &cookie_refresh_token),
+            ("client_id", &client.client_id),
+        ])
+        .await;
+    add_refresh_token_to_cookie_jar(&app, &tokens.refresh_token);
+    let cookie_response = app.post_refresh_token().await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "invalid_grant").await;
+    assert_status(&cookie_response, 401, None);
+}
+
+#[db_test]
+async fn should_return_400_for_unsupported_grant_type() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+
+    // Act
+    let response = app
+        .post_token(&[
+            ("grant_type", "password"),
+            ("client_id", client.client_id.as_str()),
+        ])
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "unsupported_grant_type").await;
+}
@@ -0,0 +1,248 @@
+use auth_service::{OpenIdConfiguration, TokenResponse, UserInfoResponse};
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::{
+    authorization_code_form, authorize_params, create_2fa_payload, get_user_id, redirect_param,
+    setup_2fa_login_started, setup_logged_in_user, setup_oauth_client, token_claims, token_jti,
+    TestOAuthClient,
+};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+
+const NONCE: &str = "n-0S6_WzA2Mj";
+
+// Authorize the client as the logged in user and exchange the code for tokens
+async fn authorize_and_exchange(
+    app: &TestApp,
+    client: &TestOAuthClient,
+    scope: &str,
+) -> TokenResponse {
+    let mut form = authorize_params(client, scope);
+    form.push(("nonce", NONCE.to_owned()));
+    form.push(("decision", "allow".to_owned()));
+    let response = app.post_authorize(&form).await;
+    assert_status(&response, 303, Some("Authorization failed"));
+    let code = redirect_param(&response, "code").expect("No code in redirect");
+
+    let response = app
+        .post_token(&authorization_code_form(client, &code))
+        .await;
+    assert_status(&response, 200, Some("Code exchange failed"));
+    response
+        .json::<TokenResponse>()
+        .await
+        .expect("Could not deserialize response body to TokenResponse")
+}
+
+#[db_test]
+async fn should_publish_openid_configuration() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app.get_openid_configuration().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let configuration = response
+        .json::<OpenIdConfiguration>()
+        .await
+        .expect("Could not deserialize response body to OpenIdConfiguration");
+    assert!(configuration.authorization_endpoint.ends_with("/authorize"));
+    assert!(configuration.userinfo_endpoint.ends_with("/userinfo"));
+    assert!(configuration.jwks_uri.ends_with("/.well-known/jwks.json"));
+    assert!(configuration
+        .scopes_supported
+        .contains(&"openid".to_owned()));
+    assert_eq!(configuration.code_challenge_methods_supported, ["S256"]);
+}
+
+#[db_test]
+async fn should_issue_id_token_for_openid_scope() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let tokens = authorize_and_exchange(&app, &client, "openid").await;
+
+    // Assert
+    let id_token = tokens.id_token.expect("No id_token issued");
+    let claims = token_claims(&id_token);
+    assert_eq!(claims["sub"], get_user_id(&app, &user.email).await);
+    assert_eq!(claims["aud"], client.client_id.as_str());
+    assert_eq!(claims["nonce"], NONCE);
+    assert_eq!(claims["amr"], serde_json::json!(["pwd"]));
+    assert!(claims["auth_time"].as_i64().is_some());
+}
+
+#[db_test]
+async fn should_not_accept_id_token_as_access_token_at_verify_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let tokens = authorize_and_exchange(&app, &client, "openid").await;
+    let id_token = tokens.id_token.expect("No id_token issued");
+
+    // Act
+    let response = app
+        .post_verify_token(&serde_json::json!({
+            "token": id_token,
+            "audience": client.client_id,
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 402, None);
+}
+
+#[db_test]
+async fn should_report_mfa_in_id_token_after_2fa_login() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    let (user, two_fa_data) = setup_2fa_login_started(&app).await;
+    let response = app
+        .post_verify_2fa(&create_2fa_payload(&user.email, &two_fa_data))
+        .await;
+    assert_status(&response, 200, Some("2FA login failed"));
+
+    // Act
+    let tokens = authorize_and_exchange(&app, &client, "openid").await;
+
+    // Assert
+    let claims = token_claims(&tokens.id_token.expect("No id_token issued"));
+    assert_eq!(claims["amr"], serde_json::json!(["pwd", "otp", "mfa"]));
+}
+
+#[db_test]
+async fn should_not_issue_id_token_without_openid_scope() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+
+    // Act
+    let tokens = authorize_and_exchange(&app, &client, "profile").await;
+
+    // Assert
+    assert!(tokens.id_token.is_none());
+}
+
+#[db_test]
+async fn should_return_userinfo_for_granted_scopes() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+
+    for (scope, email) in [("openid", None), ("openid email", Some(&user.email))] {
+        let tokens = authorize_and_exchange(&app, &client, scope).await;
+
+        // Act
+        let response = app.get_userinfo(&tokens.access_token).await;
+
+        // Assert
+        assert_status(&response, 200, Some(scope));
+        let userinfo = response
+            .json::<UserInfoResponse>()
+            .await
+            .expect("Could not deserialize response body to UserInfoResponse");
+        assert_eq!(userinfo.sub, get_user_id(&app, &user.email).await);
+        assert_eq!(userinfo.email.as_ref(), email);
+        assert_eq!(userinfo.email_verified.is_some(), email.is_some());
+    }
+}
+
+#[db_test]
+async fn should_return_403_for_userinfo_without_openid_scope() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    setup_logged_in_user(&app).await;
+    let tokens = authorize_and_exchange(&app, &client, "profile").await;
+
+    // Act
+    let response = app.get_userinfo(&tokens.access_token).await;
+
+    // Assert
+    assert_status(&response, 403, None);
+    assert_error_message(response, "insufficient_scope").await;
+}
+
+#[db_test]
+async fn should_return_401_for_userinfo_with_cookie_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_user, token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app.get_userinfo(&token).await;
+
+    // Assert
+    assert_status(&response, 401, None);
+}
+
+#[db_test]
+async fn should_log_out_and_redirect_to_client_on_end_session() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    let (_user, token) = setup_logged_in_user(&app).await;
+    let tokens = authorize_and_exchange(&app, &client, "openid").await;
+    let id_token = tokens.id_token.expect("No id_token issued");
+
+    // Act
+    let response = app
+        .get_end_session(&[
+            ("id_token_hint", id_token.as_str()),
+            ("post_logout_redirect_uri", client.redirect_uri.as_str()),
+            ("state", "logout-state"),
+        ])
+        .await;
+
+    // Assert
+    assert_status(&response, 303, None);
+    assert_eq!(
+        redirect_param(&response, "state").as_deref(),
+        Some("logout-state")
+    );
+    assert!(app
+        .banned_token_store
+        .read()
+        .await
+        .check_banned_token(&token_jti(&token))
+        .await
+        .unwrap());
+    let logout_response = app.post_logout().await;
+    assert_status(&logout_response, 400, Some("Already logged out"));
+}
+
+#[db_test]
+async fn should_return_400_for_unregistered_post_logout_redirect_uri() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let client = setup_oauth_client(&app, false).await;
+    let (_user, token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .get_end_session(&[
+            ("client_id", client.client_id.as_str()),
+            ("post_logout_redirect_uri", "https://attacker.example.com/"),
+        ])
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "invalid_request").await;
+    assert!(!app
+        .banned_token_store
+        .read()
+        .await
+        .check_banned_token(&token_jti(&token))
+        .await
+        .unwrap());
+}
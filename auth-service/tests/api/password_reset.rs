@@ -0,0 +1,201 @@
+use db_test_macro::db_test;
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::helpers_arrange::{
+    setup_logged_in_user, setup_password_reset_token, setup_registered_user, TestUser,
+};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+
+#[db_test]
+async fn should_return_200_if_reset_requested_for_registered_email() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+
+    // Act
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": user.email }))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+}
+
+#[db_test]
+async fn should_return_200_if_reset_requested_for_unknown_email() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": "unknown@example.com" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+}
+
+#[db_test]
+async fn should_return_400_if_reset_requested_for_invalid_email() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .post_password_reset_request(&serde_json::json!({ "email": "abc" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+}
+
+#[db_test]
+async fn should_return_200_and_change_password_if_valid_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+    let token = setup_password_reset_token(&app, &user.email).await;
+
+    // Act
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token,
+            "newPassword": "new_password123",
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+
+    let old_password_login = app.post_login(&user.login_payload()).await;
+    assert_status(&old_password_login, 401, None);
+
+    let new_password_login = app
+        .post_login(&serde_json::json!({
+            "email": user.email,
+            "password": "new_password123",
+        }))
+        .await;
+    assert_status(&new_password_login, 200, None);
+}
+
+#[db_test]
+async fn should_invalidate_existing_tokens_after_reset() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, token) = setup_logged_in_user(&app).await;
+    let reset_token = setup_password_reset_token(&app, &user.email).await;
+
+    // Act
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": reset_token,
+            "newPassword": "new_password123",
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+
+    let verify_response = app
+        .post_verify_token(&serde_json::json!({ "token": token }))
+        .await;
+    assert_status(&verify_response, 402, None);
+
+    let refresh_response = app.post_refresh_token().await;
+    assert_status(&refresh_response, 401, None);
+}
+
+#[db_test]
+async fn should_return_401_if_token_used_twice() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+    let token = setup_password_reset_token(&app, &user.email).await;
+    let request_body = serde_json::json!({
+        "token": token,
+        "newPassword": "new_password123",
+    });
+
+    // Act
+    let first_response = app.post_password_reset_confirm(&request_body).await;
+    let second_response = app.post_password_reset_confirm(&request_body).await;
+
+    // Assert
+    assert_status(&first_response, 200, None);
+    assert_status(&second_response, 401, None);
+    assert_error_message(second_response, "Invalid token").await;
+}
+
+#[db_test]
+#[rstest]
+#[case::unknown_token(serde_json::json!({
+            "token": Uuid::new_v4().to_string(),
+            "newPassword": "new_password123",
+        }))]
+#[case::malformed_token(serde_json::json!({
+            "token": "not-a-token",
+            "newPassword": "new_password123",
+        }))]
+async fn should_return_401_if_invalid_token(#[case] test_case: serde_json::Value) {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app.post_password_reset_confirm(&test_case).await;
+
+    // Assert
+    assert_status(
+        &response,
+        401,
+        Some(&format!("Failed for input: {:?}", test_case)),
+    );
+}
+
+#[db_test]
+async fn should_return_400_if_new_password_invalid() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+    let token = setup_password_reset_token(&app, &user.email).await;
+
+    // Act
+    let response = app
+        .post_password_reset_confirm(&serde_json::json!({
+            "token": token,
+            "newPassword": "123",
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+}
+
+#[db_test]
+#[rstest]
+#[case::missing_token(serde_json::json!({
+            "newPassword": "new_password123",
+        }))]
+#[case::missing_new_password(serde_json::json!({
+            "token": "123e4567-e89b-12d3-a456-426614174000",
+        }))]
+async fn should_return_422_if_malformed_input(#[case] test_case: serde_json::Value) {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app.post_password_reset_confirm(&test_case).await;
+
+    // Assert
+    assert_status(
+        &response,
+        422,
+        Some(&format!("Failed for input: {:?}", test_case)),
+    );
+}
@@ -0,0 +1,95 @@
+use crate::helpers_arrange::{setup_logged_in_user, TestUser};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+use auth_service::RateLimitConfig;
+use db_test_macro::db_test;
+
+fn header(response: &reqwest::Response, name: &str) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .map(|value| value.to_str().unwrap().to_owned())
+}
+
+#[db_test]
+async fn should_return_429_with_headers_when_limit_exceeded() {
+    // Arrange
+    let mut app =
+        TestApp::new_with_rate_limits(RateLimitConfig::parse("/signup=2/60").unwrap()).await;
+    let first = app.post_signup(&TestUser::new().signup_payload()).await;
+    let second = app.post_signup(&TestUser::new().signup_payload()).await;
+    assert_status(&first, 201, None);
+    assert_status(&second, 201, None);
+    assert_eq!(header(&first, "RateLimit-Limit").as_deref(), Some("2"));
+    assert_eq!(header(&first, "RateLimit-Remaining").as_deref(), Some("1"));
+    assert_eq!(header(&second, "RateLimit-Remaining").as_deref(), Some("0"));
+
+    // Act
+    let response = app.post_signup(&TestUser::new().signup_payload()).await;
+
+    // Assert
+    assert_status(&response, 429, None);
+    assert_eq!(
+        header(&response, "RateLimit-Remaining").as_deref(),
+        Some("0")
+    );
+    // One request is refilled every 30 seconds
+    let retry_after: u64 = header(&response, "Retry-After").unwrap().parse().unwrap();
+    assert!(retry_after > 0 && retry_after <= 30);
+    assert_error_message(response, "Too many requests").await;
+}
+
+#[db_test]
+async fn should_not_limit_routes_without_a_limit() {
+    // Arrange
+    let mut app =
+        TestApp::new_with_rate_limits(RateLimitConfig::parse("/signup=1/60").unwrap()).await;
+
+    // Act
+    let response = app.get_root().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    assert_eq!(header(&response, "RateLimit-Limit"), None);
+}
+
+#[db_test]
+async fn should_limit_each_forwarded_client_ip_separately() {
+    // Arrange
+    let mut app =
+        TestApp::new_with_rate_limits(RateLimitConfig::parse("/login=1/60").unwrap()).await;
+    let payload = TestUser::new().login_payload();
+    app.post_login_from(&payload, "203.0.113.7").await;
+
+    // Act
+    let same_ip_response = app.post_login_from(&payload, "203.0.113.7").await;
+    let other_ip_response = app.post_login_from(&payload, "198.51.100.1").await;
+
+    // Assert
+    assert_status(&same_ip_response, 429, None);
+    assert_status(&other_ip_response, 401, None);
+}
+
+#[db_test]
+async fn should_limit_each_account_separately() {
+    // Arrange
+    let mut app = TestApp::new_with_rate_limits(
+        RateLimitConfig::parse("/2fa/recovery-codes=1/60@account").unwrap(),
+    )
+    .await;
+    // A wrong password still counts, and shows the request got past the limit
+    let payload = serde_json::json!({ "currentPassword": "wrong-password" });
+    setup_logged_in_user(&app).await;
+    let first_user_response = app.post_regenerate_recovery_codes(&payload).await;
+    assert_status(&first_user_response, 401, None);
+    // Logging in replaces the cookie of the first user
+    setup_logged_in_user(&app).await;
+
+    // Act
+    let second_user_response = app.post_regenerate_recovery_codes(&payload).await;
+    let repeated_response = app.post_regenerate_recovery_codes(&payload).await;
+
+    // Assert
+    assert_status(&second_user_response, 401, None);
+    assert_status(&repeated_response, 429, None);
+}
@@ -0,0 +1,187 @@
+use auth_service::{RecoveryCodesResponse, SignupResponse, TwoFactorAuthResponse};
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::{create_2fa_payload, setup_logged_in_user, TestUser, TwoFAData};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+
+/// Sign up a 2FA user, returning the recovery codes from the signup response
+async fn signup_2fa_user(app: &TestApp) -> (TestUser, Vec<String>) {
+    let user = TestUser::new_with_2fa();
+    let response = app.post_signup(&user.signup_payload()).await;
+    assert_status(&response, 201, None);
+
+    let body = response
+        .json::<SignupResponse>()
+        .await
+        .expect("Could not deserialize response body to SignupResponse");
+
+    (
+        user,
+        body.recovery_codes.expect("No recovery codes returned"),
+    )
+}
+
+/// Start a 2FA login and build the verify payload with the given code
+async fn start_login_with_code(app: &TestApp, user: &TestUser, code: &str) -> serde_json::Value {
+    let response = app.post_login(&user.login_payload()).await;
+    assert_status(&response, 206, None);
+
+    let body = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+
+    create_2fa_payload(
+        &user.email,
+        &TwoFAData {
+            login_attempt_id: body.login_attempt_id,
+            two_fa_code: code.to_owned(),
+        },
+    )
+}
+
+#[db_test]
+async fn should_return_recovery_codes_on_signup_with_2fa() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let (_, recovery_codes) = signup_2fa_user(&app).await;
+
+    // Assert
+    assert_eq!(recovery_codes.len(), 10);
+}
+
+#[db_test]
+async fn should_not_return_recovery_codes_on_signup_without_2fa() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+
+    // Act
+    let response = app.post_signup(&user.signup_payload()).await;
+
+    // Assert
+    assert_status(&response, 201, None);
+    let body = response
+        .json::<SignupResponse>()
+        .await
+        .expect("Could not deserialize response body to SignupResponse");
+    assert!(body.recovery_codes.is_none());
+}
+
+#[db_test]
+async fn should_return_200_if_recovery_code_used_instead_of_2fa_code() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, recovery_codes) = signup_2fa_user(&app).await;
+    let payload = start_login_with_code(&app, &user, &recovery_codes[0]).await;
+
+    // Act
+    let response = app.post_verify_2fa(&payload).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+}
+
+#[db_test]
+async fn should_return_401_if_recovery_code_used_twice() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, recovery_codes) = signup_2fa_user(&app).await;
+    let first_payload = start_login_with_code(&app, &user, &recovery_codes[0]).await;
+    let first_response = app.post_verify_2fa(&first_payload).await;
+    assert_status(&first_response, 200, None);
+    let second_payload = start_login_with_code(&app, &user, &recovery_codes[0]).await;
+
+    // Act
+    let second_response = app.post_verify_2fa(&second_payload).await;
+
+    // Assert
+    assert_status(&second_response, 401, None);
+    assert_error_message(second_response, "Incorrect credentials").await;
+}
+
+#[db_test]
+async fn should_return_new_codes_and_invalidate_old_ones_on_regenerate() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, old_codes) = signup_2fa_user(&app).await;
+    let payload = start_login_with_code(&app, &user, &old_codes[0]).await;
+    assert_status(&app.post_verify_2fa(&payload).await, 200, None);
+
+    // Act
+    let response = app
+        .post_regenerate_recovery_codes(&serde_json::json!({ "currentPassword": user.password }))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let body = response
+        .json::<RecoveryCodesResponse>()
+        .await
+        .expect("Could not deserialize response body to RecoveryCodesResponse");
+    assert_eq!(body.recovery_codes.len(), 10);
+
+    let old_code_payload = start_login_with_code(&app, &user, &old_codes[1]).await;
+    let old_code_response = app.post_verify_2fa(&old_code_payload).await;
+    assert_status(&old_code_response, 401, None);
+
+    let new_code_payload = start_login_with_code(&app, &user, &body.recovery_codes[0]).await;
+    let new_code_response = app.post_verify_2fa(&new_code_payload).await;
+    assert_status(&new_code_response, 200, None);
+}
+
+#[db_test]
+async fn should_return_401_on_regenerate_if_password_is_wrong() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, old_codes) = signup_2fa_user(&app).await;
+    let payload = start_login_with_code(&app, &user, &old_codes[0]).await;
+    assert_status(&app.post_verify_2fa(&payload).await, 200, None);
+
+    // Act
+    let response = app
+        .post_regenerate_recovery_codes(&serde_json::json!({ "currentPassword": "wrong-password" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+    assert_error_message(response, "Incorrect credentials").await;
+    // A stolen session alone cannot invalidate the user's codes
+    let old_code_payload = start_login_with_code(&app, &user, &old_codes[1]).await;
+    let old_code_response = app.post_verify_2fa(&old_code_payload).await;
+    assert_status(&old_code_response, 200, None);
+}
+
+#[db_test]
+async fn should_return_409_on_regenerate_without_2fa() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .post_regenerate_recovery_codes(&serde_json::json!({ "currentPassword": user.password }))
+        .await;
+
+    // Assert
+    assert_status(&response, 409, None);
+    assert_error_message(response, "2FA is not enabled").await;
+}
+
+#[db_test]
+async fn should_return_400_on_regenerate_if_jwt_cookie_missing() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .post_regenerate_recovery_codes(&serde_json::json!({ "currentPassword": "password123" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "Missing token").await;
+}
@@ -0,0 +1,110 @@
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::{
+    add_refresh_token_to_cookie_jar, create_2fa_payload, setup_2fa_login_started,
+    setup_registered_user, TestUser,
+};
+use crate::helpers_assert::{assert_has_auth_cookie, assert_status, extract_refresh_token};
+use crate::helpers_harness::TestApp;
+
+#[db_test]
+async fn should_return_200_and_rotate_tokens_if_valid_refresh_cookie() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+    let login_response = app.post_login(&user.login_payload()).await;
+    let old_refresh_token = extract_refresh_token(&login_response);
+
+    // Act
+    let response = app.post_refresh_token().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    assert_has_auth_cookie(&response);
+    let new_refresh_token = extract_refresh_token(&response);
+    assert_ne!(old_refresh_token, new_refresh_token);
+}
+
+#[db_test]
+async fn should_issue_refresh_cookie_after_2fa() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, two_fa_data) = setup_2fa_login_started(&app).await;
+
+    // Act
+    let response = app
+        .post_verify_2fa(&create_2fa_payload(&user.email, &two_fa_data))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    assert!(!extract_refresh_token(&response).is_empty());
+}
+
+#[db_test]
+async fn should_return_400_if_refresh_cookie_missing() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app.post_refresh_token().await;
+
+    // Assert
+    assert_status(&response, 400, None);
+}
+
+#[db_test]
+async fn should_return_401_if_invalid_refresh_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    add_refresh_token_to_cookie_jar(&app, "invalid");
+
+    // Act
+    let response = app.post_refresh_token().await;
+
+    // Assert
+    assert_status(&response, 401, None);
+}
+
+#[db_test]
+async fn should_return_401_and_revoke_family_if_refresh_token_reused() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+    let login_response = app.post_login(&user.login_payload()).await;
+    let first_refresh_token = extract_refresh_token(&login_response);
+    let refresh_response = app.post_refresh_token().await;
+    assert_status(&refresh_response, 200, None);
+    let second_refresh_token = extract_refresh_token(&refresh_response);
+
+    // Act
+    add_refresh_token_to_cookie_jar(&app, &first_refresh_token);
+    let replay_response = app.post_refresh_token().await;
+    add_refresh_token_to_cookie_jar(&app, &second_refresh_token);
+    let rotated_response = app.post_refresh_token().await;
+
+    // Assert
+    assert_status(&replay_response, 401, None);
+    assert_status(&rotated_response, 401, None);
+}
+
+#[db_test]
+async fn should_return_401_if_refresh_token_used_after_logout() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+    let login_response = app.post_login(&user.login_payload()).await;
+    let refresh_token = extract_refresh_token(&login_response);
+    let logout_response = app.post_logout().await;
+    assert_status(&logout_response, 200, None);
+
+    // Act
+    add_refresh_token_to_cookie_jar(&app, &refresh_token);
+    let response = app.post_refresh_token().await;
+
+    // Assert
+    assert_status(&response, 401, None);
+}
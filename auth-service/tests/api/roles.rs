@@ -0,0 +1,201 @@
+use auth_service::{VerifyTokenResponse, ADMIN_ROLE};
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::{
+    get_user_id, setup_logged_in_user, setup_registered_user, setup_user_roles, token_claims,
+    TestUser,
+};
+use crate::helpers_assert::{assert_error_message, assert_status, extract_token};
+use crate::helpers_harness::{TestApp, ADMIN_TOKEN};
+
+// Register a user holding the roles and log them in, returning their auth token
+async fn setup_logged_in_user_with_roles(app: &TestApp, roles: &[&str]) -> (TestUser, String) {
+    let user = setup_registered_user(app, &TestUser::new()).await;
+    setup_user_roles(app, &user.email, roles).await;
+    let response = app.post_login(&user.login_payload()).await;
+    assert_status(&response, 200, Some("Login failed"));
+
+    let token = extract_token(&response);
+    (user, token)
+}
+
+#[db_test]
+async fn should_embed_roles_and_permissions_in_auth_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let role_response = app
+        .put_admin_role(
+            ADMIN_TOKEN,
+            "support",
+            &serde_json::json!({ "permissions": ["users:read", "sessions:revoke"] }),
+        )
+        .await;
+    assert_status(&role_response, 204, Some("Failed to create role"));
+
+    // Act
+    let (_user, token) = setup_logged_in_user_with_roles(&app, &["support", ADMIN_ROLE]).await;
+
+    // Assert
+    let claims = token_claims(&token);
+    assert_eq!(claims["roles"], serde_json::json!(["admin", "support"]));
+    assert_eq!(
+        claims["permissions"],
+        serde_json::json!(["sessions:revoke", "users:read"])
+    );
+}
+
+#[db_test]
+async fn should_pick_up_role_changes_on_refresh() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, token) = setup_logged_in_user(&app).await;
+    assert!(token_claims(&token).get("roles").is_none());
+    setup_user_roles(&app, &user.email, &[ADMIN_ROLE]).await;
+
+    // Act
+    let response = app.post_refresh_token().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let claims = token_claims(&extract_token(&response));
+    assert_eq!(claims["roles"], serde_json::json!(["admin"]));
+}
+
+#[db_test]
+async fn should_let_users_with_admin_role_use_admin_api() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_admin, token) = setup_logged_in_user_with_roles(&app, &[ADMIN_ROLE]).await;
+
+    // Act
+    let response = app
+        .put_admin_role(
+            &token,
+            "auditor",
+            &serde_json::json!({ "permissions": ["audit:read"] }),
+        )
+        .await;
+
+    // Assert
+    assert_status(&response, 204, None);
+}
+
+#[db_test]
+async fn should_return_403_for_admin_api_without_admin_role() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let role_response = app
+        .put_admin_role(
+            ADMIN_TOKEN,
+            "support",
+            &serde_json::json!({ "permissions": [] }),
+        )
+        .await;
+    assert_status(&role_response, 204, Some("Failed to create role"));
+    let (user, token) = setup_logged_in_user_with_roles(&app, &["support"]).await;
+
+    // Act
+    let response = app
+        .put_admin_user_roles(
+            &token,
+            &serde_json::json!({ "email": user.email, "roles": [ADMIN_ROLE] }),
+        )
+        .await;
+
+    // Assert
+    assert_status(&response, 403, None);
+    assert_error_message(response, "Missing role").await;
+}
+
+#[db_test]
+async fn should_return_400_for_missing_admin_credentials() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .http_client
+        .put(format!("{}/admin/roles/support", &app.address))
+        .json(&serde_json::json!({ "permissions": [] }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "Missing token").await;
+}
+
+#[db_test]
+async fn should_return_400_for_unknown_or_invalid_roles() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = setup_registered_user(&app, &TestUser::new()).await;
+    let cases = [
+        serde_json::json!({ "email": user.email, "roles": ["ghost"] }),
+        serde_json::json!({ "email": user.email, "roles": ["Not Valid"] }),
+    ];
+
+    for case in cases {
+        // Act
+        let response = app.put_admin_user_roles(ADMIN_TOKEN, &case).await;
+
+        // Assert
+        assert_status(&response, 400, Some(&case.to_string()));
+        assert_error_message(response, "Invalid role").await;
+    }
+    let response = app
+        .put_admin_role(
+            ADMIN_TOKEN,
+            "Bad%20Role",
+            &serde_json::json!({ "permissions": [] }),
+        )
+        .await;
+    assert_status(&response, 400, Some("Invalid role name"));
+}
+
+#[db_test]
+async fn should_return_404_when_setting_roles_of_unknown_user() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .put_admin_user_roles(
+            ADMIN_TOKEN,
+            &serde_json::json!({ "email": "nobody@example.com", "roles": [ADMIN_ROLE] }),
+        )
+        .await;
+
+    // Assert
+    assert_status(&response, 404, None);
+}
+
+#[db_test]
+async fn should_report_roles_to_relying_apps() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, token) = setup_logged_in_user_with_roles(&app, &[ADMIN_ROLE]).await;
+
+    // Act
+    let forward_auth_response = app.get_forward_auth(Some(&token)).await;
+    let verify_response = app
+        .post_verify_token(&serde_json::json!({ "token": token }))
+        .await;
+
+    // Assert
+    assert_status(&forward_auth_response, 200, None);
+    let roles_header = forward_auth_response
+        .headers()
+        .get("X-Auth-Roles")
+        .and_then(|value| value.to_str().ok());
+    assert_eq!(roles_header, Some("admin"));
+
+    assert_status(&verify_response, 200, None);
+    let verified = verify_response
+        .json::<VerifyTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to VerifyTokenResponse");
+    assert_eq!(verified.sub, get_user_id(&app, &user.email).await);
+    assert_eq!(verified.roles, vec![ADMIN_ROLE]);
+}
@@ -0,0 +1,242 @@
+use auth_service::{SessionsResponse, JWT_COOKIE_NAME};
+use db_test_macro::db_test;
+
+use crate::helpers_arrange::{
+    add_token_to_cookie_jar, create_2fa_payload, setup_2fa_login_started, setup_logged_in_user,
+    token_sid,
+};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+
+fn auth_token(response: &reqwest::Response) -> String {
+    response
+        .cookies()
+        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .expect("No auth cookie found")
+        .value()
+        .to_owned()
+}
+
+#[db_test]
+async fn should_list_sessions_of_current_user() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, first_token) = setup_logged_in_user(&app).await;
+    let second_login = app
+        .post_login_from(&user.login_payload(), "203.0.113.7")
+        .await;
+    assert_status(&second_login, 200, Some("Second login failed"));
+    let second_token = auth_token(&second_login);
+
+    // Act
+    let response = app.get_sessions().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let sessions = response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions;
+    assert_eq!(sessions.len(), 2);
+
+    let current = sessions
+        .iter()
+        .find(|session| session.current)
+        .expect("No current session");
+    assert_eq!(current.id, token_sid(&second_token));
+    assert_eq!(current.ip.as_deref(), Some("203.0.113.7"));
+    assert!(sessions
+        .iter()
+        .any(|session| session.id == token_sid(&first_token) && !session.current));
+}
+
+#[db_test]
+async fn should_record_user_agent() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    let login = app
+        .post_login_with_user_agent(&user.login_payload(), "Mozilla/5.0 (X11; Linux x86_64)")
+        .await;
+    assert_status(&login, 200, Some("Login failed"));
+
+    // Act
+    let response = app.get_sessions().await;
+
+    // Assert
+    let sessions = response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions;
+    let current = sessions
+        .iter()
+        .find(|session| session.current)
+        .expect("No current session");
+    assert_eq!(
+        current.user_agent.as_deref(),
+        Some("Mozilla/5.0 (X11; Linux x86_64)")
+    );
+}
+
+#[db_test]
+async fn should_record_session_on_verify_2fa() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, two_fa_data) = setup_2fa_login_started(&app).await;
+    let verify_response = app
+        .post_verify_2fa(&create_2fa_payload(&user.email, &two_fa_data))
+        .await;
+    assert_status(&verify_response, 200, Some("2FA verification failed"));
+    let token = auth_token(&verify_response);
+
+    // Act
+    let response = app.get_sessions().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let sessions = response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions;
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].id, token_sid(&token));
+    assert!(sessions[0].current);
+}
+
+#[db_test]
+async fn should_revoke_session_and_reject_its_tokens() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, first_token) = setup_logged_in_user(&app).await;
+    let second_login = app.post_login(&user.login_payload()).await;
+    assert_status(&second_login, 200, Some("Second login failed"));
+
+    // Act
+    let response = app.delete_session(&token_sid(&first_token)).await;
+
+    // Assert
+    assert_status(&response, 204, None);
+    let verify_response = app
+        .post_verify_token(&serde_json::json!({ "token": first_token }))
+        .await;
+    assert_status(&verify_response, 402, Some("Revoked session token"));
+
+    let sessions = app
+        .get_sessions()
+        .await
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions;
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0].current);
+}
+
+#[db_test]
+async fn should_reject_refresh_of_revoked_current_session() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_user, token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app.delete_session(&token_sid(&token)).await;
+
+    // Assert
+    assert_status(&response, 204, None);
+    let sessions_response = app.get_sessions().await;
+    assert_status(
+        &sessions_response,
+        401,
+        Some("Auth cookie of revoked session"),
+    );
+    let refresh_response = app.post_refresh_token().await;
+    assert_status(&refresh_response, 401, Some("Refresh of revoked session"));
+}
+
+#[db_test]
+async fn should_return_404_if_session_belongs_to_other_user() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_other_user, other_token) = setup_logged_in_user(&app).await;
+    let (_user, _token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app.delete_session(&token_sid(&other_token)).await;
+
+    // Assert
+    assert_status(&response, 404, None);
+    assert_error_message(response, "Session not found").await;
+    let verify_response = app
+        .post_verify_token(&serde_json::json!({ "token": other_token }))
+        .await;
+    assert_status(
+        &verify_response,
+        200,
+        Some("Other user's session is intact"),
+    );
+}
+
+#[db_test]
+async fn should_return_404_if_session_unknown() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_user, _token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app.delete_session("unknown-session").await;
+
+    // Assert
+    assert_status(&response, 404, None);
+}
+
+#[db_test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let list_response = app.get_sessions().await;
+    let revoke_response = app.delete_session("some-session").await;
+
+    // Assert
+    assert_status(&list_response, 400, None);
+    assert_status(&revoke_response, 400, None);
+}
+
+#[db_test]
+async fn should_return_401_if_invalid_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    add_token_to_cookie_jar(&app, "invalid");
+
+    // Act
+    let response = app.get_sessions().await;
+
+    // Assert
+    assert_status(&response, 401, None);
+}
+
+#[db_test]
+async fn should_not_list_sessions_after_logout() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _token) = setup_logged_in_user(&app).await;
+    let logout_response = app.post_logout().await;
+    assert_status(&logout_response, 200, Some("Logout failed"));
+    let login_response = app.post_login(&user.login_payload()).await;
+    assert_status(&login_response, 200, Some("Login failed"));
+
+    // Act
+    let response = app.get_sessions().await;
+
+    // Assert
+    let sessions = response
+        .json::<SessionsResponse>()
+        .await
+        .expect("Could not deserialize response body to SessionsResponse")
+        .sessions;
+    assert_eq!(sessions.len(), 1);
+}
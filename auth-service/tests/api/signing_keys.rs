@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use auth_service::{JwtKeyRing, RotateSigningKeysResponse};
+use db_test_macro::db_test;
+use jsonwebtoken::{decode_header, jwk::JwkSet, Algorithm};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::helpers_arrange::setup_logged_in_user;
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::{TestApp, ADMIN_TOKEN};
+
+fn temp_key_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("jwt-keys-{}", Uuid::new_v4()))
+}
+
+#[db_test]
+async fn should_keep_existing_tokens_valid_after_rotation() {
+    // Arrange
+    let key_dir = temp_key_dir();
+    let key_ring = JwtKeyRing::open(Algorithm::EdDSA, &key_dir, None).unwrap();
+    let mut app = TestApp::new_with_jwt_key_ring(key_ring).await;
+    let (_, old_token) = setup_logged_in_user(&app).await;
+    let old_kid = decode_header(&old_token).unwrap().kid.unwrap();
+
+    // Act
+    let response = app.post_rotate_signing_keys(ADMIN_TOKEN).await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let new_kid = response
+        .json::<RotateSigningKeysResponse>()
+        .await
+        .expect("Could not deserialize response body to RotateSigningKeysResponse")
+        .kid;
+    assert_ne!(new_kid, old_kid);
+
+    let response = app.post_verify_token(&json!({ "token": old_token })).await;
+    assert_status(&response, 200, Some("Token signed before rotation"));
+
+    let (_, new_token) = setup_logged_in_user(&app).await;
+    assert_eq!(
+        decode_header(&new_token).unwrap().kid,
+        Some(new_kid.clone())
+    );
+
+    let jwks = app.get_jwks().await.json::<JwkSet>().await.unwrap();
+    assert!(jwks.find(&old_kid).is_some());
+    assert!(jwks.find(&new_kid).is_some());
+
+    std::fs::remove_dir_all(key_dir).unwrap();
+}
+
+#[db_test]
+async fn should_return_401_with_incorrect_admin_token() {
+    // Arrange
+    let key_dir = temp_key_dir();
+    let key_ring = JwtKeyRing::open(Algorithm::EdDSA, &key_dir, None).unwrap();
+    let mut app = TestApp::new_with_jwt_key_ring(key_ring).await;
+
+    // Act
+    let response = app.post_rotate_signing_keys("not-the-admin-token").await;
+
+    // Assert
+    assert_status(&response, 401, None);
+    assert_error_message(response, "Invalid token").await;
+
+    std::fs::remove_dir_all(key_dir).unwrap();
+}
+
+#[db_test]
+async fn should_return_409_without_key_directory() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app.post_rotate_signing_keys(ADMIN_TOKEN).await;
+
+    // Assert
+    assert_status(&response, 409, None);
+    assert_error_message(
+        response,
+        "Key rotation needs an RS256 or EdDSA key directory",
+    )
+    .await;
+}
@@ -0,0 +1,160 @@
+use auth_service::{EnrollTotpResponse, TwoFAMethod, TwoFactorAuthResponse, JWT_COOKIE_NAME};
+use db_test_macro::db_test;
+use secrecy::Secret;
+
+use crate::helpers_arrange::{
+    current_totp_code, get_2fa_code_tuple, setup_logged_in_user, setup_totp_enabled_user,
+};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+
+#[db_test]
+async fn should_return_200_and_otpauth_uri_on_enroll() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app.post_totp_enroll().await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    let body = response
+        .json::<EnrollTotpResponse>()
+        .await
+        .expect("Could not deserialize response body to EnrollTotpResponse");
+    assert!(body.otpauth_uri.starts_with("otpauth://totp/"));
+    assert!(body
+        .otpauth_uri
+        .contains(&format!("secret={}", body.secret)));
+    assert!(body.otpauth_uri.contains(&user.email.replace('@', "%40")));
+}
+
+#[db_test]
+async fn should_return_400_on_enroll_if_jwt_cookie_missing() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app.post_totp_enroll().await;
+
+    // Assert
+    assert_status(&response, 400, None);
+    assert_error_message(response, "Missing token").await;
+}
+
+#[db_test]
+async fn should_return_401_on_confirm_if_code_incorrect() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    setup_logged_in_user(&app).await;
+    app.post_totp_enroll().await;
+
+    // Act
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": "000000" }))
+        .await;
+
+    // Assert
+    // "000000" may be the current code by chance, which is too unlikely to matter
+    assert_status(&response, 401, None);
+}
+
+#[db_test]
+async fn should_return_401_on_confirm_without_enrollment() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": "123456" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+}
+
+#[db_test]
+async fn should_return_400_on_confirm_if_code_malformed() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    setup_logged_in_user(&app).await;
+    app.post_totp_enroll().await;
+
+    // Act
+    let response = app
+        .post_totp_confirm(&serde_json::json!({ "code": "12ab" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 400, None);
+}
+
+#[db_test]
+async fn should_require_totp_on_login_after_enrollment() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _) = setup_totp_enabled_user(&app).await;
+
+    // Act
+    let response = app.post_login(&user.login_payload()).await;
+
+    // Assert
+    assert_status(&response, 206, None);
+    let body = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+    assert_eq!(body.two_fa_method, TwoFAMethod::Totp);
+}
+
+#[db_test]
+async fn should_return_200_on_verify_2fa_with_totp_code() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, secret) = setup_totp_enabled_user(&app).await;
+    let login_response = app.post_login(&user.login_payload()).await;
+    let login_body = login_response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+
+    // Act
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": user.email,
+            "loginAttemptId": login_body.login_attempt_id,
+            "2FACode": current_totp_code(&secret),
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == JWT_COOKIE_NAME && !cookie.value().is_empty()));
+}
+
+#[db_test]
+async fn should_return_401_on_verify_2fa_with_stored_code_for_totp_user() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, _) = setup_totp_enabled_user(&app).await;
+    app.post_login(&user.login_payload()).await;
+    let (login_attempt_id, stored_code) =
+        get_2fa_code_tuple(&app, Secret::new(user.email.clone())).await;
+
+    // Act
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": user.email,
+            "loginAttemptId": login_attempt_id,
+            "2FACode": stored_code,
+        }))
+        .await;
+
+    // Assert
+    // The stored code may match the current TOTP code by chance, which is too unlikely to matter
+    assert_status(&response, 401, None);
+}
@@ -0,0 +1,368 @@
+use std::sync::Arc;
+
+use auth_service::{
+    Email, HashMapUserStore, Password, RecoveryCode, Role, User, UserRoles, UserStoreError,
+    UserStoreType, ADMIN_ROLE,
+};
+use db_test_macro::db_test;
+use secrecy::{ExposeSecret, Secret};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::helpers_harness::TestApp;
+
+// The same checks run against every `UserStore` implementation, so the in-memory store
+// used in development behaves like the Postgres store used in production.
+// Every check registers its users under its own domain, which keeps the checks apart
+// when they share a store.
+
+fn email(address: &str) -> Email {
+    Email::parse(Secret::new(address.to_owned())).unwrap()
+}
+
+fn password(password: &str) -> Password {
+    Password::parse(Secret::new(password.to_owned())).unwrap()
+}
+
+fn random_domain() -> String {
+    format!("{}.example.com", Uuid::new_v4().simple())
+}
+
+async fn add_user(store: &UserStoreType, address: &str) -> User {
+    let user = User::new(email(address), password("password123"), false);
+    store
+        .write()
+        .await
+        .add_user(user.clone())
+        .await
+        .expect("Failed to add user");
+    user
+}
+
+async fn check_add_and_get_user(store: &UserStoreType) {
+    let domain = random_domain();
+    let user = add_user(store, &format!("alice@{}", domain)).await;
+
+    let stored = store.read().await.get_user(&user.email).await.unwrap();
+    assert_eq!(stored.id, user.id);
+    assert_eq!(stored.email, user.email);
+    assert!(!stored.requires_2fa);
+    assert!(!stored.verified);
+    assert!(!stored.disabled);
+
+    let duplicate = store.write().await.add_user(user).await;
+    assert_eq!(duplicate, Err(UserStoreError::UserAlreadyExists));
+    let unknown = store
+        .read()
+        .await
+        .get_user(&email(&format!("nobody@{}", domain)))
+        .await;
+    assert_eq!(unknown, Err(UserStoreError::UserNotFound));
+}
+
+async fn check_get_user_by_id(store: &UserStoreType) {
+    let domain = random_domain();
+    let user = add_user(store, &format!("alice@{}", domain)).await;
+    add_user(store, &format!("bob@{}", domain)).await;
+    let store = store.read().await;
+
+    let stored = store.get_user_by_id(&user.id).await.unwrap();
+
+    assert_eq!(stored.email, user.email);
+    assert_eq!(
+        store.get_user_by_id(&Uuid::new_v4()).await,
+        Err(UserStoreError::UserNotFound)
+    );
+}
+
+async fn check_validate_user(store: &UserStoreType) {
+    let domain = random_domain();
+    let user = add_user(store, &format!("alice@{}", domain)).await;
+    let store = store.read().await;
+
+    assert_eq!(
+        store
+            .validate_user(&user.email, &password("password123"))
+            .await,
+        Ok(())
+    );
+    assert_eq!(
+        store
+            .validate_user(&user.email, &password("wrong-password"))
+            .await,
+        Err(UserStoreError::InvalidCredentials)
+    );
+    assert_eq!(
+        store
+            .validate_user(
+                &email(&format!("nobody@{}", domain)),
+                &password("password123")
+            )
+            .await,
+        Err(UserStoreError::UserNotFound)
+    );
+}
+
+async fn check_update_password(store: &UserStoreType) {
+    let domain = random_domain();
+    let user = add_user(store, &format!("alice@{}", domain)).await;
+    let mut store = store.write().await;
+
+    let result = store
+        .update_password(&user.email, password("new-password"))
+        .await;
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        store
+            .validate_user(&user.email, &password("new-password"))
+            .await,
+        Ok(())
+    );
+    assert_eq!(
+        store
+            .validate_user(&user.email, &password("password123"))
+            .await,
+        Err(UserStoreError::InvalidCredentials)
+    );
+    assert_eq!(
+        store
+            .update_password(
+                &email(&format!("nobody@{}", domain)),
+                password("new-password")
+            )
+            .await,
+        Err(UserStoreError::UserNotFound)
+    );
+}
+
+async fn check_update_email(store: &UserStoreType) {
+    let domain = random_domain();
+    let user = add_user(store, &format!("alice@{}", domain)).await;
+    let taken = add_user(store, &format!("bob@{}", domain)).await;
+    let new_email = email(&format!("alice.new@{}", domain));
+    let mut store = store.write().await;
+    store.mark_verified(&user.email).await.unwrap();
+    store
+        .set_user_roles(&user.email, vec![Role::admin()])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.update_email(&user.email, &taken.email).await,
+        Err(UserStoreError::UserAlreadyExists)
+    );
+    let result = store.update_email(&user.email, &new_email).await;
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        store.get_user(&user.email).await,
+        Err(UserStoreError::UserNotFound)
+    );
+    let moved = store.get_user(&new_email).await.unwrap();
+    assert_eq!(moved.id, user.id);
+    assert!(!moved.verified);
+    assert_eq!(
+        store
+            .validate_user(&new_email, &password("password123"))
+            .await,
+        Ok(())
+    );
+    assert_eq!(
+        store.get_user_roles(&new_email).await.unwrap().roles,
+        vec![Role::admin()]
+    );
+    // A missing user is reported before a taken address
+    assert_eq!(
+        store.update_email(&user.email, &taken.email).await,
+        Err(UserStoreError::UserNotFound)
+    );
+}
+
+async fn check_set_requires_2fa_and_disabled(store: &UserStoreType) {
+    let domain = random_domain();
+    let user = add_user(store, &format!("alice@{}", domain)).await;
+    let mut store = store.write().await;
+
+    store.set_requires_2fa(&user.email, true).await.unwrap();
+    store.set_disabled(&user.email, true).await.unwrap();
+    let updated = store.get_user(&user.email).await.unwrap();
+    assert!(updated.requires_2fa);
+    assert!(updated.disabled);
+
+    store.set_requires_2fa(&user.email, false).await.unwrap();
+    store.set_disabled(&user.email, false).await.unwrap();
+    let updated = store.get_user(&user.email).await.unwrap();
+    assert!(!updated.requires_2fa);
+    assert!(!updated.disabled);
+
+    let nobody = email(&format!("nobody@{}", domain));
+    assert_eq!(
+        store.set_requires_2fa(&nobody, true).await,
+        Err(UserStoreError::UserNotFound)
+    );
+    assert_eq!(
+        store.set_disabled(&nobody, true).await,
+        Err(UserStoreError::UserNotFound)
+    );
+}
+
+async fn check_delete_user(store: &UserStoreType) {
+    let domain = random_domain();
+    let user = add_user(store, &format!("alice@{}", domain)).await;
+    let code = RecoveryCode::default();
+    {
+        let mut store = store.write().await;
+        store
+            .set_recovery_codes(&user.email, vec![code.clone()])
+            .await
+            .unwrap();
+        store
+            .set_user_roles(&user.email, vec![Role::admin()])
+            .await
+            .unwrap();
+    }
+
+    let result = store.write().await.delete_user(&user.email).await;
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        store.read().await.get_user(&user.email).await,
+        Err(UserStoreError::UserNotFound)
+    );
+    assert_eq!(
+        store.write().await.delete_user(&user.email).await,
+        Err(UserStoreError::UserNotFound)
+    );
+    // Nothing of the deleted user carries over to a new user with the same email
+    let user = add_user(store, &format!("alice@{}", domain)).await;
+    let mut store = store.write().await;
+    assert_eq!(
+        store.get_user_roles(&user.email).await,
+        Ok(UserRoles::default())
+    );
+    assert_eq!(
+        store.use_recovery_code(&user.email, &code).await,
+        Err(UserStoreError::InvalidCredentials)
+    );
+}
+
+async fn check_list_users(store: &UserStoreType) {
+    let domain = random_domain();
+    // Ordered byte by byte, so upper case comes first
+    for name in ["carol", "Zed", "alice", "bob"] {
+        add_user(store, &format!("{}@{}", name, domain)).await;
+    }
+    let store = store.read().await;
+
+    let first_page = store.list_users(None, 2, Some(&domain)).await.unwrap();
+    let second_page = store
+        .list_users(Some(&first_page[1].email), 2, Some(&domain))
+        .await
+        .unwrap();
+    let last_page = store
+        .list_users(Some(&second_page[1].email), 2, Some(&domain))
+        .await
+        .unwrap();
+
+    let names = |users: &[User]| {
+        users
+            .iter()
+            .map(|user| {
+                let address = user.email.as_ref().expose_secret();
+                address.split('@').next().unwrap().to_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&first_page), vec!["Zed", "alice"]);
+    assert_eq!(names(&second_page), vec!["bob", "carol"]);
+    assert!(last_page.is_empty());
+}
+
+async fn check_search_and_count_users(store: &UserStoreType) {
+    let domain = random_domain();
+    for name in ["alice", "ALINA", "bob", "a_b", "axb"] {
+        add_user(store, &format!("{}@{}", name, domain)).await;
+    }
+    let store = store.read().await;
+
+    // The search ignores case
+    assert_eq!(store.count_users(Some(&domain.to_uppercase())).await, Ok(5));
+    let matching = store
+        .list_users(None, 10, Some(&format!("ali%@{}", domain)))
+        .await
+        .unwrap();
+    let underscore = store
+        .list_users(None, 10, Some(&format!("a_b@{}", domain)))
+        .await
+        .unwrap();
+
+    // LIKE wildcards in the search match literally
+    assert!(matching.is_empty());
+    assert_eq!(underscore.len(), 1);
+    assert_eq!(underscore[0].email, email(&format!("a_b@{}", domain)));
+    assert!(store.count_users(None).await.unwrap() >= 5);
+}
+
+async fn check_user_roles(store: &UserStoreType) {
+    let domain = random_domain();
+    let user = add_user(store, &format!("alice@{}", domain)).await;
+    let mut store = store.write().await;
+
+    assert_eq!(
+        store
+            .set_user_roles(&user.email, vec![Role::parse("ghost".to_owned()).unwrap()])
+            .await,
+        Err(UserStoreError::RoleNotFound)
+    );
+    store
+        .set_user_roles(&user.email, vec![Role::admin()])
+        .await
+        .unwrap();
+    let roles = store.get_user_roles(&user.email).await.unwrap();
+    assert_eq!(
+        roles
+            .roles
+            .iter()
+            .map(|role| role.as_ref())
+            .collect::<Vec<_>>(),
+        vec![ADMIN_ROLE]
+    );
+    assert_eq!(
+        store
+            .get_user_roles(&email(&format!("nobody@{}", domain)))
+            .await,
+        Err(UserStoreError::UserNotFound)
+    );
+}
+
+async fn run_user_store_conformance_suite(store: &UserStoreType) {
+    check_add_and_get_user(store).await;
+    check_get_user_by_id(store).await;
+    check_validate_user(store).await;
+    check_update_password(store).await;
+    check_update_email(store).await;
+    check_set_requires_2fa_and_disabled(store).await;
+    check_delete_user(store).await;
+    check_list_users(store).await;
+    check_search_and_count_users(store).await;
+    check_user_roles(store).await;
+}
+
+#[db_test]
+async fn postgres_user_store_conforms_to_user_store_contract() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act & Assert
+    run_user_store_conformance_suite(&app.user_store).await;
+}
+
+#[tokio::test]
+async fn hashmap_user_store_conforms_to_user_store_contract() {
+    // Arrange
+    let store: UserStoreType = Arc::new(RwLock::new(HashMapUserStore::new()));
+
+    // Act & Assert
+    run_user_store_conformance_suite(&store).await;
+}
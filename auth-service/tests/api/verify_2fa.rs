@@ -1,10 +1,12 @@
-use crate::helpers_arrange::{create_2fa_payload, setup_2fa_login_started};
-use crate::helpers_assert::{assert_has_auth_cookie, assert_status};
-use crate::helpers_harness::{get_random_email, TestApp};
-use auth_service::{Email, ErrorResponse, LoginAttemptId, TwoFACode, TwoFactorAuthResponse};
+use crate::helpers_arrange::{
+    create_2fa_payload, get_2fa_code_tuple, setup_2fa_login_started, TwoFAData,
+};
+use crate::helpers_assert::{assert_error_message, assert_has_auth_cookie, assert_status};
+use crate::helpers_harness::TestApp;
+use auth_service::MAX_TWO_FA_ATTEMPTS;
 use db_test_macro::db_test;
 use rstest::rstest;
-use secrecy::{ExposeSecret, Secret};
+use secrecy::Secret;
 
 #[db_test]
 async fn should_return_200_if_correct_code() {
@@ -91,6 +93,30 @@ async fn should_return_401_if_same_code_twice() {
     assert_status(&second_response, 401, None);
 }
 
+#[db_test]
+async fn should_return_401_for_wrong_code_once_attempt_is_consumed() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, two_fa_data) = setup_2fa_login_started(&app).await;
+    let correct_response = app
+        .post_verify_2fa(&create_2fa_payload(&user.email, &two_fa_data))
+        .await;
+    assert_status(&correct_response, 200, Some("Correct code rejected"));
+    let wrong_code = TwoFAData {
+        login_attempt_id: two_fa_data.login_attempt_id,
+        two_fa_code: "000000".to_owned(),
+    };
+
+    // Act
+    let response = app
+        .post_verify_2fa(&create_2fa_payload(&user.email, &wrong_code))
+        .await;
+
+    // Assert
+    assert_status(&response, 401, None);
+    assert_error_message(response, "Incorrect credentials").await;
+}
+
 #[db_test]
 #[rstest]
 #[case::empty_json(serde_json::json!({}))]
@@ -125,3 +151,74 @@ async fn should_return_422_if_malformed_input(#[case] test_case: serde_json::Val
         Some(&format!("Failed for input: {:?}", test_case)),
     );
 }
+
+#[db_test]
+async fn should_return_429_and_invalidate_code_after_too_many_incorrect_codes() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, two_fa_data) = setup_2fa_login_started(&app).await;
+    // Shift every digit so the wrong code never matches the real one
+    let wrong_code: String = two_fa_data
+        .two_fa_code
+        .chars()
+        .map(|c| char::from_digit((c.to_digit(10).unwrap() + 1) % 10, 10).unwrap())
+        .collect();
+    let wrong_payload = create_2fa_payload(
+        &user.email,
+        &TwoFAData {
+            login_attempt_id: two_fa_data.login_attempt_id.clone(),
+            two_fa_code: wrong_code,
+        },
+    );
+    for _ in 1..MAX_TWO_FA_ATTEMPTS {
+        let response = app.post_verify_2fa(&wrong_payload).await;
+        assert_status(&response, 401, None);
+    }
+
+    // Act
+    let response = app.post_verify_2fa(&wrong_payload).await;
+
+    // Assert
+    assert_status(&response, 429, None);
+    assert_error_message(response, "Too many 2FA attempts, please log in again").await;
+
+    // The correct code no longer works, the user has to log in again
+    let correct_code_response = app
+        .post_verify_2fa(&create_2fa_payload(&user.email, &two_fa_data))
+        .await;
+    assert_status(&correct_code_response, 401, None);
+}
+
+#[db_test]
+async fn should_reset_attempts_on_new_login() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (user, two_fa_data) = setup_2fa_login_started(&app).await;
+    let wrong_payload = create_2fa_payload(
+        &user.email,
+        &TwoFAData {
+            login_attempt_id: two_fa_data.login_attempt_id.clone(),
+            two_fa_code: "abcde-fghjk".to_owned(),
+        },
+    );
+    for _ in 1..MAX_TWO_FA_ATTEMPTS {
+        app.post_verify_2fa(&wrong_payload).await;
+    }
+    app.post_login(&user.login_payload()).await;
+    let (login_attempt_id, two_fa_code) =
+        get_2fa_code_tuple(&app, Secret::new(user.email.clone())).await;
+
+    // Act
+    let response = app
+        .post_verify_2fa(&create_2fa_payload(
+            &user.email,
+            &TwoFAData {
+                login_attempt_id,
+                two_fa_code,
+            },
+        ))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+}
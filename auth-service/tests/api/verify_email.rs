@@ -0,0 +1,130 @@
+use auth_service::{TwoFactorAuthResponse, UnverifiedLoginPolicy};
+use db_test_macro::db_test;
+use rstest::rstest;
+use uuid::Uuid;
+
+use crate::helpers_arrange::{setup_email_verification_token, setup_registered_user, TestUser};
+use crate::helpers_assert::{assert_error_message, assert_status};
+use crate::helpers_harness::TestApp;
+
+#[db_test]
+async fn should_return_200_if_valid_token() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+    let token = setup_email_verification_token(&app, &user.email).await;
+
+    // Act
+    let response = app
+        .post_verify_email(&serde_json::json!({ "token": token }))
+        .await;
+
+    // Assert
+    assert_status(&response, 200, None);
+}
+
+#[db_test]
+async fn should_allow_login_after_verification_if_policy_refuses_unverified() {
+    // Arrange
+    let mut app = TestApp::new_with_unverified_login_policy(UnverifiedLoginPolicy::Refuse).await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+    let token = setup_email_verification_token(&app, &user.email).await;
+
+    // Act
+    let verify_response = app
+        .post_verify_email(&serde_json::json!({ "token": token }))
+        .await;
+    let login_response = app.post_login(&user.login_payload()).await;
+
+    // Assert
+    assert_status(&verify_response, 200, None);
+    assert_status(&login_response, 200, None);
+}
+
+#[db_test]
+async fn should_return_403_if_unverified_and_policy_refuses_unverified() {
+    // Arrange
+    let mut app = TestApp::new_with_unverified_login_policy(UnverifiedLoginPolicy::Refuse).await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+
+    // Act
+    let response = app.post_login(&user.login_payload()).await;
+
+    // Assert
+    assert_status(&response, 403, None);
+    assert_error_message(response, "Email not verified").await;
+}
+
+#[db_test]
+async fn should_require_2fa_if_unverified_and_policy_restricts_unverified() {
+    // Arrange
+    let mut app = TestApp::new_with_unverified_login_policy(UnverifiedLoginPolicy::Restrict).await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+
+    // Act
+    let response = app.post_login(&user.login_payload()).await;
+
+    // Assert
+    assert_status(&response, 206, None);
+    let response_body = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+    assert_eq!(response_body.message, "2FA required".to_owned());
+}
+
+#[db_test]
+async fn should_return_401_if_token_used_twice() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let user = TestUser::new();
+    setup_registered_user(&app, &user).await;
+    let token = setup_email_verification_token(&app, &user.email).await;
+    let request_body = serde_json::json!({ "token": token });
+
+    // Act
+    let first_response = app.post_verify_email(&request_body).await;
+    let second_response = app.post_verify_email(&request_body).await;
+
+    // Assert
+    assert_status(&first_response, 200, None);
+    assert_status(&second_response, 401, None);
+    assert_error_message(second_response, "Invalid token").await;
+}
+
+#[db_test]
+#[rstest]
+#[case::unknown_token(serde_json::json!({ "token": Uuid::new_v4().to_string() }))]
+#[case::malformed_token(serde_json::json!({ "token": "not-a-token" }))]
+async fn should_return_401_if_invalid_token(#[case] test_case: serde_json::Value) {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app.post_verify_email(&test_case).await;
+
+    // Assert
+    assert_status(
+        &response,
+        401,
+        Some(&format!("Failed for input: {:?}", test_case)),
+    );
+}
+
+#[db_test]
+async fn should_return_422_if_malformed_input() {
+    // Arrange
+    let mut app = TestApp::new().await;
+
+    // Act
+    let response = app
+        .post_verify_email(&serde_json::json!({ "verificationToken": "abc" }))
+        .await;
+
+    // Assert
+    assert_status(&response, 422, None);
+}
@@ -1,4 +1,4 @@
-use crate::helpers_arrange::setup_logged_in_user;
+use crate::helpers_arrange::{setup_logged_in_user, token_jti};
 use crate::helpers_assert::assert_status;
 use crate::helpers_harness::TestApp;
 use db_test_macro::db_test;
@@ -37,6 +37,24 @@ async fn should_return_401_if_invalid_token() {
     assert_status(&response, 402, None);
 }
 
+#[db_test]
+async fn should_return_402_if_token_is_for_another_audience() {
+    // Arrange
+    let mut app = TestApp::new().await;
+    let (_user, token) = setup_logged_in_user(&app).await;
+
+    // Act
+    let response = app
+        .post_verify_token(&serde_json::json!({
+            "token": token,
+            "audience": "other-app",
+        }))
+        .await;
+
+    // Assert
+    assert_status(&response, 402, None);
+}
+
 #[db_test]
 async fn should_return_401_if_token_is_banned() {
     // Arrange
@@ -59,7 +77,7 @@ async fn should_return_401_if_token_is_banned() {
         .banned_token_store
         .read()
         .await
-        .check_banned_token(&token)
+        .check_banned_token(&token_jti(&token))
         .await
         .unwrap());
     assert_status(&response, 402, None);